@@ -11,3 +11,43 @@ fn universe_ticks(b: &mut test::Bencher) {
         universe.tick();
     });
 }
+
+#[bench]
+fn universe_ticks_generations(b: &mut test::Bencher) {
+    let mut universe = wasm_game_of_life::Universe::new();
+    universe.set_rule("B2/S345/C4").unwrap();
+
+    b.iter(|| {
+        universe.tick();
+    });
+}
+
+#[bench]
+fn universe_ticks_isotropic(b: &mut test::Bencher) {
+    let mut universe = wasm_game_of_life::Universe::new();
+    universe.set_rule("B3/S2-i34q").unwrap();
+
+    b.iter(|| {
+        universe.tick();
+    });
+}
+
+#[bench]
+fn universe_ticks_larger_than_life(b: &mut test::Bencher) {
+    let mut universe = wasm_game_of_life::Universe::new();
+    universe.set_rule("R5,C0,M1,S34..58,B34..45,NM").unwrap();
+
+    b.iter(|| {
+        universe.tick();
+    });
+}
+
+#[bench]
+fn universe_ticks_margolus(b: &mut test::Bencher) {
+    let mut universe = wasm_game_of_life::Universe::new();
+    universe.set_rule("Critters").unwrap();
+
+    b.iter(|| {
+        universe.tick();
+    });
+}
@@ -0,0 +1,100 @@
+use crate::rule::Automaton;
+use crate::{BoundaryMode, NeighborhoodKind, Universe};
+use fixedbitset::FixedBitSet;
+
+/// Everything about the board other than its cells that a tick depends
+/// on. Ticks under different settings can't be compared.
+#[derive(Clone, Debug, PartialEq)]
+struct TickSettings {
+    width: u32,
+    height: u32,
+    rule: Automaton,
+    neighborhood: NeighborhoodKind,
+    boundary: BoundaryMode,
+    background: bool,
+    include_center: bool,
+    frozen_border: u32,
+}
+
+/// What the last cell-by-cell tick did, so the next one can skip cells
+/// that can't change.
+#[derive(Clone, Debug)]
+pub struct LastTick {
+    settings: TickSettings,
+    /// Cells that tick changed.
+    changed: FixedBitSet,
+    /// The live cells it produced.
+    after: FixedBitSet,
+}
+
+impl Universe {
+    fn tick_settings(&self) -> TickSettings {
+        TickSettings {
+            width: self.width,
+            height: self.height,
+            rule: self.rule.clone(),
+            neighborhood: self.neighborhood,
+            boundary: self.boundary,
+            background: self.background,
+            include_center: self.include_center,
+            frozen_border: self.frozen_border,
+        }
+    }
+
+    /// Remember a tick from the current cells to `next` under the current
+    /// settings.
+    pub(crate) fn last_tick(&self, next: &FixedBitSet) -> LastTick {
+        let mut changed = self.cells.clone();
+        changed.symmetric_difference_with(next);
+        LastTick {
+            settings: self.tick_settings(),
+            changed,
+            after: next.clone(),
+        }
+    }
+
+    /// The cells whose next state may differ from their current one under
+    /// a rule that only looks at the cells around each one, or `None` if
+    /// every cell has to be evaluated.
+    ///
+    /// A cell whose surroundings are the same as before the last tick, and
+    /// haven't been edited since, will do what it did then: stay as it is.
+    /// So only cells near one that the last tick changed or that was
+    /// edited afterwards need evaluating. When those are more than a
+    /// quarter of the board a full scan is cheaper and `None` is returned,
+    /// as it is when the last tick ran under other settings or wasn't
+    /// remembered.
+    pub(crate) fn active_cells(&self) -> Option<FixedBitSet> {
+        let last = self.last_tick.as_ref()?;
+        if last.settings != self.tick_settings() {
+            return None;
+        }
+        let mut changed = self.cells.clone();
+        changed.symmetric_difference_with(&last.after);
+        changed.union_with(&last.changed);
+
+        let radius: i64 = match self.neighborhood {
+            NeighborhoodKind::VonNeumannRadius2 => 2,
+            _ => 1,
+        };
+        let limit = self.cells.len() / 4;
+        if changed.count_ones(..) > limit {
+            return None;
+        }
+        let mut active = FixedBitSet::with_capacity(self.cells.len());
+        for idx in changed.ones() {
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            for d_row in -radius..=radius {
+                for d_col in -radius..=radius {
+                    if let Some(near) = self.offset_index(row, col, d_row, d_col) {
+                        active.insert(near);
+                    }
+                }
+            }
+        }
+        if active.count_ones(..) > limit {
+            return None;
+        }
+        Some(active)
+    }
+}
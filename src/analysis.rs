@@ -0,0 +1,208 @@
+use crate::{BoundaryMode, Universe};
+use fixedbitset::FixedBitSet;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Longest period `spaceship_speed` looks for.
+const MAX_SHIP_PERIOD: u32 = 256;
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Where a cluster of `occupied` positions starts along an axis of `size`
+/// positions: the lowest one, or on a torus the first one after the longest
+/// run of empty positions, so that a cluster straddling the wrap-around
+/// point starts at its far side rather than at 0.
+fn cluster_start(occupied: impl Iterator<Item = u32>, size: u32, toroidal: bool) -> i64 {
+    let mut taken = vec![false; size as usize];
+    occupied.for_each(|position| taken[position as usize] = true);
+    if !toroidal {
+        return taken.iter().position(|&taken| taken).unwrap_or(0) as i64;
+    }
+    let (mut best, mut best_len, mut run) = (0, 0, 0);
+    for position in 0..2 * taken.len() {
+        if taken[position % taken.len()] {
+            if run > best_len {
+                best = position % taken.len();
+                best_len = run;
+            }
+            run = 0;
+        } else {
+            run += 1;
+        }
+    }
+    best as i64
+}
+
+/// `delta` as the shorter way round an axis of `size` positions, in
+/// `(-size / 2, size / 2]`.
+fn wrap_delta(delta: i64, size: u32) -> i64 {
+    let size = size as i64;
+    let delta = delta.rem_euclid(size);
+    if 2 * delta > size {
+        delta - size
+    } else {
+        delta
+    }
+}
+
+/// Speed of a ship moving `(d_row, d_col)` cells every `period` generations
+/// in standard notation, reduced by the common divisor.
+fn speed_notation(d_row: u32, d_col: u32, period: u32) -> String {
+    let (big, small) = (d_row.max(d_col), d_row.min(d_col));
+    let divisor = gcd(gcd(big, small), period);
+    let (big, small, period) = (big / divisor, small / divisor, period / divisor);
+    let denominator = if period == 1 {
+        String::new()
+    } else {
+        format!("/{}", period)
+    };
+    if small == 0 || small == big {
+        let numerator = if big == 1 {
+            String::new()
+        } else {
+            big.to_string()
+        };
+        let direction = if small == 0 { "orthogonal" } else { "diagonal" };
+        format!("{}c{} {}", numerator, denominator, direction)
+    } else {
+        format!("({},{})c{} oblique", big, small, denominator)
+    }
+}
+
+/// Mean of positions on a circle of circumference `size`, so that a cluster
+/// straddling the wrap-around point averages to its middle.
+fn circular_mean(positions: impl Iterator<Item = u32>, size: u32) -> f64 {
+    let scale = std::f64::consts::TAU / size as f64;
+    let (mut sin, mut cos) = (0.0, 0.0);
+    for position in positions {
+        let angle = position as f64 * scale;
+        sin += angle.sin();
+        cos += angle.cos();
+    }
+    (sin.atan2(cos) / scale).rem_euclid(size as f64)
+}
+
+impl Universe {
+    /// Centre of mass of the live cells as `(row, col)`, taking wrap-around
+    /// into account in toroidal mode. `None` for an empty board.
+    fn centroid(&self) -> Option<(f64, f64)> {
+        let population = self.cells.count_ones(..);
+        if population == 0 {
+            return None;
+        }
+        let rows = self.cells.ones().map(|idx| idx as u32 / self.width);
+        let cols = self.cells.ones().map(|idx| idx as u32 % self.width);
+        Some(match self.boundary {
+            BoundaryMode::Toroidal => (
+                circular_mean(rows, self.height),
+                circular_mean(cols, self.width),
+            ),
+            BoundaryMode::Bounded => (
+                rows.map(f64::from).sum::<f64>() / population as f64,
+                cols.map(f64::from).sum::<f64>() / population as f64,
+            ),
+        })
+    }
+
+    /// Top-left corner of the live cells' bounding box, which in toroidal
+    /// mode may lie on the far side of an edge they straddle.
+    fn cluster_corner(&self) -> (i64, i64) {
+        let toroidal = self.boundary == BoundaryMode::Toroidal;
+        let rows = self.cells.ones().map(|idx| idx as u32 / self.width);
+        let cols = self.cells.ones().map(|idx| idx as u32 % self.width);
+        (
+            cluster_start(rows, self.height, toroidal),
+            cluster_start(cols, self.width, toroidal),
+        )
+    }
+
+    /// Whether the board is exactly `original` moved by `(d_row, d_col)`.
+    fn is_translation_of(&self, original: &FixedBitSet, d_row: i64, d_col: i64) -> bool {
+        if self.cells.count_ones(..) != original.count_ones(..) {
+            return false;
+        }
+        original.ones().all(|idx| {
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            self.offset_index(row, col, d_row, d_col)
+                .is_some_and(|moved| self.cells[moved])
+        })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Largest distance from the live cells' centroid to any live cell, or 0
+    /// for an empty board. In toroidal mode both the centroid and the
+    /// distances take the shorter way around each edge.
+    pub fn activity_radius(&self) -> f64 {
+        let (center_row, center_col) = match self.centroid() {
+            Some(centroid) => centroid,
+            None => return 0.0,
+        };
+        let axis_distance = |position: u32, center: f64, size: u32| {
+            let d = (position as f64 - center).abs();
+            match self.boundary {
+                BoundaryMode::Toroidal => d.min(size as f64 - d),
+                BoundaryMode::Bounded => d,
+            }
+        };
+        self.cells
+            .ones()
+            .map(|idx| {
+                let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+                let d_row = axis_distance(row, center_row, self.height);
+                let d_col = axis_distance(col, center_col, self.width);
+                d_row.hypot(d_col)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// Speed of the pattern on the board if it is a spaceship, such as
+    /// `"c/4 diagonal"` for a glider or `"c/2 orthogonal"` for a
+    /// lightweight spaceship. `None` for an empty board, a still life or
+    /// oscillator, or anything that doesn't repeat as a translated copy of
+    /// itself within 256 generations.
+    ///
+    /// The speed is the displacement over the period, both divided by
+    /// their greatest common divisor, with a numerator of 1 left out.
+    /// Moving along one axis is orthogonal, moving equally along both is
+    /// diagonal, and anything else is written `(a,b)c/p oblique`. Runs the
+    /// simulation speculatively and restores the board afterwards.
+    pub fn spaceship_speed(&mut self) -> Option<String> {
+        if self.cells.count_ones(..) == 0 {
+            return None;
+        }
+        let original = self.cells.clone();
+        let (start_row, start_col) = self.cluster_corner();
+        let saved = self.save_state();
+
+        let mut speed = None;
+        for period in 1..=MAX_SHIP_PERIOD {
+            self.step();
+            let (row, col) = self.cluster_corner();
+            let (mut d_row, mut d_col) = (row - start_row, col - start_col);
+            if self.boundary == BoundaryMode::Toroidal {
+                d_row = wrap_delta(d_row, self.height);
+                d_col = wrap_delta(d_col, self.width);
+            }
+            if self.is_translation_of(&original, d_row, d_col) {
+                if d_row != 0 || d_col != 0 {
+                    speed = Some(speed_notation(
+                        d_row.unsigned_abs() as u32,
+                        d_col.unsigned_abs() as u32,
+                        period,
+                    ));
+                }
+                break;
+            }
+        }
+        self.restore_state(saved);
+        speed
+    }
+}
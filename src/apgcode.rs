@@ -0,0 +1,182 @@
+//! apgcodes, the names Catagolue gives objects: a prefix saying what kind
+//! of object it is, then `_`, then its cells in extended Wechsler format.
+//!
+//! The prefix is `xs` and the population for a still life, `xp` and the
+//! period for an oscillator, and `xq` and the period for a spaceship, as in
+//! `xs4_33` for the block and `xq4_153` for the glider.
+//!
+//! The cells are cut into strips five rows deep, separated by `z`. Each
+//! column of a strip is one character from `0`-`9` and `a`-`v`, giving the
+//! column's five cells as bits with the top one lowest. Runs of empty
+//! columns shrink to `w` for two, `x` for three, and `y` and a character
+//! from `0`-`9` and `a`-`z` for 4 to 39, and are left off the end of a
+//! strip.
+
+use crate::pattern::Pattern;
+use crate::{Error, Universe};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Longest period `apgcode` looks for.
+const MAX_PERIOD: u32 = 256;
+
+/// Rows in a strip.
+const STRIP: u32 = 5;
+
+const COLUMNS: &[u8] = b"0123456789abcdefghijklmnopqrstuv";
+const RUNS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Append `zeros` empty columns to `out` in their shortest form.
+fn push_empty_columns(out: &mut String, mut zeros: u32) {
+    while zeros > 0 {
+        let run = zeros.min(39);
+        match run {
+            1 => out.push('0'),
+            2 => out.push('w'),
+            3 => out.push('x'),
+            run => {
+                out.push('y');
+                out.push(RUNS[run as usize - 4] as char);
+            }
+        }
+        zeros -= run;
+    }
+}
+
+/// `pattern`'s cells in extended Wechsler format.
+fn encode(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    for strip in 0..pattern.rows.div_ceil(STRIP) {
+        if strip > 0 {
+            out.push('z');
+        }
+        let mut zeros = 0;
+        for col in 0..pattern.cols {
+            let column = (0..STRIP)
+                .map(|bit| (bit, strip * STRIP + bit))
+                .filter(|&(_, row)| row < pattern.rows && pattern.get(row, col))
+                .fold(0, |column, (bit, _)| column | 1 << bit);
+            if column == 0 {
+                zeros += 1;
+            } else {
+                push_empty_columns(&mut out, zeros);
+                zeros = 0;
+                out.push(COLUMNS[column] as char);
+            }
+        }
+    }
+    out
+}
+
+/// Cells in extended Wechsler format, in a pattern just large enough for
+/// them counting from the top-left corner.
+fn decode(cells: &str) -> Result<Pattern, Error> {
+    let mut live = Vec::new();
+    let (mut strip, mut col) = (0u32, 0u32);
+    let mut chars = cells.bytes();
+    while let Some(c) = chars.next() {
+        let run = match c {
+            b'w' => 2,
+            b'x' => 3,
+            b'y' => match chars.next().and_then(|c| RUNS.iter().position(|&r| r == c)) {
+                Some(run) => run as u32 + 4,
+                None => {
+                    return Err(Error::new(format!(
+                        "bad run of empty columns in {:?}",
+                        cells
+                    )))
+                }
+            },
+            b'z' => {
+                strip += 1;
+                col = 0;
+                continue;
+            }
+            c => match COLUMNS.iter().position(|&column| column == c) {
+                Some(column) => {
+                    let rows = (0..STRIP).filter(|bit| column >> bit & 1 == 1);
+                    live.extend(rows.map(|bit| (strip * STRIP + bit, col)));
+                    1
+                }
+                None => {
+                    return Err(Error::new(format!(
+                        "unexpected character {:?} in {:?}",
+                        c as char, cells
+                    )))
+                }
+            },
+        };
+        col = col.saturating_add(run);
+    }
+    let rows = live.iter().map(|&(row, _)| row + 1).max().unwrap_or(0);
+    let cols = live
+        .iter()
+        .map(|&(_, col)| col.saturating_add(1))
+        .max()
+        .unwrap_or(0);
+    let mut pattern = Pattern::bounded(rows, cols)?;
+    for (row, col) in live {
+        pattern.set(row, col, true);
+    }
+    Ok(pattern)
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// The apgcode of the pattern on the board, treated as a single object.
+    /// The code is the shortest, then alphabetically first, over every
+    /// phase and orientation, so any copy of an object gets the same name.
+    /// `None` for an empty board or one that doesn't repeat itself, maybe
+    /// moved, within 256 generations. Runs the simulation speculatively and
+    /// restores the board afterwards.
+    pub fn apgcode(&mut self) -> Option<String> {
+        let population = self.cells.count_ones(..);
+        if population == 0 {
+            return None;
+        }
+        let original = self.cells.clone();
+        let saved = self.save_state();
+
+        let mut phases = vec![self.extract_pattern()];
+        let mut prefix = None;
+        for period in 1..=MAX_PERIOD {
+            self.step();
+            let phase = self.extract_pattern();
+            if phase == phases[0] {
+                prefix = Some(match period {
+                    _ if self.cells != original => format!("xq{}", period),
+                    1 => format!("xs{}", population),
+                    _ => format!("xp{}", period),
+                });
+                break;
+            }
+            phases.push(phase);
+        }
+        self.restore_state(saved);
+
+        let cells = phases
+            .iter()
+            .flat_map(Pattern::orientations)
+            .map(|pattern| encode(&pattern))
+            .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))?;
+        Some(format!("{}_{}", prefix?, cells))
+    }
+
+    /// A universe just large enough for the object an apgcode names, under
+    /// Conway's rule. The prefix must be `xs`, `xp` or `xq` and a number;
+    /// other kinds of apgcode don't spell out their cells.
+    pub fn from_apgcode(code: &str) -> Result<Universe, Error> {
+        let (prefix, cells) = code
+            .split_once('_')
+            .ok_or_else(|| Error::new(format!("{:?} has no prefix", code)))?;
+        let kind = prefix.get(..2);
+        let number = prefix.get(2..).and_then(|n| n.parse::<u32>().ok());
+        if !matches!(kind, Some("xs" | "xp" | "xq")) || number.is_none() {
+            return Err(Error::new(format!(
+                "unsupported apgcode prefix {:?}",
+                prefix
+            )));
+        }
+        Ok(Universe::from_pattern(&decode(cells)?))
+    }
+}
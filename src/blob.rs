@@ -0,0 +1,85 @@
+//! Patterns as short URL-safe strings.
+//!
+//! A blob is URL-safe base64 (without padding) of the pattern's row and
+//! column counts as LEB128 varints, followed by its cells packed eight to a
+//! byte, row by row, least significant bit first.
+
+use crate::pattern::Pattern;
+use crate::utils;
+use crate::{Error, Universe};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+fn push_varint(out: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Option<u32> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= ((byte & 0x7f) as u32).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn encode(pattern: &Pattern) -> String {
+    let mut bytes = Vec::new();
+    push_varint(&mut bytes, pattern.rows);
+    push_varint(&mut bytes, pattern.cols);
+    let start = bytes.len();
+    bytes.resize(start + pattern.cells.len().div_ceil(8), 0);
+    for (i, &alive) in pattern.cells.iter().enumerate() {
+        if alive {
+            bytes[start + i / 8] |= 1 << (i % 8);
+        }
+    }
+    utils::base64_url_encode(&bytes)
+}
+
+fn decode(blob: &str) -> Result<Pattern, Error> {
+    let bytes =
+        utils::base64_url_decode(blob).ok_or_else(|| Error::new("blob is not valid base64"))?;
+    let mut rest = &bytes[..];
+    let truncated = || Error::new("blob is truncated");
+    let rows = read_varint(&mut rest).ok_or_else(truncated)?;
+    let cols = read_varint(&mut rest).ok_or_else(truncated)?;
+    let len = rows as u64 * cols as u64;
+    if (rest.len() as u64) < len.div_ceil(8) {
+        return Err(truncated());
+    }
+    let cells = (0..len as usize)
+        .map(|i| rest[i / 8] & (1 << (i % 8)) != 0)
+        .collect();
+    Ok(Pattern { rows, cols, cells })
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// The live cells' bounding box as a compact URL-safe blob, suitable for
+    /// a query parameter. See `insert_blob`.
+    pub fn extract_blob(&self) -> String {
+        encode(&self.extract_pattern())
+    }
+
+    /// Stamp a blob made by `extract_blob` with its top-left corner at
+    /// `(row, col)`, overwriting every cell of its bounding box. Cells past
+    /// an edge wrap or are dropped according to the boundary mode, but the
+    /// corner itself must be on the board.
+    pub fn insert_blob(&mut self, blob: &str, row: u32, col: u32) -> Result<(), Error> {
+        self.checked_index(row, col)?;
+        let pattern = decode(blob)?;
+        self.begin_edit();
+        self.stamp_pattern(row, col, &pattern);
+        self.end_edit();
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+//! One cap on the memory undo, step-back history and snapshots keep
+//! between them, so long sessions don't grow the heap without bound.
+//!
+//! Whenever one of them grows past the budget, entries are forgotten
+//! oldest first until what is left fits: edits for `undo`, then undone
+//! edits for `redo`, then frames for `step_back`, then snapshots in the
+//! order they were saved.
+
+use crate::{snapshot, Universe};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+impl Universe {
+    /// Forget the oldest history until it fits the budget.
+    pub(crate) fn enforce_history_budget(&mut self) {
+        if self.history_budget == usize::MAX {
+            return;
+        }
+        let mut used = self.history_memory_used();
+        while used > self.history_budget {
+            let freed = self
+                .undo
+                .drop_oldest()
+                .or_else(|| self.rewind.drop_oldest())
+                .or_else(|| snapshot::drop_oldest(&mut self.snapshots));
+            match freed {
+                Some(bytes) => used -= bytes,
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Most bytes undo, step-back history and snapshots may keep between
+    /// them, `usize::MAX` unless changed.
+    pub fn history_memory_budget(&self) -> usize {
+        self.history_budget
+    }
+
+    /// Keep undo, step-back history and snapshots within `bytes` between
+    /// them, forgetting the oldest undo edits first, then undone edits,
+    /// then step-back frames, then the earliest saved snapshots. Pruning
+    /// happens now and whenever one of them grows. `usize::MAX` lifts the
+    /// cap.
+    pub fn set_history_memory_budget(&mut self, bytes: usize) {
+        self.history_budget = bytes;
+        self.enforce_history_budget();
+    }
+
+    /// Bytes undo, step-back history and snapshots take up between them.
+    pub fn history_memory_used(&self) -> usize {
+        self.undo.bytes() + self.rewind.bytes() + snapshot::bytes(&self.snapshots)
+    }
+}
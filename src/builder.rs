@@ -0,0 +1,157 @@
+use crate::rule::{Automaton, LifeRule};
+use crate::{utils, BoundaryMode, Error, FillMode, NeighborhoodKind, Universe};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Collects every construction option so a universe can be set up in one
+/// go, instead of through setters that each reset part of its state.
+///
+/// Each option method consumes the builder and returns it, so calls chain
+/// from JavaScript as well as Rust:
+///
+/// ```js
+/// const universe = Universe.builder().width(512).height(512).rule("B3/S23")
+///     .fill(FillMode.Random).density(0.3).seed(42n).build();
+/// ```
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub struct UniverseBuilder {
+    width: u32,
+    height: u32,
+    fill: FillMode,
+    density: f64,
+    seed: Option<u64>,
+    boundary: BoundaryMode,
+    background: bool,
+    include_center: bool,
+    neighborhood: NeighborhoodKind,
+    frozen_border: u32,
+    heatmap: bool,
+    rule: Automaton,
+}
+
+impl Default for UniverseBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl UniverseBuilder {
+    /// A builder with the same settings as `Universe::new`.
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new() -> UniverseBuilder {
+        UniverseBuilder {
+            width: 256,
+            height: 256,
+            fill: FillMode::Pattern,
+            density: 0.5,
+            seed: None,
+            boundary: BoundaryMode::Toroidal,
+            background: false,
+            include_center: false,
+            neighborhood: NeighborhoodKind::Moore,
+            frozen_border: 0,
+            heatmap: false,
+            rule: Automaton::Life(LifeRule::CONWAY),
+        }
+    }
+
+    pub fn width(mut self, width: u32) -> UniverseBuilder {
+        self.width = width;
+        self
+    }
+
+    pub fn height(mut self, height: u32) -> UniverseBuilder {
+        self.height = height;
+        self
+    }
+
+    pub fn fill(mut self, fill: FillMode) -> UniverseBuilder {
+        self.fill = fill;
+        self
+    }
+
+    /// Probability of each cell being alive with `FillMode::Random`,
+    /// clamped to `0..=1`. Defaults to one half.
+    pub fn density(mut self, density: f64) -> UniverseBuilder {
+        self.density = density;
+        self
+    }
+
+    /// Make `FillMode::Random` reproducible. Without a seed the random
+    /// fill differs on every build.
+    pub fn seed(mut self, seed: u64) -> UniverseBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn boundary_mode(mut self, mode: BoundaryMode) -> UniverseBuilder {
+        self.boundary = mode;
+        self
+    }
+
+    pub fn background(mut self, alive: bool) -> UniverseBuilder {
+        self.background = alive;
+        self
+    }
+
+    pub fn include_center(mut self, include: bool) -> UniverseBuilder {
+        self.include_center = include;
+        self
+    }
+
+    pub fn neighborhood(mut self, kind: NeighborhoodKind) -> UniverseBuilder {
+        self.neighborhood = kind;
+        self
+    }
+
+    pub fn frozen_border(mut self, thickness: u32) -> UniverseBuilder {
+        self.frozen_border = thickness;
+        self
+    }
+
+    pub fn heatmap(mut self, enabled: bool) -> UniverseBuilder {
+        self.heatmap = enabled;
+        self
+    }
+
+    /// Run a rule other than Conway's. Errors if `rule` can't be
+    /// parsed; see `Universe::set_rule`.
+    pub fn rule(mut self, rule: &str) -> Result<UniverseBuilder, Error> {
+        self.rule = Automaton::parse(rule)?;
+        Ok(self)
+    }
+
+    pub fn build(self) -> Universe {
+        let fill = match self.fill {
+            FillMode::Random => FillMode::Clear,
+            fill => fill,
+        };
+        let mut universe = Universe::with_size(self.width, self.height, Some(fill));
+        universe.set_boundary_mode(self.boundary);
+        universe.set_background(self.background);
+        universe.set_include_center(self.include_center);
+        universe.set_neighborhood(self.neighborhood);
+        universe.set_heatmap_enabled(self.heatmap);
+        universe.apply_rule(self.rule);
+        match self.fill {
+            FillMode::Pattern => {}
+            FillMode::Clear => universe.reset_clear(),
+            FillMode::Random => {
+                let seed = self.seed.unwrap_or_else(utils::random_seed);
+                universe.fill_random(&[self.density; 4], seed);
+            }
+        }
+        universe.set_frozen_border(self.frozen_border);
+        universe.prev_cells.clone_from(&universe.cells);
+        universe
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Start configuring a new universe. See `UniverseBuilder`.
+    pub fn builder() -> UniverseBuilder {
+        UniverseBuilder::new()
+    }
+}
@@ -0,0 +1,34 @@
+//! Stopping long operations from outside them.
+
+use std::cell::Cell;
+use std::rc::Rc;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// A handle for stopping a run, from `UniverseRun::cancel_token`, that can
+/// be handed to whatever should be able to stop it, such as a cancel
+/// button, without the run itself. Cancelling stops the run at the end of
+/// its current chunk. `Universe::find_pattern` takes one too, and checks
+/// it between generations.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Rc<Cell<bool>>,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl CancelToken {
+    /// A token not yet cancelled.
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+}
@@ -0,0 +1,168 @@
+use crate::rng::Rng;
+use crate::rule::{Automaton, LifeRule};
+use crate::{Error, Universe};
+use fixedbitset::FixedBitSet;
+use std::fmt;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// RGBA colours of live cells by colour: red, blue, green and yellow.
+pub const COLOR_RGBA: [[u8; 4]; 4] = [
+    [220, 50, 40, 255],
+    [40, 100, 230, 255],
+    [30, 140, 50, 255],
+    [240, 190, 40, 255],
+];
+
+/// A Life-like rule whose live cells each have one of `colors` colours,
+/// as in Immigration (two colours) and QuadLife (four). Cells are born,
+/// survive and die exactly as under `life`; survivors keep their colour
+/// and a newborn cell takes the colour most of its live neighbours have.
+/// On a tie it takes the one colour none of them have if there is exactly
+/// one, as when three differently coloured cells give birth in QuadLife,
+/// and otherwise the lowest of the tied colours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColoredLife {
+    life: LifeRule,
+    colors: u8,
+}
+
+impl ColoredLife {
+    pub fn life(self) -> LifeRule {
+        self.life
+    }
+
+    pub fn colors(self) -> u8 {
+        self.colors
+    }
+
+    /// Parse `Immigration` or `QuadLife`, which run Conway's rule, or any
+    /// two-state Life-like rule followed by either name, as in
+    /// `B36/S23/Immigration`. Letters may be either case.
+    pub fn parse(rule: &str) -> Result<ColoredLife, String> {
+        let upper = rule.trim().to_ascii_uppercase();
+        let invalid = || format!("invalid rule {:?}", upper);
+        let (life, variant) = match upper.rsplit_once('/') {
+            Some((life, variant)) => (LifeRule::parse(life).map_err(|_| invalid())?, variant),
+            None => (LifeRule::CONWAY, upper.as_str()),
+        };
+        let colors = match variant {
+            "IMMIGRATION" => 2,
+            "QUADLIFE" => 4,
+            _ => return Err(invalid()),
+        };
+        if life.states() != 2 {
+            return Err(invalid());
+        }
+        Ok(ColoredLife { life, colors })
+    }
+}
+
+impl fmt::Display for ColoredLife {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.life != LifeRule::CONWAY {
+            write!(f, "{}/", self.life)?;
+        }
+        f.write_str(if self.colors == 2 {
+            "Immigration"
+        } else {
+            "QuadLife"
+        })
+    }
+}
+
+impl Universe {
+    /// Colour of the cell at `idx`: 0 if it is dead, and otherwise from 1
+    /// up. Live cells that were never given a colour count as colour 1.
+    pub(crate) fn cell_color(&self, idx: usize) -> u8 {
+        match &self.colors {
+            Some(colors) if self.cells[idx] => colors[idx].max(1),
+            _ => 0,
+        }
+    }
+
+    /// The colours of the cells in `next`, the generation after the
+    /// current one under `rule`; see `ColoredLife`.
+    pub(crate) fn recolored(&self, rule: ColoredLife, next: &FixedBitSet) -> Vec<u8> {
+        let offsets = self.neighborhood_offsets();
+        let mut colors = vec![0; next.len()];
+        for idx in next.ones() {
+            if self.cells[idx] {
+                colors[idx] = self.cell_color(idx);
+                continue;
+            }
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            let mut counts = [0u8; 5];
+            for &(d_row, d_col) in offsets {
+                if let Some(parent) = self.offset_index(row, col, d_row, d_col) {
+                    counts[self.cell_color(parent) as usize] += 1;
+                }
+            }
+            let counts = &counts[1..=rule.colors as usize];
+            let most = *counts.iter().max().unwrap();
+            let tied = counts.iter().filter(|&&count| count == most).count();
+            let missing: Vec<usize> = (0..counts.len()).filter(|&i| counts[i] == 0).collect();
+            let color = if tied > 1 && missing.len() == 1 {
+                missing[0]
+            } else {
+                counts.iter().position(|&count| count == most).unwrap()
+            };
+            colors[idx] = color as u8 + 1;
+        }
+        colors
+    }
+
+    fn colored_rule(&self) -> Result<ColoredLife, Error> {
+        match self.rule {
+            Automaton::Colored(rule) => Ok(rule),
+            _ => Err(Error::new(format!(
+                "colours need the Immigration or QuadLife rule, not {}",
+                self.rule
+            ))),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// The colour of every cell, one byte per cell, row by row: 0 for dead
+    /// cells and 1 to 2 (Immigration) or 1 to 4 (QuadLife) for live ones.
+    /// All zeros under rules without colours.
+    pub fn cell_colors(&self) -> Vec<u8> {
+        (0..(self.width * self.height) as usize)
+            .map(|idx| self.cell_color(idx))
+            .collect()
+    }
+
+    /// Bring `(row, col)` to life in `color`, or kill it with colour 0.
+    /// Errors if the cell is off the board, the rule has no colours or it
+    /// has fewer than `color`.
+    pub fn set_cell_color(&mut self, row: u32, col: u32, color: u8) -> Result<(), Error> {
+        let idx = self.checked_index(row, col)?;
+        let rule = self.colored_rule()?;
+        if color > rule.colors {
+            return Err(Error::new(format!(
+                "colour {} is not one of the {} colours of {}",
+                color, rule.colors, rule
+            )));
+        }
+        self.cells.set(idx, color > 0);
+        if let Some(colors) = &mut self.colors {
+            colors[idx] = color;
+        }
+        Ok(())
+    }
+
+    /// Give every live cell a random colour, reproducibly for a given
+    /// `seed`. Errors if the rule has no colours.
+    pub fn randomize_colors(&mut self, seed: u64) -> Result<(), Error> {
+        let rule = self.colored_rule()?;
+        let mut rng = Rng::new(seed);
+        if let Some(colors) = &mut self.colors {
+            for idx in self.cells.ones() {
+                colors[idx] = 1 + (rng.next_f64() * rule.colors as f64) as u8;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{BoundaryMode, NeighborhoodKind, Universe};
+use fixedbitset::FixedBitSet;
+
+/// Live neighbours of every cell, kept up to date by adjusting the counts
+/// around each cell that is born or dies rather than recounting.
+#[derive(Clone, Debug)]
+pub struct NeighborCounts {
+    /// Per cell, its live neighbours, not counting itself.
+    counts: Vec<u8>,
+    /// The live cells the counts are for.
+    cells: FixedBitSet,
+    /// The board's shape, since reshaping it can keep the number of cells.
+    width: u32,
+    height: u32,
+    neighborhood: NeighborhoodKind,
+    boundary: BoundaryMode,
+    background: bool,
+}
+
+impl NeighborCounts {
+    /// The counts, if they are for the board as it is now.
+    pub(crate) fn current(&self, universe: &Universe) -> Option<&[u8]> {
+        let fresh = (self.width, self.height) == (universe.width, universe.height)
+            && self.neighborhood == universe.neighborhood
+            && self.boundary == universe.boundary
+            && self.background == universe.background
+            && self.cells == universe.cells;
+        fresh.then(|| &self.counts[..])
+    }
+}
+
+impl Universe {
+    /// Bring the neighbour counts up to date with the board: a step of one
+    /// for each neighbour of every cell born or killed since they were
+    /// last brought up to date, by a tick or an edit, or a full count if
+    /// there are none yet or the shape, neighbourhood or boundary changed.
+    pub(crate) fn update_neighbor_counts(&mut self) {
+        let stale = match &self.neighbor_counts {
+            Some(counts) => {
+                (counts.width, counts.height) != (self.width, self.height)
+                    || counts.neighborhood != self.neighborhood
+                    || counts.boundary != self.boundary
+                    || counts.background != self.background
+            }
+            None => true,
+        };
+        if stale {
+            let offsets = self.neighborhood_offsets();
+            let counts = (0..self.height)
+                .flat_map(|row| (0..self.width).map(move |col| (row, col)))
+                .map(|(row, col)| self.offsets_neighbor_count(row, col, offsets))
+                .collect();
+            self.neighbor_counts = Some(NeighborCounts {
+                counts,
+                cells: self.cells.clone(),
+                width: self.width,
+                height: self.height,
+                neighborhood: self.neighborhood,
+                boundary: self.boundary,
+                background: self.background,
+            });
+            return;
+        }
+
+        let mut neighbor_counts = self.neighbor_counts.take().unwrap();
+        let mut changed = neighbor_counts.cells.clone();
+        changed.symmetric_difference_with(&self.cells);
+        for idx in changed.ones() {
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            let born = self.cells[idx];
+            // The neighbourhoods are symmetric, so the cells that have this
+            // one as a neighbour are its own neighbours.
+            for &(d_row, d_col) in self.neighborhood_offsets() {
+                if let Some(near) = self.offset_index(row, col, d_row, d_col) {
+                    let count = &mut neighbor_counts.counts[near];
+                    *count = if born { *count + 1 } else { *count - 1 };
+                }
+            }
+        }
+        neighbor_counts.cells.clone_from(&self.cells);
+        self.neighbor_counts = Some(neighbor_counts);
+    }
+}
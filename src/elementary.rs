@@ -0,0 +1,43 @@
+use crate::engine::{self, Neighborhood};
+use std::fmt;
+
+/// One of Wolfram's 256 elementary cellular automata, run as a spacetime
+/// diagram: the bottom row is the current generation, and each tick
+/// scrolls the board up one row and computes the new bottom row from the
+/// old one. Bit `4 * left + 2 * centre + right` of the rule number is a
+/// cell's next state given its own and its neighbours' states.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ElementaryRule {
+    number: u8,
+}
+
+impl ElementaryRule {
+    /// Parse Golly's notation: `W` followed by the rule number, such as
+    /// `W110`.
+    pub fn parse(rule: &str) -> Result<ElementaryRule, String> {
+        let rule = rule.trim().to_ascii_uppercase();
+        rule.strip_prefix('W')
+            .and_then(|number| number.parse().ok())
+            .map(|number| ElementaryRule { number })
+            .ok_or_else(|| format!("invalid rule {:?}", rule))
+    }
+}
+
+impl fmt::Display for ElementaryRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "W{}", self.number)
+    }
+}
+
+impl engine::Rule for ElementaryRule {
+    #[inline]
+    fn next_state(&self, _current: u8, neighborhood: &Neighborhood) -> u8 {
+        let block = neighborhood.block();
+        if neighborhood.row() + 1 < neighborhood.rows() {
+            // Take the cell below's state, scrolling the diagram up.
+            return (block >> 7 & 1) as u8;
+        }
+        let (left, centre, right) = (block >> 3 & 1, block >> 4 & 1, block >> 5 & 1);
+        self.number >> (4 * left + 2 * centre + right) & 1
+    }
+}
@@ -0,0 +1,276 @@
+use crate::rng;
+use crate::threads;
+use crate::{NeighborhoodKind, Universe};
+use fixedbitset::FixedBitSet;
+
+/// What a rule can see of the cells around the one being updated.
+pub struct Neighborhood<'a> {
+    universe: &'a Universe,
+    row: u32,
+    col: u32,
+    idx: usize,
+    totals: &'a [u32],
+    /// `Universe::padded_cells`, or empty outside the Moore neighbourhood.
+    padded: &'a [u8],
+    /// The 3x3 block around the cell as in `block`, kept as a running
+    /// window along the row when `padded` is in use.
+    block: u16,
+    /// `Universe::neighbor_counts` if they are up to date, or empty.
+    counts: &'a [u8],
+}
+
+impl Neighborhood<'_> {
+    /// Live cells among the eight around the cell, plus the cell itself
+    /// when `include_center` is set.
+    #[inline]
+    pub fn live_count(&self) -> u8 {
+        if !self.counts.is_empty() {
+            let center = self.universe.include_center && self.universe.cells[self.idx];
+            return self.counts[self.idx] + center as u8;
+        }
+        if self.padded.is_empty() {
+            return self.universe.live_neighbor_count(self.row, self.col);
+        }
+        let center = if self.universe.include_center {
+            0
+        } else {
+            1 << 4
+        };
+        (self.block & !center).count_ones() as u8
+    }
+
+    /// The 3x3 block around the cell as laid out by `neighborhood_mask`.
+    #[inline]
+    pub fn block(&self) -> u16 {
+        if self.padded.is_empty() {
+            return self.universe.neighborhood_mask(self.row, self.col);
+        }
+        self.block
+    }
+
+    /// States of the cells above, left, right and below, off-grid ones in
+    /// bounded mode taking the background state.
+    #[inline]
+    pub fn orthogonal_states(&self) -> [u8; 4] {
+        [(-1, 0), (0, -1), (0, 1), (1, 0)].map(|(d_row, d_col)| {
+            match self.universe.offset_index(self.row, self.col, d_row, d_col) {
+                Some(idx) => self.universe.cell_state(idx),
+                None => self.universe.background as u8,
+            }
+        })
+    }
+
+    /// States of the eight cells around, in the order Golly's rule trees
+    /// take them: north-west, north-east, south-west, south-east, north,
+    /// west, east, south. Off-grid ones in bounded mode take the
+    /// background state.
+    #[inline]
+    pub fn moore_states(&self) -> [u8; 8] {
+        [
+            (-1, -1),
+            (-1, 1),
+            (1, -1),
+            (1, 1),
+            (-1, 0),
+            (0, -1),
+            (0, 1),
+            (1, 0),
+        ]
+        .map(|(d_row, d_col)| {
+            match self.universe.offset_index(self.row, self.col, d_row, d_col) {
+                Some(idx) => self.universe.cell_state(idx),
+                None => self.universe.background as u8,
+            }
+        })
+    }
+
+    /// A uniform number in `[0, 1)` fixed by `seed`, the generation and
+    /// the cell, so rules can be random and still replay exactly.
+    #[inline]
+    pub fn random(&self, seed: u64) -> f64 {
+        let bits = rng::mix(rng::mix(seed, self.universe.generation), self.idx as u64);
+        (bits >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// The cell's row.
+    #[inline]
+    pub fn row(&self) -> u32 {
+        self.row
+    }
+
+    /// The number of rows on the board.
+    #[inline]
+    pub fn rows(&self) -> u32 {
+        self.universe.height
+    }
+
+    /// The 2x2 Margolus block the cell belongs to this generation and the
+    /// cell's bit within it; see `MargolusRule`.
+    #[inline]
+    pub fn margolus_block(&self) -> (u8, u8) {
+        self.universe.margolus_block(self.row, self.col)
+    }
+
+    /// The cell's entry in the totals the rule computed with
+    /// `Rule::totals`.
+    #[inline]
+    pub fn total(&self) -> u32 {
+        self.totals[self.idx]
+    }
+}
+
+/// A cellular automaton rule, applied to every cell on each tick.
+///
+/// Cell states are bytes: 0 is dead, 1 is alive, and rules with more
+/// states give the others their own meaning. The board's bitset holds the
+/// cells in state 1. Rules are shared between the threads working out a
+/// tick's rows under the `threads` feature, so must be `Sync`.
+pub trait Rule: Sync {
+    /// The state of a cell in the next generation.
+    fn next_state(&self, current: u8, neighborhood: &Neighborhood) -> u8;
+
+    /// A value per cell, row by row, worked out for the whole board before
+    /// each tick and handed back through `Neighborhood::total`. For rules
+    /// whose neighbourhoods are too large to visit cell by cell.
+    fn totals(&self, _universe: &Universe) -> Vec<u32> {
+        Vec::new()
+    }
+
+    /// Whether the next state depends only on the cell, its neighbourhood
+    /// and the cells in its 3x3 block, and never changes a cell whose
+    /// surroundings are as they were the last time it stayed put. Such
+    /// rules only evaluate the cells near recent changes.
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// Next state under a rule where live cells that don't survive decay
+/// through states `2..states` before dying, as in Generations rules.
+#[inline]
+pub fn life_like(states: u8, current: u8, born: bool, survives: bool) -> u8 {
+    match current {
+        0 => born as u8,
+        1 if survives => 1,
+        state if state + 1 < states => state + 1,
+        _ => 0,
+    }
+}
+
+impl Universe {
+    /// Compute the next generation under `rule`: the live cells into
+    /// `next` and, if given, every cell's state into `states`. Frozen
+    /// border cells take the background state.
+    ///
+    /// Generic over the rule so each one gets its own specialised loop.
+    pub(crate) fn evolve<R: Rule>(
+        &self,
+        rule: &R,
+        next: &mut FixedBitSet,
+        mut states: Option<&mut [u8]>,
+    ) {
+        let totals = rule.totals(self);
+        let counts = self
+            .neighbor_counts
+            .as_ref()
+            .and_then(|counts| counts.current(self))
+            .unwrap_or(&[]);
+        if states.is_none() && rule.is_local() {
+            if let Some(active) = self.active_cells() {
+                next.clone_from(&self.cells);
+                for idx in active.ones() {
+                    let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+                    let state = self.next_cell_state(rule, row, col, &totals, counts, &[], 0);
+                    next.set(idx, state == 1);
+                }
+                return;
+            }
+        }
+        let padded = match self.neighborhood {
+            NeighborhoodKind::Moore if counts.is_empty() => self.padded_cells(),
+            _ => Vec::new(),
+        };
+        let rows = threads::map_rows(self.height, |row| {
+            self.next_row(rule, row, &totals, counts, &padded)
+        });
+        next.set_range(.., false);
+        for (idx, &state) in rows.iter().flatten().enumerate() {
+            if state == 1 {
+                next.insert(idx);
+            }
+            if let Some(states) = &mut states {
+                states[idx] = state;
+            }
+        }
+    }
+
+    /// The next state of every cell in `row` under `rule`, with `totals`,
+    /// `counts` and `padded` as in `Neighborhood`.
+    fn next_row<R: Rule>(
+        &self,
+        rule: &R,
+        row: u32,
+        totals: &[u32],
+        counts: &[u8],
+        padded: &[u8],
+    ) -> Vec<u8> {
+        let padded_width = self.width as usize + 2;
+        let above = &padded[(row as usize * padded_width).min(padded.len())..];
+        // Column `col` of the three padded rows around this one as bits 0,
+        // 3 and 6, ready to slot into a 3x3 block.
+        let column = |col: usize| {
+            above[col] as u16
+                | (above[col + padded_width] as u16) << 3
+                | (above[col + 2 * padded_width] as u16) << 6
+        };
+        let mut block = 0;
+        if !padded.is_empty() {
+            block = column(0) << 1 | column(1) << 2;
+        }
+        (0..self.width)
+            .map(|col| {
+                if !padded.is_empty() {
+                    // Slide the 3x3 window one column right.
+                    block = (block >> 1 & 0b011_011_011) | column(col as usize + 2) << 2;
+                }
+                self.next_cell_state(rule, row, col, totals, counts, padded, block)
+            })
+            .collect()
+    }
+
+    /// The next state of `(row, col)` under `rule`, with `counts`,
+    /// `padded` and `block` as in `Neighborhood`. Frozen border cells
+    /// take the background state.
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    fn next_cell_state<R: Rule>(
+        &self,
+        rule: &R,
+        row: u32,
+        col: u32,
+        totals: &[u32],
+        counts: &[u8],
+        padded: &[u8],
+        block: u16,
+    ) -> u8 {
+        if self.in_frozen_border(row, col) {
+            return self.background as u8;
+        }
+        let idx = self.get_index(row, col);
+        let neighborhood = Neighborhood {
+            universe: self,
+            row,
+            col,
+            idx,
+            totals,
+            padded,
+            block,
+            counts,
+        };
+        let current = match &self.states {
+            Some(_) => self.cell_state(idx),
+            None => self.cells[idx] as u8,
+        };
+        rule.next_state(current, &neighborhood)
+    }
+}
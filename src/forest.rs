@@ -0,0 +1,106 @@
+use crate::engine::{self, Neighborhood};
+use crate::rule::Automaton;
+use crate::{Error, Universe};
+use std::fmt;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Forest-fire cell states. Trees are state 1, so they are the board's
+/// live cells.
+pub const EMPTY: u8 = 0;
+pub const TREE: u8 = 1;
+pub const BURNING: u8 = 2;
+
+/// The Drossel-Schwabl forest-fire model. A burning cell burns out, a tree
+/// catches fire if one of its four orthogonal neighbours is burning or,
+/// with probability `lightning`, if struck by lightning, and a tree grows
+/// on an empty cell with probability `growth`.
+///
+/// The random draws come from `seed`, the generation and the cell, so a
+/// run is the same every time it is replayed from the same board.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForestFire {
+    growth: f64,
+    lightning: f64,
+    seed: u64,
+}
+
+impl ForestFire {
+    /// Errors unless both probabilities are between 0 and 1.
+    pub fn new(growth: f64, lightning: f64, seed: u64) -> Result<ForestFire, String> {
+        for (name, p) in [("growth", growth), ("lightning", lightning)] {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(format!("{} probability {} is not between 0 and 1", name, p));
+            }
+        }
+        Ok(ForestFire {
+            growth,
+            lightning,
+            seed,
+        })
+    }
+
+    /// Parse the `ForestFire,P0.05,F0.0001,S42` form written by `Display`:
+    /// growth probability, lightning probability and seed. Letters may be
+    /// either case.
+    pub fn parse(rule: &str) -> Result<ForestFire, String> {
+        let rule = rule.trim().to_ascii_uppercase();
+        let invalid = || format!("invalid rule {:?}", rule);
+        let fields: Vec<&str> = rule.split(',').map(str::trim).collect();
+        if fields.len() != 4 || fields[0] != "FORESTFIRE" {
+            return Err(invalid());
+        }
+        let growth = fields[1].strip_prefix('P').and_then(|p| p.parse().ok());
+        let lightning = fields[2].strip_prefix('F').and_then(|f| f.parse().ok());
+        let seed = fields[3].strip_prefix('S').and_then(|s| s.parse().ok());
+        match (growth, lightning, seed) {
+            (Some(growth), Some(lightning), Some(seed)) => {
+                ForestFire::new(growth, lightning, seed).map_err(|_| invalid())
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for ForestFire {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ForestFire,P{},F{},S{}",
+            self.growth, self.lightning, self.seed
+        )
+    }
+}
+
+impl engine::Rule for ForestFire {
+    #[inline]
+    fn next_state(&self, current: u8, neighborhood: &Neighborhood) -> u8 {
+        match current {
+            EMPTY if neighborhood.random(self.seed) < self.growth => TREE,
+            EMPTY => EMPTY,
+            TREE => {
+                let burning = neighborhood.orthogonal_states().contains(&BURNING);
+                if burning || neighborhood.random(self.seed) < self.lightning {
+                    BURNING
+                } else {
+                    TREE
+                }
+            }
+            _ => EMPTY,
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Switch to the forest-fire model with tree growth probability
+    /// `growth` (p) and lightning probability `lightning` (f), drawing
+    /// from `seed`; see `ForestFire`. Errors unless both probabilities are
+    /// between 0 and 1. The board is left as it is, its live cells
+    /// becoming trees.
+    pub fn set_forest_fire(&mut self, growth: f64, lightning: f64, seed: u64) -> Result<(), Error> {
+        let rule = ForestFire::new(growth, lightning, seed)?;
+        self.apply_rule(Automaton::ForestFire(rule));
+        Ok(())
+    }
+}
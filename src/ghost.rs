@@ -0,0 +1,85 @@
+use crate::pattern::Pattern;
+use crate::{Error, Universe};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// A preview pattern drawn over the board but never simulated.
+#[derive(Clone)]
+pub struct Ghost {
+    pattern: Pattern,
+    row: u32,
+    col: u32,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Show a `rows` by `cols` preview pattern (one byte per cell, row by
+    /// row, 0 dead) with its top-left corner at `(anchor_row, anchor_col)`.
+    ///
+    /// The ghost only affects `render_with_ghost`; it takes no part in
+    /// `tick` and is never written to the board. Replaces any earlier ghost.
+    pub fn set_ghost(
+        &mut self,
+        rows: u32,
+        cols: u32,
+        cells: &[u8],
+        anchor_row: u32,
+        anchor_col: u32,
+    ) -> Result<(), Error> {
+        let pattern = Pattern::from_bytes(rows, cols, cells).ok_or_else(|| {
+            Error::new(format!(
+                "expected {} cells, got {}",
+                rows * cols,
+                cells.len()
+            ))
+        })?;
+        self.ghost = Some(Ghost {
+            pattern,
+            row: anchor_row,
+            col: anchor_col,
+        });
+        Ok(())
+    }
+
+    pub fn clear_ghost(&mut self) {
+        self.ghost = None;
+    }
+
+    /// Like `render`, but with the live cells of the ghost drawn as `▣`
+    /// whatever lies beneath them. Ghost cells past an edge wrap or are
+    /// dropped according to the boundary mode.
+    pub fn render_with_ghost(&self) -> String {
+        let mut ghost_cells = vec![false; (self.width * self.height) as usize];
+        if let Some(ghost) = &self.ghost {
+            let pattern = &ghost.pattern;
+            for d_row in 0..pattern.rows {
+                for d_col in 0..pattern.cols {
+                    if !pattern.get(d_row, d_col) {
+                        continue;
+                    }
+                    if let Some(idx) =
+                        self.offset_index(ghost.row, ghost.col, d_row as i64, d_col as i64)
+                    {
+                        ghost_cells[idx] = true;
+                    }
+                }
+            }
+        }
+
+        let mut out = String::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                out.push(if ghost_cells[idx] {
+                    '▣'
+                } else if self.cells[idx] {
+                    '◼'
+                } else {
+                    '◻'
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
@@ -0,0 +1,296 @@
+//! `Engine::Gpu`: running Life-like rules in a WebGPU compute shader, with
+//! the `gpu` feature. The WebGPU bindings in `web-sys` are unstable, so
+//! this also takes building with `RUSTFLAGS="--cfg=web_sys_unstable_apis"`.
+//!
+//! Reading a texture back is asynchronous, so `tick` and `jump` stay on the
+//! CPU and only `run_async` hands generations to the GPU, a batch at a
+//! time.
+
+use crate::hashlife::Engine;
+use crate::recording::Op;
+use crate::texels::{self, TEXEL};
+use crate::Universe;
+use js_sys::{Number, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    gpu_buffer_usage, gpu_map_mode, gpu_texture_usage, GpuBindGroup, GpuBindGroupDescriptor,
+    GpuBindGroupEntry, GpuBuffer, GpuBufferDescriptor, GpuComputePipeline,
+    GpuComputePipelineDescriptor, GpuDevice, GpuProgrammableStage, GpuShaderModuleDescriptor,
+    GpuTexelCopyBufferInfo, GpuTexelCopyBufferLayout, GpuTexelCopyTextureInfo, GpuTexture,
+    GpuTextureDescriptor, GpuTextureFormat,
+};
+
+/// Most generations `run_async` queues on the GPU between read-backs.
+pub const BATCH: u32 = 64;
+
+/// One generation of a Life-like rule: each invocation reads a cell's 3x3
+/// block from `current` and writes its next state to `next`.
+const SHADER: &str = "
+struct Params {
+    birth: u32,
+    survival: u32,
+    toroidal: u32,
+    background: u32,
+    include_center: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var current: texture_2d<u32>;
+@group(0) @binding(2) var next: texture_storage_2d<r32uint, write>;
+
+fn cell(x: i32, y: i32, size: vec2<i32>) -> u32 {
+    if (params.toroidal == 1u) {
+        return textureLoad(current, (vec2<i32>(x, y) + size) % size, 0).r;
+    }
+    if (x < 0 || y < 0 || x >= size.x || y >= size.y) {
+        return params.background;
+    }
+    return textureLoad(current, vec2<i32>(x, y), 0).r;
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(current));
+    let x = i32(id.x);
+    let y = i32(id.y);
+    if (x >= size.x || y >= size.y) {
+        return;
+    }
+    let alive = cell(x, y, size);
+    var count = alive * params.include_center;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            if (dx != 0 || dy != 0) {
+                count += cell(x + dx, y + dy, size);
+            }
+        }
+    }
+    let counts = select(params.birth, params.survival, alive == 1u);
+    textureStore(next, vec2<i32>(x, y), vec4<u32>((counts >> count) & 1u, 0u, 0u, 0u));
+}
+";
+
+/// Bytes per row of a read-back buffer `width` texels wide, which must be
+/// a multiple of 256.
+fn padded_row(width: u32) -> u32 {
+    (width * TEXEL).div_ceil(256) * 256
+}
+
+/// The two textures a board steps between, and the bind groups reading
+/// from one and writing to the other.
+#[derive(Clone)]
+struct Board {
+    width: u32,
+    height: u32,
+    textures: [GpuTexture; 2],
+    /// Bind groups reading from texture `i` and writing to the other.
+    bind_groups: [GpuBindGroup; 2],
+}
+
+/// A WebGPU device set up to run two-state Life-like rules, for
+/// `Universe::set_gpu`. Experimental.
+///
+/// Boards live in two `r32uint` storage textures, one texel per cell, and
+/// each generation is a compute pass from one into the other. The textures
+/// are made for the first board run and again whenever the size changes.
+/// Cloning shares the device and textures.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct GpuContext {
+    device: GpuDevice,
+    pipeline: GpuComputePipeline,
+    params: GpuBuffer,
+    board: Option<Board>,
+    /// The latest generation is in `board.textures[front]`.
+    front: usize,
+}
+
+impl GpuContext {
+    /// The board's textures, made afresh unless they are already `width`
+    /// by `height`.
+    fn board(&mut self, width: u32, height: u32) -> Result<&Board, JsValue> {
+        let fits =
+            matches!(&self.board, Some(board) if (board.width, board.height) == (width, height));
+        if !fits {
+            let size = [Number::from(width), Number::from(height)];
+            let usage = gpu_texture_usage::STORAGE_BINDING
+                | gpu_texture_usage::TEXTURE_BINDING
+                | gpu_texture_usage::COPY_SRC
+                | gpu_texture_usage::COPY_DST;
+            let texture = || {
+                self.device.create_texture(&GpuTextureDescriptor::new(
+                    GpuTextureFormat::R32uint,
+                    &size,
+                    usage,
+                ))
+            };
+            let textures = [texture()?, texture()?];
+            let layout = self.pipeline.get_bind_group_layout(0);
+            let bind_group = |from: usize| -> Result<GpuBindGroup, JsValue> {
+                let entries = [
+                    GpuBindGroupEntry::new_with_gpu_buffer(0, &self.params),
+                    GpuBindGroupEntry::new_with_gpu_texture_view(1, &textures[from].create_view()?),
+                    GpuBindGroupEntry::new_with_gpu_texture_view(
+                        2,
+                        &textures[1 - from].create_view()?,
+                    ),
+                ];
+                Ok(self
+                    .device
+                    .create_bind_group(&GpuBindGroupDescriptor::new(&entries, &layout)))
+            };
+            let bind_groups = [bind_group(0)?, bind_group(1)?];
+            self.board = Some(Board {
+                width,
+                height,
+                textures,
+                bind_groups,
+            });
+        }
+        Ok(self.board.as_ref().expect("board was just made"))
+    }
+
+    /// Upload `universe`, queue `generations` generations and a copy of
+    /// the result into a buffer that is returned for mapping. Errors unless
+    /// the GPU can run `universe`.
+    fn run(&mut self, universe: &Universe, generations: u32) -> Result<GpuBuffer, JsValue> {
+        let params = texels::params(universe)?;
+        let texels = texels::pack(&universe.cells);
+        let (width, height) = (universe.width, universe.height);
+        let size = [Number::from(width), Number::from(height)];
+        let queue = self.device.queue();
+        queue.write_buffer_with_u32_and_u8_slice(&self.params, 0, &params)?;
+        let board = self.board(width, height)?.clone();
+        let layout = GpuTexelCopyBufferLayout::new();
+        layout.set_bytes_per_row(width * TEXEL);
+        queue.write_texture_with_u8_slice_and_u32_sequence(
+            &GpuTexelCopyTextureInfo::new(&board.textures[0]),
+            &texels,
+            &layout,
+            &size,
+        )?;
+
+        let mut front = 0;
+        let encoder = self.device.create_command_encoder();
+        for _ in 0..generations {
+            let pass = encoder.begin_compute_pass();
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, Some(&board.bind_groups[front]));
+            pass.dispatch_workgroups_with_workgroup_count_y(width.div_ceil(8), height.div_ceil(8));
+            pass.end();
+            front = 1 - front;
+        }
+        let padded_row = padded_row(width);
+        let staging = self.device.create_buffer(&GpuBufferDescriptor::new(
+            padded_row * height,
+            gpu_buffer_usage::MAP_READ | gpu_buffer_usage::COPY_DST,
+        ))?;
+        let destination = GpuTexelCopyBufferInfo::new(&staging);
+        destination.set_bytes_per_row(padded_row);
+        encoder.copy_texture_to_buffer_with_u32_sequence(
+            &GpuTexelCopyTextureInfo::new(&board.textures[front]),
+            &destination,
+            &size,
+        )?;
+        queue.submit(&[encoder.finish()]);
+        self.front = front;
+        Ok(staging)
+    }
+}
+
+#[wasm_bindgen]
+impl GpuContext {
+    /// Ask the browser for a WebGPU device and set up the shader. Rejects
+    /// if WebGPU isn't available.
+    pub async fn create() -> Result<GpuContext, JsValue> {
+        let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window"))?;
+        let adapter = JsFuture::from(window.navigator().gpu().request_adapter())
+            .await?
+            .into_option()
+            .ok_or_else(|| JsValue::from_str("no WebGPU adapter"))?;
+        let device = JsFuture::from(adapter.request_device()).await?;
+
+        let module = device.create_shader_module(&GpuShaderModuleDescriptor::new(SHADER));
+        let stage = GpuProgrammableStage::new(&module);
+        stage.set_entry_point("main");
+        let pipeline = device.create_compute_pipeline(
+            &GpuComputePipelineDescriptor::new_with_gpu_auto_layout_mode(
+                web_sys::GpuAutoLayoutMode::Auto,
+                &stage,
+            ),
+        );
+        let params = device.create_buffer(&GpuBufferDescriptor::new(
+            32,
+            gpu_buffer_usage::UNIFORM | gpu_buffer_usage::COPY_DST,
+        ))?;
+        Ok(GpuContext {
+            device,
+            pipeline,
+            params,
+            board: None,
+            front: 0,
+        })
+    }
+}
+
+impl Universe {
+    /// Whether `run_async` should hand generations to the GPU: the engine
+    /// is `Engine::Gpu`, a device has been set and it can run the board.
+    pub(crate) fn on_gpu(&self) -> bool {
+        self.engine == Engine::Gpu && self.gpu.is_some() && self.runs_on_gpu()
+    }
+
+    /// Run `generations` generations on the GPU and read the board back.
+    /// They are recorded as that many ticks, but count as a single step
+    /// for `step_back`, ages and the heat map, as a Hashlife jump does.
+    /// Errors if the GPU does, leaving the board as it was.
+    pub(crate) async fn tick_on_gpu(&mut self, generations: u32) -> Result<(), JsValue> {
+        let mut gpu = self
+            .gpu
+            .take()
+            .ok_or_else(|| JsValue::from_str("no GPU has been set"))?;
+        let staging = gpu.run(self, generations);
+        self.gpu = Some(gpu);
+        let staging = staging?;
+        JsFuture::from(staging.map_async(gpu_map_mode::READ)).await?;
+        let range: JsValue = staging.get_mapped_range()?.into();
+        let mapped = Uint8Array::new(&range).to_vec();
+        staging.unmap();
+        let (width, height) = (self.width as usize, self.height as usize);
+        let cells = texels::unpack(&mapped, width, height, padded_row(self.width) as usize);
+
+        for _ in 0..generations {
+            self.record(Op::Tick);
+        }
+        self.remember();
+        let mut next = std::mem::take(&mut self.temp_cells);
+        for (idx, &cell) in cells.iter().enumerate() {
+            next.set(idx, cell == 1);
+        }
+        self.last_tick = None;
+        self.neighbor_counts = None;
+        self.finish_step(next, generations as u64);
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Give `Engine::Gpu` a device to run on, or take it away with
+    /// `undefined`, leaving `run_async` on the CPU.
+    pub fn set_gpu(&mut self, gpu: Option<GpuContext>) {
+        self.gpu = gpu;
+    }
+
+    /// The texture holding the generation last worked out on the GPU, one
+    /// `r32uint` texel per cell, 1 for live cells, for drawing without
+    /// reading back. It only matches the board until the board next
+    /// changes other than on the GPU. `undefined` if no device has been
+    /// set or it hasn't run yet.
+    pub fn gpu_texture(&self) -> Option<GpuTexture> {
+        let gpu = self.gpu.as_ref()?;
+        let board = gpu.board.as_ref()?;
+        Some(board.textures[gpu.front].clone())
+    }
+}
@@ -0,0 +1,333 @@
+use crate::recording::Op;
+use crate::rule::{Automaton, LifeRule};
+use crate::{BoundaryMode, Error, NeighborhoodKind, Universe};
+use fixedbitset::FixedBitSet;
+use std::collections::HashMap;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Largest `k` accepted by `jump`.
+pub const MAX_JUMP: u32 = 62;
+
+/// Largest `k` accepted by `jump` when it has to tick generation by
+/// generation, about a million ticks.
+const MAX_FLAT_JUMP: u32 = 20;
+
+/// Nodes the cache may hold before it is dropped and started afresh.
+const MAX_NODES: usize = 1 << 20;
+
+/// How `tick` and `jump` compute new generations.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    /// Go over the board generation by generation.
+    Flat = 0,
+    /// Gosper's Hashlife: the board as a quadtree of shared nodes, each
+    /// node's future worked out once and remembered. Very fast on boards
+    /// with a lot of repetition in space or time, and able to jump ahead
+    /// by huge numbers of generations at once.
+    Hashlife = 1,
+    /// A WebGPU compute shader, with the `gpu` feature and a device from
+    /// `set_gpu`. Experimental. Only `run_async` uses it, as reading the
+    /// board back is asynchronous; `tick` and `jump` go generation by
+    /// generation as under `Flat`.
+    Gpu = 2,
+}
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+
+/// A square of `2^level` cells on a side: a single cell at level 0, and
+/// otherwise four quadrants one level down.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    /// North-west, north-east, south-west and south-east.
+    quadrants: [NodeId; 4],
+    level: u8,
+    alive: bool,
+}
+
+/// The Hashlife cache for one rule: every distinct node seen, and the
+/// futures worked out for them.
+#[derive(Clone, Debug)]
+pub struct Hashlife {
+    rule: LifeRule,
+    include_center: bool,
+    nodes: Vec<Node>,
+    interned: HashMap<[NodeId; 4], NodeId>,
+    /// A node's centre half `2^j` generations on, by node and `j`.
+    results: HashMap<(NodeId, u8), NodeId>,
+}
+
+impl Hashlife {
+    fn new(rule: LifeRule, include_center: bool) -> Hashlife {
+        let leaf = |alive| Node {
+            quadrants: [DEAD; 4],
+            level: 0,
+            alive,
+        };
+        Hashlife {
+            rule,
+            include_center,
+            nodes: vec![leaf(false), leaf(true)],
+            interned: HashMap::new(),
+            results: HashMap::new(),
+        }
+    }
+
+    fn quadrants(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].quadrants
+    }
+
+    /// The node made of four quadrants, shared with any identical one.
+    fn join(&mut self, quadrants: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.interned.get(&quadrants) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            quadrants,
+            level: self.nodes[quadrants[0] as usize].level + 1,
+            alive: quadrants.iter().any(|&q| self.nodes[q as usize].alive),
+        });
+        self.interned.insert(quadrants, id);
+        id
+    }
+
+    /// The node for the `2^level` square of `cells`, a board `width` cells
+    /// wide, with its top-left corner at `(row, col)`.
+    fn build(&mut self, cells: &FixedBitSet, width: u32, level: u8, row: u32, col: u32) -> NodeId {
+        if level == 0 {
+            return cells[(row * width + col) as usize] as NodeId;
+        }
+        let half = 1 << (level - 1);
+        let quadrants = [(0, 0), (0, half), (half, 0), (half, half)]
+            .map(|(d_row, d_col)| self.build(cells, width, level - 1, row + d_row, col + d_col));
+        self.join(quadrants)
+    }
+
+    /// Set the live cells of node `id` in `cells`, with its top-left corner
+    /// at `(row, col)`.
+    fn write(&self, id: NodeId, row: u32, col: u32, width: u32, cells: &mut FixedBitSet) {
+        let node = self.nodes[id as usize];
+        if !node.alive {
+            return;
+        }
+        if node.level == 0 {
+            cells.insert((row * width + col) as usize);
+            return;
+        }
+        let half = 1 << (node.level - 1);
+        for (quadrant, (d_row, d_col)) in
+            node.quadrants
+                .iter()
+                .zip([(0, 0), (0, half), (half, 0), (half, half)])
+        {
+            self.write(*quadrant, row + d_row, col + d_col, width, cells);
+        }
+    }
+
+    /// The centre half of a node, as it is now.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.quadrants(id).map(|q| self.quadrants(q));
+        self.join([nw[3], ne[2], sw[1], se[0]])
+    }
+
+    /// The centre 2x2 of a 4x4 node one generation on.
+    fn base(&mut self, id: NodeId) -> NodeId {
+        // The 4x4 cells as bits, row by row.
+        let mut grid = 0u16;
+        for (i, quadrant) in self.quadrants(id).iter().enumerate() {
+            for (j, &cell) in self.quadrants(*quadrant).iter().enumerate() {
+                let (row, col) = (i / 2 * 2 + j / 2, i % 2 * 2 + j % 2);
+                grid |= (cell as u16) << (row * 4 + col);
+            }
+        }
+        let next = |row: usize, col: usize| -> NodeId {
+            let centre = 1u16 << (row * 4 + col);
+            let block = 0b0111_0111_0111u16 << ((row - 1) * 4 + col - 1);
+            let count = (grid & block & !centre).count_ones()
+                + (self.include_center && grid & centre != 0) as u32;
+            let counts = if grid & centre != 0 {
+                self.rule.survival()
+            } else {
+                self.rule.birth()
+            };
+            (counts >> count & 1) as NodeId
+        };
+        let quadrants = [next(1, 1), next(1, 2), next(2, 1), next(2, 2)];
+        self.join(quadrants)
+    }
+
+    /// The centre half of node `id` `2^j` generations on. `j` may be at
+    /// most two less than the node's level.
+    ///
+    /// The node is cut into nine overlapping squares half its size. For the
+    /// largest step each is advanced half the way, the results are put
+    /// together into four squares, and those are advanced the rest of the
+    /// way; for smaller steps the first stage only takes the squares'
+    /// centres and the second does all the advancing.
+    fn advance(&mut self, id: NodeId, j: u8) -> NodeId {
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+        let level = self.nodes[id as usize].level;
+        let result = if level == 2 {
+            self.base(id)
+        } else {
+            let [nw, ne, sw, se] = self.quadrants(id);
+            let [nw_q, ne_q, sw_q, se_q] = [nw, ne, sw, se].map(|q| self.quadrants(q));
+            let squares = [
+                nw,
+                self.join([nw_q[1], ne_q[0], nw_q[3], ne_q[2]]),
+                ne,
+                self.join([nw_q[2], nw_q[3], sw_q[0], sw_q[1]]),
+                self.join([nw_q[3], ne_q[2], sw_q[1], se_q[0]]),
+                self.join([ne_q[2], ne_q[3], se_q[0], se_q[1]]),
+                sw,
+                self.join([sw_q[1], se_q[0], sw_q[3], se_q[2]]),
+                se,
+            ];
+            let full = j + 2 == level;
+            let s = squares.map(|square| {
+                if full {
+                    self.advance(square, j - 1)
+                } else {
+                    self.centre(square)
+                }
+            });
+            let second = if full { j - 1 } else { j };
+            let quadrants = [
+                [s[0], s[1], s[3], s[4]],
+                [s[1], s[2], s[4], s[5]],
+                [s[3], s[4], s[6], s[7]],
+                [s[4], s[5], s[7], s[8]],
+            ]
+            .map(|quadrants| {
+                let square = self.join(quadrants);
+                self.advance(square, second)
+            });
+            self.join(quadrants)
+        };
+        self.results.insert((id, j), result);
+        result
+    }
+}
+
+impl Universe {
+    /// The rule to run under Hashlife, if it is the engine and the board
+    /// suits it: a two-state Life-like rule on the Moore neighbourhood, on
+    /// a toroidal square board whose side is a power of two and at least
+    /// 4, with no frozen border, ants or noise.
+    pub(crate) fn hashlife_rule(&self) -> Option<LifeRule> {
+        match self.rule {
+            Automaton::Life(rule)
+                if self.engine == Engine::Hashlife
+                    && rule.states() == 2
+                    && self.neighborhood == NeighborhoodKind::Moore
+                    && self.boundary == BoundaryMode::Toroidal
+                    && self.width == self.height
+                    && self.width.is_power_of_two()
+                    && self.width >= 4
+                    && self.frozen_border == 0
+                    && self.ants.is_empty()
+                    && self.noise.is_none() =>
+            {
+                Some(rule)
+            }
+            _ => None,
+        }
+    }
+
+    /// Compute the board `2^k` generations on under `rule` into `next`.
+    ///
+    /// The torus is tiled out into a square big enough that the light
+    /// cone of the board's corner tile stays inside it for `2^k`
+    /// generations. Tiles are all the same node, so this costs next to
+    /// nothing, and advancing it gives a square starting on a tile
+    /// boundary whose top-left tile is the board.
+    pub(crate) fn hashlife_into(&mut self, rule: LifeRule, k: u32, next: &mut FixedBitSet) {
+        let mut hashlife = match self.hashlife.take() {
+            Some(hashlife)
+                if hashlife.rule == rule
+                    && hashlife.include_center == self.include_center
+                    && hashlife.nodes.len() < MAX_NODES =>
+            {
+                hashlife
+            }
+            _ => Hashlife::new(rule, self.include_center),
+        };
+        let level = self.width.trailing_zeros() as u8;
+        let board = hashlife.build(&self.cells, self.width, level, 0, 0);
+        let tilings = (k as u8 + 2).saturating_sub(level).max(2);
+        let mut tiled = board;
+        for _ in 0..tilings {
+            tiled = hashlife.join([tiled; 4]);
+        }
+        let mut result = hashlife.advance(tiled, k as u8);
+        for _ in 1..tilings {
+            result = hashlife.quadrants(result)[0];
+        }
+        next.clear();
+        hashlife.write(result, 0, 0, self.width, next);
+        self.hashlife = Some(hashlife);
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    /// Choose how generations are computed. Hashlife only runs two-state
+    /// Life-like rules on the Moore neighbourhood, on toroidal square
+    /// boards whose side is a power of two, without a frozen border, ants
+    /// or noise, and the GPU only boards `runs_on_gpu` accepts; anything
+    /// else goes generation by generation as before. Either way the board
+    /// is read and edited the same.
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+        if engine != Engine::Hashlife {
+            self.hashlife = None;
+        }
+    }
+
+    /// Advance `2^k` generations. Under Hashlife that is a single step
+    /// however large `k` is; otherwise it ticks `2^k` times. Ages and the
+    /// heat map count a Hashlife jump as one generation. Errors if `k` is
+    /// over 62, or over 20 when the board can't run under Hashlife, so a
+    /// jump can't tick for hours.
+    pub fn jump(&mut self, k: u32) -> Result<(), Error> {
+        let rule = self.hashlife_rule();
+        let max = if rule.is_some() {
+            MAX_JUMP
+        } else {
+            MAX_FLAT_JUMP
+        };
+        if k > max {
+            return Err(Error::new(format!(
+                "can jump at most 2^{} generations, not 2^{}",
+                max, k
+            )));
+        }
+        self.record(Op::Jump(k));
+        self.remember();
+        match rule {
+            Some(rule) => {
+                let mut next = std::mem::take(&mut self.temp_cells);
+                self.hashlife_into(rule, k, &mut next);
+                self.last_tick = None;
+                self.finish_step(next, 1 << k);
+            }
+            None => {
+                for _ in 0..1u64 << k {
+                    self.step();
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use crate::engine::{self, Neighborhood};
+use crate::rule::split_rulestring;
+use std::fmt;
+
+/// Hensel letters for each neighbour count up to 4, in canonical order.
+/// Counts above 4 use the letters of their complement: the `5c`
+/// configuration is the `3c` one with every neighbour flipped.
+const LETTERS: [&str; 5] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrytwz"];
+
+/// One representative neighbourhood per letter, in the same order as
+/// `LETTERS`, with bit `3 * row + col` set for each live cell of the 3x3
+/// block. The other configurations with that letter are its rotations and
+/// reflections.
+const REPRESENTATIVES: [&[u16]; 5] = [
+    &[0],
+    &[1, 2],
+    &[5, 10, 3, 40, 33, 68],
+    &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97],
+    &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+];
+
+/// Bit of the centre cell in a 3x3 neighbourhood mask.
+pub const CENTRE: u16 = 1 << 4;
+
+/// An isotropic non-totalistic rule: birth and survival depend on the
+/// shape of the live neighbours up to rotation and reflection, not just on
+/// their number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IsotropicRule {
+    /// For each neighbour count, a mask of the letters that cause a birth,
+    /// bit `i` standing for the `i`th letter of that count.
+    birth: [u16; 9],
+    survival: [u16; 9],
+    states: u8,
+    /// Whether a cell is born or survives for each 3x3 neighbourhood mask,
+    /// one bit per mask.
+    table: [u64; 8],
+}
+
+impl IsotropicRule {
+    pub fn states(self) -> u8 {
+        self.states
+    }
+
+    /// Parse a rule in Hensel notation, such as `B2-a/S12` or
+    /// `B3/S23-a4i`, in the same layouts as `LifeRule::parse`. Each count
+    /// may be followed by the letters it applies to, or by `-` and the
+    /// letters it doesn't; a bare count applies to every configuration.
+    pub fn parse(rule: &str) -> Result<IsotropicRule, String> {
+        let rule = rule.trim().to_ascii_uppercase();
+        let invalid = || format!("invalid rule {:?}", rule);
+        let (birth, survival, states) = split_rulestring(&rule).ok_or_else(invalid)?;
+        let mut parsed = IsotropicRule {
+            birth: parse_letters(birth).ok_or_else(invalid)?,
+            survival: parse_letters(survival).ok_or_else(invalid)?,
+            states,
+            table: [0; 8],
+        };
+        for mask in 0..512u16 {
+            let alive = mask & CENTRE != 0;
+            let (count, letter) = classify(mask & !CENTRE);
+            let letters = if alive {
+                parsed.survival[count]
+            } else {
+                parsed.birth[count]
+            };
+            if letters >> letter & 1 == 1 {
+                parsed.table[mask as usize / 64] |= 1 << (mask % 64);
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl engine::Rule for IsotropicRule {
+    #[inline]
+    fn next_state(&self, current: u8, neighborhood: &Neighborhood) -> u8 {
+        let mask = neighborhood.block();
+        let hit = self.table[mask as usize / 64] >> (mask % 64) & 1 == 1;
+        engine::life_like(self.states, current, hit, hit)
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+/// Letters of a neighbour count, by count.
+fn letters(count: usize) -> &'static str {
+    LETTERS[count.min(8 - count)]
+}
+
+/// Parse the counts and letters of one half of a rule into per-count
+/// letter masks.
+fn parse_letters(part: &str) -> Option<[u16; 9]> {
+    let mut masks = [0u16; 9];
+    let mut chars = part.chars().peekable();
+    while let Some(c) = chars.next() {
+        let count = c.to_digit(10).filter(|&n| n <= 8)? as usize;
+        if masks[count] != 0 {
+            return None;
+        }
+        let all = (1u16 << letters(count).len().max(1)) - 1;
+        let negate = chars.peek() == Some(&'-');
+        if negate {
+            chars.next();
+        }
+        let mut listed = 0u16;
+        while let Some(&letter) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+            chars.next();
+            let position = letters(count).find(letter.to_ascii_lowercase())?;
+            listed |= 1 << position;
+        }
+        masks[count] = match (negate, listed) {
+            (false, 0) => all,
+            (false, listed) => listed,
+            (true, 0) => return None,
+            (true, listed) => all & !listed,
+        };
+        if masks[count] == 0 {
+            return None;
+        }
+    }
+    Some(masks)
+}
+
+/// Neighbour count and letter index of a 3x3 mask without its centre.
+fn classify(neighbours: u16) -> (usize, u32) {
+    let count = neighbours.count_ones() as usize;
+    let canonical = if count > 4 {
+        neighbours ^ (0x1ff & !CENTRE)
+    } else {
+        neighbours
+    };
+    let letter = REPRESENTATIVES[canonical.count_ones() as usize]
+        .iter()
+        .position(|&representative| symmetries(representative).contains(&canonical))
+        .unwrap();
+    (count, letter as u32)
+}
+
+/// The eight rotations and reflections of a 3x3 mask.
+fn symmetries(mask: u16) -> [u16; 8] {
+    let transform = |mask: u16, map: &dyn Fn(u16, u16) -> (u16, u16)| -> u16 {
+        (0..9)
+            .filter(|bit| mask >> bit & 1 == 1)
+            .map(|bit| {
+                let (row, col) = map(bit / 3, bit % 3);
+                1 << (3 * row + col)
+            })
+            .sum()
+    };
+    let mut out = [0; 8];
+    let mut current = mask;
+    for i in 0..4 {
+        out[i] = current;
+        out[i + 4] = transform(current, &|row, col| (row, 2 - col));
+        current = transform(current, &|row, col| (col, 2 - row));
+    }
+    out
+}
+
+impl fmt::Display for IsotropicRule {
+    /// Canonical form: counts in ascending order, each followed by its
+    /// letters or, when that is shorter, by `-` and the letters left out.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let half = |masks: &[u16; 9]| -> String {
+            let mut out = String::new();
+            for (count, &mask) in masks.iter().enumerate() {
+                if mask == 0 {
+                    continue;
+                }
+                out.push(std::char::from_digit(count as u32, 10).unwrap());
+                let letters = letters(count);
+                let all = (1u16 << letters.len().max(1)) - 1;
+                if mask == all || letters.is_empty() {
+                    continue;
+                }
+                let (prefix, shown) = if (all & !mask).count_ones() < mask.count_ones() {
+                    ("-", all & !mask)
+                } else {
+                    ("", mask)
+                };
+                out.push_str(prefix);
+                out.extend(
+                    letters
+                        .chars()
+                        .enumerate()
+                        .filter(|(i, _)| shown >> i & 1 == 1)
+                        .map(|(_, c)| c),
+                );
+            }
+            out
+        };
+        write!(f, "B{}/S{}", half(&self.birth), half(&self.survival))?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
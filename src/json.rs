@@ -0,0 +1,176 @@
+//! The universe as JSON, for keeping app state in a readable form that
+//! diffs well in version control. Only built with the `serde` feature.
+//!
+//! The settings are plain fields, and the cells are one string per row,
+//! `.` for a dead cell and `O` for a live one, so a board reads much as it
+//! looks. Under rules with more than two states a `states` field follows
+//! with each cell's state, row by row.
+
+use crate::rule::Automaton;
+use crate::{BoundaryMode, Error, FillMode, NeighborhoodKind, Universe};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryFrom;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    width: u32,
+    height: u32,
+    rule: String,
+    generation: u64,
+    boundary: BoundaryMode,
+    neighborhood: NeighborhoodKind,
+    background: bool,
+    include_center: bool,
+    frozen_border: u32,
+    stop_when_extinct: bool,
+    stop_when_stable: bool,
+    cells: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    states: Option<Vec<Vec<u8>>>,
+}
+
+impl From<&Universe> for Snapshot {
+    fn from(universe: &Universe) -> Snapshot {
+        let rows = (0..universe.height)
+            .map(|row| (0..universe.width).map(move |col| universe.get_index(row, col)));
+        let cells = rows
+            .clone()
+            .map(|row| {
+                row.map(|idx| if universe.cells[idx] { 'O' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        let states = (universe.rule.states() > 2).then(|| {
+            rows.map(|row| row.map(|idx| universe.cell_state(idx)).collect())
+                .collect()
+        });
+        Snapshot {
+            width: universe.width,
+            height: universe.height,
+            rule: universe.rule(),
+            generation: universe.generation,
+            boundary: universe.boundary,
+            neighborhood: universe.neighborhood,
+            background: universe.background,
+            include_center: universe.include_center,
+            frozen_border: universe.frozen_border,
+            stop_when_extinct: universe.stop_when_extinct,
+            stop_when_stable: universe.stop_when_stable,
+            cells,
+            states,
+        }
+    }
+}
+
+impl TryFrom<Snapshot> for Universe {
+    type Error = Error;
+
+    fn try_from(snapshot: Snapshot) -> Result<Universe, Error> {
+        let (width, height) = (snapshot.width, snapshot.height);
+        // Empty boards are fine: `to_json` writes them like any other.
+        if width.checked_mul(height).is_none() {
+            return Err(Error::new(format!("invalid size {} by {}", width, height)));
+        }
+        let rule = Automaton::parse(&snapshot.rule)?;
+        let states = rule.states();
+
+        // Check the shape before allocating, so a size the cells don't
+        // back up can't ask for a huge board.
+        if snapshot.cells.len() != height as usize {
+            return Err(Error::new(format!(
+                "expected {} rows of cells, got {}",
+                height,
+                snapshot.cells.len()
+            )));
+        }
+        for (row, line) in snapshot.cells.iter().enumerate() {
+            if line.chars().count() != width as usize {
+                return Err(Error::new(format!(
+                    "row {} is not {} cells wide",
+                    row, width
+                )));
+            }
+        }
+        if let Some(rows) = &snapshot.states {
+            let fits =
+                rows.len() == height as usize && rows.iter().all(|row| row.len() == width as usize);
+            if !fits {
+                return Err(Error::new(format!(
+                    "states must be {} by {}",
+                    width, height
+                )));
+            }
+        }
+
+        let mut universe = Universe::with_size(width, height, Some(FillMode::Clear));
+        universe.apply_rule(rule);
+        for (row, line) in snapshot.cells.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                let idx = universe.get_index(row as u32, col as u32);
+                match c {
+                    '.' => {}
+                    'O' => universe.cells.insert(idx),
+                    c => {
+                        return Err(Error::new(format!(
+                            "unexpected character {:?} in row {}",
+                            c, row
+                        )))
+                    }
+                }
+            }
+        }
+        if let Some(rows) = &snapshot.states {
+            for (idx, &state) in rows.iter().flatten().enumerate() {
+                if state >= states {
+                    return Err(Error::new(format!("cell state {} is out of range", state)));
+                }
+                if state >= 2 {
+                    universe.put_state(idx, state);
+                }
+            }
+        }
+
+        universe.generation = snapshot.generation;
+        universe.boundary = snapshot.boundary;
+        universe.neighborhood = snapshot.neighborhood;
+        universe.background = snapshot.background;
+        universe.include_center = snapshot.include_center;
+        universe.frozen_border = snapshot.frozen_border;
+        universe.stop_when_extinct = snapshot.stop_when_extinct;
+        universe.stop_when_stable = snapshot.stop_when_stable;
+        universe.prev_cells.clone_from(&universe.cells);
+        Ok(universe)
+    }
+}
+
+impl Serialize for Universe {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Snapshot::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Universe {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Universe, D::Error> {
+        let snapshot = Snapshot::deserialize(deserializer)?;
+        Universe::try_from(snapshot).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// The size, rule, generation, settings and cells as pretty-printed
+    /// JSON. Rules loaded with `load_rule_file` are saved by name only, so
+    /// such saves can't be read back.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a universe always serializes")
+    }
+
+    /// Rebuild a universe from `to_json`. Errors on malformed JSON, a
+    /// missing field, or a size, rule or cell that doesn't make sense.
+    pub fn from_json(json: &str) -> Result<Universe, Error> {
+        serde_json::from_str(json).map_err(|error| Error::new(error.to_string()))
+    }
+}
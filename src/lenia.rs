@@ -0,0 +1,285 @@
+use crate::rng::Rng;
+use crate::utils;
+use crate::Error;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Largest kernel radius accepted, in cells.
+const MAX_RADIUS: u32 = 50;
+
+/// Orbium, Lenia's best-known glider, from Bert Chan's collection. It
+/// needs the default parameters.
+const ORBIUM: [[f32; 20]; 20] = [
+    [
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.1, 0.14, 0.1, 0.0, 0.0, 0.03, 0.03, 0.0, 0.0, 0.3, 0.0,
+        0.0, 0.0, 0.0,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.08, 0.24, 0.3, 0.3, 0.18, 0.14, 0.15, 0.16, 0.15, 0.09, 0.2,
+        0.0, 0.0, 0.0, 0.0,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.15, 0.34, 0.44, 0.46, 0.38, 0.18, 0.14, 0.11, 0.13, 0.19, 0.18,
+        0.45, 0.0, 0.0, 0.0,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.0, 0.06, 0.13, 0.39, 0.5, 0.5, 0.37, 0.06, 0.0, 0.0, 0.0, 0.02, 0.16,
+        0.68, 0.0, 0.0, 0.0,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.11, 0.17, 0.17, 0.33, 0.4, 0.38, 0.28, 0.14, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.18, 0.42, 0.0, 0.0,
+    ],
+    [
+        0.0, 0.0, 0.09, 0.18, 0.13, 0.06, 0.08, 0.26, 0.32, 0.32, 0.27, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.82, 0.0, 0.0,
+    ],
+    [
+        0.27, 0.0, 0.16, 0.12, 0.0, 0.0, 0.0, 0.25, 0.38, 0.44, 0.45, 0.34, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.22, 0.17, 0.0,
+    ],
+    [
+        0.0, 0.07, 0.2, 0.02, 0.0, 0.0, 0.0, 0.31, 0.48, 0.57, 0.6, 0.57, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.49, 0.0,
+    ],
+    [
+        0.0, 0.59, 0.19, 0.0, 0.0, 0.0, 0.0, 0.2, 0.57, 0.69, 0.76, 0.76, 0.49, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.36, 0.0,
+    ],
+    [
+        0.0, 0.58, 0.19, 0.0, 0.0, 0.0, 0.0, 0.0, 0.67, 0.83, 0.9, 0.92, 0.87, 0.12, 0.0, 0.0, 0.0,
+        0.0, 0.22, 0.07,
+    ],
+    [
+        0.0, 0.0, 0.46, 0.0, 0.0, 0.0, 0.0, 0.0, 0.7, 0.93, 1.0, 1.0, 1.0, 0.61, 0.0, 0.0, 0.0,
+        0.0, 0.18, 0.11,
+    ],
+    [
+        0.0, 0.0, 0.82, 0.0, 0.0, 0.0, 0.0, 0.0, 0.47, 1.0, 1.0, 0.98, 1.0, 0.96, 0.27, 0.0, 0.0,
+        0.0, 0.19, 0.1,
+    ],
+    [
+        0.0, 0.0, 0.46, 0.0, 0.0, 0.0, 0.0, 0.0, 0.25, 1.0, 1.0, 0.84, 0.92, 0.97, 0.54, 0.14,
+        0.04, 0.1, 0.21, 0.05,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.4, 0.0, 0.0, 0.0, 0.0, 0.09, 0.8, 1.0, 0.82, 0.8, 0.85, 0.63, 0.31, 0.18,
+        0.19, 0.2, 0.01,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.36, 0.1, 0.0, 0.0, 0.0, 0.05, 0.54, 0.86, 0.79, 0.74, 0.72, 0.6, 0.39,
+        0.28, 0.24, 0.13, 0.0,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.01, 0.3, 0.07, 0.0, 0.0, 0.08, 0.36, 0.64, 0.7, 0.64, 0.6, 0.51, 0.39,
+        0.29, 0.19, 0.04, 0.0,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.0, 0.1, 0.24, 0.14, 0.1, 0.15, 0.29, 0.45, 0.53, 0.52, 0.46, 0.4, 0.31,
+        0.21, 0.08, 0.0, 0.0,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.08, 0.21, 0.21, 0.22, 0.29, 0.36, 0.39, 0.37, 0.33, 0.26, 0.18,
+        0.09, 0.0, 0.0, 0.0,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.03, 0.13, 0.19, 0.22, 0.24, 0.24, 0.23, 0.18, 0.13, 0.05,
+        0.0, 0.0, 0.0, 0.0,
+    ],
+    [
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.02, 0.06, 0.08, 0.09, 0.07, 0.05, 0.01, 0.0, 0.0,
+        0.0, 0.0, 0.0,
+    ],
+];
+
+/// A Lenia world: a continuous cellular automaton on a toroidal grid,
+/// with states between 0 and 1.
+///
+/// Each step, every cell's potential is the weighted average of the cells
+/// around it under a ring-shaped kernel of the given `radius`, peaking
+/// half way out. The cell then grows by `growth(potential) / time_scale`,
+/// where `growth` is a bell curve centred on `mu` with width `sigma`,
+/// scaled to run from -1 to 1, and is clipped back into `0..=1`.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub struct Lenia {
+    width: u32,
+    height: u32,
+    cells: Vec<f32>,
+    next: Vec<f32>,
+    radius: u32,
+    time_scale: f32,
+    mu: f32,
+    sigma: f32,
+    /// The kernel's non-zero weights as `(d_row, d_col, weight)`, summing
+    /// to 1.
+    kernel: Vec<(i32, i32, f32)>,
+}
+
+/// Offsets and weights of a ring kernel of `radius`: a bell curve over
+/// the distance from the centre as a fraction of the radius, peaking at
+/// one half.
+fn ring_kernel(radius: u32) -> Vec<(i32, i32, f32)> {
+    let r = radius as i32;
+    let mut kernel = Vec::new();
+    for d_row in -r..=r {
+        for d_col in -r..=r {
+            let distance = ((d_row * d_row + d_col * d_col) as f32).sqrt() / radius as f32;
+            if distance < 1.0 {
+                let weight = (-((distance - 0.5) / 0.15).powi(2) / 2.0).exp();
+                kernel.push((d_row, d_col, weight));
+            }
+        }
+    }
+    let total: f32 = kernel.iter().map(|&(_, _, weight)| weight).sum();
+    kernel.iter_mut().for_each(|entry| entry.2 /= total);
+    kernel
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Lenia {
+    /// An empty world with the parameters Orbium lives under: radius 13,
+    /// time scale 10, `mu` 0.15 and `sigma` 0.015.
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32) -> Lenia {
+        utils::set_panic_hook();
+        let size = (width * height) as usize;
+        Lenia {
+            width,
+            height,
+            cells: vec![0.0; size],
+            next: vec![0.0; size],
+            radius: 13,
+            time_scale: 10.0,
+            mu: 0.15,
+            sigma: 0.015,
+            kernel: ring_kernel(13),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Change the kernel radius, the number of steps per unit of time and
+    /// the growth curve. Errors unless the radius is between 1 and 50 and
+    /// the time scale and `sigma` are positive.
+    pub fn set_params(
+        &mut self,
+        radius: u32,
+        time_scale: f32,
+        mu: f32,
+        sigma: f32,
+    ) -> Result<(), Error> {
+        if !(1..=MAX_RADIUS).contains(&radius) || time_scale <= 0.0 || sigma <= 0.0 {
+            return Err(Error::new(format!(
+                "invalid Lenia parameters: radius {}, time scale {}, sigma {}",
+                radius, time_scale, sigma
+            )));
+        }
+        if radius != self.radius {
+            self.kernel = ring_kernel(radius);
+        }
+        self.radius = radius;
+        self.time_scale = time_scale;
+        self.mu = mu;
+        self.sigma = sigma;
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = 0.0);
+    }
+
+    /// Set every cell to a random state, reproducibly for a given `seed`.
+    pub fn fill_random(&mut self, seed: u64) {
+        let mut rng = Rng::new(seed);
+        self.cells
+            .iter_mut()
+            .for_each(|cell| *cell = rng.next_f64() as f32);
+    }
+
+    /// Place an Orbium with its top-left corner at `(row, col)`, wrapping
+    /// around the edges. It glides under the default parameters. Does
+    /// nothing on an empty world.
+    pub fn insert_orbium(&mut self, row: u32, col: u32) {
+        if self.cells.is_empty() {
+            return;
+        }
+        for (d_row, states) in ORBIUM.iter().enumerate() {
+            for (d_col, &state) in states.iter().enumerate() {
+                let r = (row + d_row as u32) % self.height;
+                let c = (col + d_col as u32) % self.width;
+                self.cells[(r * self.width + c) as usize] = state;
+            }
+        }
+    }
+
+    /// Advance by `steps` time steps.
+    pub fn tick(&mut self, steps: u32) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Sum of every cell's state.
+    pub fn mass(&self) -> f32 {
+        self.cells.iter().sum()
+    }
+
+    /// Pointer to the cell states, laid out as in `cells`.
+    pub fn cells_ptr(&self) -> *const f32 {
+        self.cells.as_ptr()
+    }
+}
+
+impl Lenia {
+    /// Every cell's state, row by row, borrowed for Rust callers.
+    /// JavaScript takes a view with `cells`.
+    pub fn cells_slice(&self) -> &[f32] {
+        &self.cells
+    }
+
+    /// One step. The potentials are convolved from a copy of the world
+    /// padded by the radius on every side with wrapped cells, one kernel
+    /// offset at a time over whole rows, so no lookup needs to wrap.
+    fn step(&mut self) {
+        if self.cells.is_empty() {
+            return;
+        }
+        let (width, height) = (self.width as usize, self.height as usize);
+        let r = self.radius as usize;
+        let padded_width = width + 2 * r;
+        let mut padded = vec![0.0f32; padded_width * (height + 2 * r)];
+        for (i, padded_row) in padded.chunks_mut(padded_width).enumerate() {
+            let row = (i + height * (r / height + 1) - r) % height;
+            for (j, cell) in padded_row.iter_mut().enumerate() {
+                let col = (j + width * (r / width + 1) - r) % width;
+                *cell = self.cells[row * width + col];
+            }
+        }
+
+        let potential = &mut self.next;
+        potential.iter_mut().for_each(|p| *p = 0.0);
+        for &(d_row, d_col, weight) in &self.kernel {
+            for row in 0..height {
+                let start = (row as i32 + r as i32 + d_row) as usize * padded_width
+                    + (r as i32 + d_col) as usize;
+                let source = &padded[start..start + width];
+                let target = &mut potential[row * width..(row + 1) * width];
+                for (p, &cell) in target.iter_mut().zip(source) {
+                    *p += weight * cell;
+                }
+            }
+        }
+
+        let two_sigma_squared = 2.0 * self.sigma * self.sigma;
+        for (cell, &p) in self.cells.iter_mut().zip(potential.iter()) {
+            let growth = 2.0 * (-(p - self.mu).powi(2) / two_sigma_squared).exp() - 1.0;
+            *cell = (*cell + growth / self.time_scale).clamp(0.0, 1.0);
+        }
+    }
+}
@@ -1,12 +1,121 @@
+//! Cellular automata for the web, also usable as a plain Rust library.
+//!
+//! The simulation is plain Rust that needs nothing from JavaScript off
+//! wasm32, so boards can be built, edited and ticked natively, in
+//! benchmarks and tests. On wasm32 its types and methods are exported with
+//! `#[wasm_bindgen]`, and a thin layer adds what only makes sense on the
+//! web: `web`, for methods that take or return typed arrays and for
+//! throwing `Error`s to JavaScript as strings; `run`, for runs that yield
+//! to the page between chunks; `gpu`, for WebGPU; and the Web Worker pool
+//! in `threads`. `utils` asks JavaScript for the time and random numbers
+//! there, and the standard library everywhere else.
+
+mod active;
+mod analysis;
+mod apgcode;
+mod blob;
+mod budget;
+mod builder;
+mod cancel;
+mod colored;
+mod counts;
+mod elementary;
+mod engine;
+mod error;
+mod forest;
+mod ghost;
+#[cfg(all(feature = "gpu", web_sys_unstable_apis, target_arch = "wasm32"))]
+mod gpu;
+mod gray_scott;
+mod hashlife;
+mod hensel;
+#[cfg(feature = "serde")]
+mod json;
+mod lenia;
+mod life105;
+mod life106;
+mod ltl;
+mod macrocell;
+mod margolus;
+mod pattern;
+mod plaintext;
+mod recording;
+mod rewind;
+mod rle;
+mod rng;
+mod rule;
+mod rule_file;
+#[cfg(target_arch = "wasm32")]
+mod run;
+mod sandpile;
+mod serialize;
+mod shard;
+mod share;
+mod shared;
+mod snapshot;
+mod sparse;
+mod stochastic;
+mod swar;
+mod texels;
+mod threads;
+mod turmite;
+mod undo;
 mod utils;
+mod wator;
+#[cfg(target_arch = "wasm32")]
+mod web;
+mod wireworld;
 
-use js_sys::Math;
 use std::fmt;
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
 extern crate fixedbitset;
+#[cfg(target_arch = "wasm32")]
 extern crate web_sys;
+use active::LastTick;
+use counts::NeighborCounts;
 use fixedbitset::FixedBitSet;
+use ghost::Ghost;
+use hashlife::Hashlife;
+use pattern::Pattern;
+use recording::Op;
+use rewind::Rewind;
+use rng::Rng;
+use rule::{Automaton, LifeRule, LifeTable};
+use serialize::PendingLoad;
+use shared::SharedCells;
+use snapshot::Snapshots;
+use stochastic::Noise;
+use turmite::Ant;
+use undo::UndoStack;
+use wator::Creature;
+
+pub use builder::UniverseBuilder;
+pub use cancel::CancelToken;
+pub use error::Error;
+#[cfg(all(feature = "gpu", web_sys_unstable_apis, target_arch = "wasm32"))]
+pub use gpu::GpuContext;
+pub use gray_scott::{GrayScott, GrayScottPreset};
+pub use hashlife::Engine;
+pub use lenia::Lenia;
+pub use rle::RleReader;
+pub use rule::Rule;
+#[cfg(target_arch = "wasm32")]
+pub use run::UniverseRun;
+pub use shard::UniverseShard;
+pub use sparse::SparseUniverse;
+/// Start the Web Worker pool ticks run on, with the `threads` feature:
+/// `await init_thread_pool(navigator.hardwareConcurrency)` before the
+/// first tick. The workers share memory through a `SharedArrayBuffer`, so
+/// the page must be cross-origin isolated, served with
+/// `Cross-Origin-Opener-Policy: same-origin` and
+/// `Cross-Origin-Embedder-Policy: require-corp`; otherwise the pool can't
+/// start.
+#[cfg(all(feature = "threads", target_arch = "wasm32"))]
+pub use threads::init_thread_pool;
+pub use turmite::Direction;
+pub use wireworld::Circuit;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -14,36 +123,54 @@ use fixedbitset::FixedBitSet;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[wasm_bindgen]
-extern "C" {
-    fn alert(s: &str);
-}
-
 // A macro to provide `println!(..)`-style syntax for `console.log` logging.
+#[cfg(target_arch = "wasm32")]
+#[allow(unused_macros)]
 macro_rules! log {
     ( $( $t:tt )* ) => {
         web_sys::console::log_1(&format!( $( $t )* ).into());
     }
 }
 
+// Off the web there is no console, so log to standard error instead.
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(unused_macros)]
+macro_rules! log {
+    ( $( $t:tt )* ) => {
+        eprintln!( $( $t )* );
+    }
+}
+
+/// Times its own lifetime with `console.time`, or off the web by printing
+/// the elapsed time to standard error.
 pub struct Timer<'a> {
     name: &'a str,
+    #[cfg(not(target_arch = "wasm32"))]
+    start: f64,
 }
 
 impl<'a> Timer<'a> {
     pub fn new(name: &'a str) -> Timer<'a> {
+        #[cfg(target_arch = "wasm32")]
         web_sys::console::time_with_label(name);
-        Timer { name }
+        Timer {
+            name,
+            #[cfg(not(target_arch = "wasm32"))]
+            start: utils::now(),
+        }
     }
 }
 
 impl<'a> Drop for Timer<'a> {
     fn drop(&mut self) {
+        #[cfg(target_arch = "wasm32")]
         web_sys::console::time_end_with_label(self.name);
+        #[cfg(not(target_arch = "wasm32"))]
+        eprintln!("{}: {}ms", self.name, utils::now() - self.start);
     }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Cell {
@@ -51,15 +178,203 @@ pub enum Cell {
     Alive = 1,
 }
 
-#[wasm_bindgen]
+/// How a newly created universe is filled.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillMode {
+    /// Every cell whose index is a multiple of 2 or 7 is alive.
+    Pattern = 0,
+    /// Every cell is dead.
+    Clear = 1,
+    /// Each cell is alive with probability one half.
+    Random = 2,
+}
+
+/// Whether this build ticks Life-like rules with WebAssembly SIMD; see the
+/// `simd` feature.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub fn simd_enabled() -> bool {
+    swar::SIMD
+}
+
+/// Number of differing bits between two packed cell buffers, such as the
+/// ones behind `Universe::cells`. Returns `u32::MAX` if their lengths
+/// differ.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub fn hamming_distance(a: &[u32], b: &[u32]) -> u32 {
+    if a.len() != b.len() {
+        return u32::MAX;
+    }
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
+/// What lies beyond the edges of the universe.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoundaryMode {
+    /// Opposite edges are joined, so patterns wrap around.
+    Toroidal = 0,
+    /// Off-grid neighbours are fixed to the background state.
+    Bounded = 1,
+}
+
+/// Which cells count as a cell's neighbours for rules that count them.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NeighborhoodKind {
+    /// The eight cells around it.
+    Moore = 0,
+    /// The four cells sharing an edge with it.
+    VonNeumann = 1,
+    /// The twelve cells at most two steps away along rows and columns
+    /// combined.
+    VonNeumannRadius2 = 2,
+}
+
+/// Offsets of the Moore neighbourhood.
+const MOORE: [(i64, i64); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Offsets of the von Neumann neighbourhood.
+const VON_NEUMANN: [(i64, i64); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// Offsets of the radius-2 von Neumann neighbourhood.
+const VON_NEUMANN_2: [(i64, i64); 12] = [
+    (-2, 0),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -2),
+    (0, -1),
+    (0, 1),
+    (0, 2),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (2, 0),
+];
+
+/// What `resize` does with the existing cells.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResizeMode {
+    /// Keep cells at the same coordinates, cropping or padding at the
+    /// bottom and right.
+    PreserveTopLeft = 0,
+    /// Keep the old board centred in the new one, cropping or padding
+    /// evenly on all sides.
+    PreserveCentered = 1,
+    /// Start over with an all-dead board, like `set_width`.
+    Clear = 2,
+}
+
+/// Everything `tick` changes, so speculative runs can be undone exactly.
+#[derive(Clone)]
+struct SavedState {
+    generation: u64,
+    cells: FixedBitSet,
+    prev_cells: FixedBitSet,
+    ages: Vec<u32>,
+    heatmap: Option<Vec<u16>>,
+    states: Option<Vec<u8>>,
+    noise: Option<Noise>,
+    ants: Vec<Ant>,
+    sand: Option<Vec<u32>>,
+    sea: Option<Vec<Creature>>,
+    colors: Option<Vec<u8>>,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone)]
 pub struct Universe {
     width: u32,
     height: u32,
+    generation: u64,
     cells: FixedBitSet,
+    /// Scratch buffer each tick computes the next generation into before
+    /// it is swapped in. Its contents between ticks are meaningless.
     temp_cells: FixedBitSet,
+    /// The generation before the last tick.
+    prev_cells: FixedBitSet,
+    ages: Vec<u32>,
+    heatmap: Option<Vec<u16>>,
+    /// Dying states under a Generations rule, `None` for two-state rules.
+    /// Only values of 2 and up are meaningful; see `cell_state`.
+    states: Option<Vec<u8>>,
+    /// Grains on each cell under the Sandpile rule, `None` under others.
+    sand: Option<Vec<u32>>,
+    /// The counters of each fish and shark under Wa-Tor, `None` under
+    /// other rules.
+    sea: Option<Vec<Creature>>,
+    /// The colour of each cell under Immigration and QuadLife, `None`
+    /// under other rules; see `cell_color`.
+    colors: Option<Vec<u8>>,
+    /// Probabilities for stochastic transitions, `None` to run the rule
+    /// as written.
+    noise: Option<Noise>,
+    /// The stream `reset_random` draws from, and that noise is seeded from
+    /// unless given a seed of its own; see `set_seed`.
+    rng: Rng,
+    /// Turmites walking the board; while there are any they replace the
+    /// rule.
+    ants: Vec<Ant>,
+    /// The last tick, if it went cell by cell, for the next one to skip
+    /// cells that can't change; see `active_cells`.
+    last_tick: Option<LastTick>,
+    /// Live neighbours of each cell under Life-like rules that go cell by
+    /// cell, `None` otherwise.
+    neighbor_counts: Option<NeighborCounts>,
+    engine: Engine,
+    /// Nodes and results kept between Hashlife steps, `None` until the
+    /// first one.
+    hashlife: Option<Hashlife>,
+    /// The device `Engine::Gpu` runs on, `None` until `set_gpu`.
+    #[cfg(all(feature = "gpu", web_sys_unstable_apis, target_arch = "wasm32"))]
+    gpu: Option<gpu::GpuContext>,
+    recording: Option<Vec<u8>>,
+    /// Copies of the cells for another thread to read, `None` unless
+    /// `share_cells` was called.
+    shared: Option<SharedCells>,
+    ghost: Option<Ghost>,
+    /// Boards put aside with `save_snapshot`.
+    snapshots: Snapshots,
+    /// Edits `undo` can take back.
+    undo: UndoStack,
+    /// The last few generations, for `step_back`.
+    rewind: Rewind,
+    /// Most bytes undo, history and snapshots may keep between them.
+    history_budget: usize,
+    pending_load: Option<PendingLoad>,
+    frozen_border: u32,
+    boundary: BoundaryMode,
+    background: bool,
+    include_center: bool,
+    /// Whether `tick_n` stops once no cells are alive.
+    stop_when_extinct: bool,
+    /// Whether `tick_n` stops once a tick leaves the cells unchanged.
+    stop_when_stable: bool,
+    neighborhood: NeighborhoodKind,
+    rule: Automaton,
+    /// `rule` as a `LifeTable` if it is Life-like, `None` otherwise.
+    life_table: Option<LifeTable>,
 }
 
 impl Cell {
+    #[allow(dead_code)]
     fn toggle(&mut self) {
         *self = match *self {
             Cell::Alive => Cell::Dead,
@@ -73,12 +388,124 @@ impl Universe {
         (row * self.width + col) as usize
     }
 
+    /// Whether the last tick should stop `tick_n`: it left no cells alive
+    /// or changed none, and stopping then is turned on.
+    fn settled(&self) -> bool {
+        (self.stop_when_extinct && self.cells.is_clear())
+            || (self.stop_when_stable && self.cells == self.prev_cells)
+    }
+
+    /// Index of `(row, col)`, or an error naming the cell if it is off the
+    /// board.
+    fn checked_index(&self, row: u32, col: u32) -> Result<usize, Error> {
+        if row < self.height && col < self.width {
+            Ok(self.get_index(row, col))
+        } else {
+            Err(Error::new(format!(
+                "cell ({}, {}) is outside the {} by {} board",
+                row, col, self.width, self.height
+            )))
+        }
+    }
+
     fn get_row_index(&self, row: u32) -> usize {
         (row * self.width) as usize
     }
 
+    /// Index of the cell `(d_row, d_col)` away from `(row, col)`, wrapping
+    /// in toroidal mode. `None` if it lies off the grid in bounded mode.
+    fn offset_index(&self, row: u32, col: u32, d_row: i64, d_col: i64) -> Option<usize> {
+        let r = row as i64 + d_row;
+        let c = col as i64 + d_col;
+        let (height, width) = (self.height as i64, self.width as i64);
+        match self.boundary {
+            BoundaryMode::Toroidal => {
+                Some(self.get_index(r.rem_euclid(height) as u32, c.rem_euclid(width) as u32))
+            }
+            BoundaryMode::Bounded if r < 0 || c < 0 || r >= height || c >= width => None,
+            BoundaryMode::Bounded => Some(self.get_index(r as u32, c as u32)),
+        }
+    }
+
+    fn stamp_cell(&mut self, row: u32, col: u32, d_row: i64, d_col: i64, alive: bool) {
+        if let Some(idx) = self.offset_index(row, col, d_row, d_col) {
+            self.touch(idx);
+            self.cells.set(idx, alive);
+        }
+    }
+
+    fn in_frozen_border(&self, row: u32, col: u32) -> bool {
+        let t = self.frozen_border;
+        row < t
+            || col < t
+            || row >= self.height.saturating_sub(t)
+            || col >= self.width.saturating_sub(t)
+    }
+
+    /// The 3x3 block centred on `(row, col)` as a mask with bit
+    /// `3 * d_row + d_col` set for each live cell, `(d_row, d_col)` running
+    /// from the top-left corner. Off-grid cells in bounded mode count as
+    /// the background state.
+    fn neighborhood_mask(&self, row: u32, col: u32) -> u16 {
+        let mut mask = 0;
+        for d_row in 0..3 {
+            for d_col in 0..3 {
+                let alive = match self.offset_index(row, col, d_row - 1, d_col - 1) {
+                    Some(idx) => self.cells[idx],
+                    None => self.background,
+                };
+                mask |= (alive as u16) << (3 * d_row + d_col);
+            }
+        }
+        mask
+    }
+
+    /// The board as one byte per cell, 0 or 1, with a one-cell border
+    /// around it holding copies of the opposite edges in toroidal mode and
+    /// the background state in bounded mode, so the 3x3 block around any
+    /// cell can be read at fixed offsets without wrapping. Rows are
+    /// `width + 2` bytes long and cell `(row, col)` is at
+    /// `(row + 1) * (width + 2) + col + 1`.
+    pub(crate) fn padded_cells(&self) -> Vec<u8> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let padded_width = width + 2;
+        let mut padded = vec![self.background as u8; padded_width * (height + 2)];
+        for row in 0..height {
+            let start = (row + 1) * padded_width + 1;
+            for (col, cell) in padded[start..start + width].iter_mut().enumerate() {
+                *cell = self.cells[row * width + col] as u8;
+            }
+        }
+        if self.boundary == BoundaryMode::Toroidal {
+            for row in 1..=height {
+                let start = row * padded_width;
+                padded[start] = padded[start + width];
+                padded[start + width + 1] = padded[start + 1];
+            }
+            padded.copy_within(height * padded_width..(height + 1) * padded_width, 0);
+            padded.copy_within(padded_width..2 * padded_width, (height + 1) * padded_width);
+        }
+        padded
+    }
+
+    /// Live cells in the neighbourhood of `(row, column)`, plus the cell
+    /// itself when `include_center` is set.
     fn live_neighbor_count(&self, row: u32, column: u32) -> u8 {
-        let mut count = 0;
+        let center = (self.include_center && self.cells[self.get_index(row, column)]) as u8;
+        match self.neighborhood {
+            NeighborhoodKind::Moore => {}
+            NeighborhoodKind::VonNeumann => {
+                return center + self.offsets_neighbor_count(row, column, &VON_NEUMANN)
+            }
+            NeighborhoodKind::VonNeumannRadius2 => {
+                return center + self.offsets_neighbor_count(row, column, &VON_NEUMANN_2)
+            }
+        }
+        if self.boundary == BoundaryMode::Bounded {
+            return center + self.bounded_neighbor_count(row, column);
+        }
+
+        let mut count = center;
 
         let north = if row == 0 { self.height - 1 } else { row - 1 };
         let south = if row == self.height - 1 { 0 } else { row + 1 };
@@ -113,6 +540,366 @@ impl Universe {
         count
     }
 
+    fn bounded_neighbor_count(&self, row: u32, column: u32) -> u8 {
+        let mut count = 0;
+        for d_row in [-1i64, 0, 1].iter().cloned() {
+            for d_col in [-1i64, 0, 1].iter().cloned() {
+                if d_row == 0 && d_col == 0 {
+                    continue;
+                }
+                let r = row as i64 + d_row;
+                let c = column as i64 + d_col;
+                let alive = if r < 0 || c < 0 || r >= self.height as i64 || c >= self.width as i64 {
+                    self.background
+                } else {
+                    self.cells[self.get_index(r as u32, c as u32)]
+                };
+                count += alive as u8;
+            }
+        }
+        count
+    }
+
+    /// Offsets of the cells in the current neighbourhood.
+    fn neighborhood_offsets(&self) -> &'static [(i64, i64)] {
+        match self.neighborhood {
+            NeighborhoodKind::Moore => &MOORE,
+            NeighborhoodKind::VonNeumann => &VON_NEUMANN,
+            NeighborhoodKind::VonNeumannRadius2 => &VON_NEUMANN_2,
+        }
+    }
+
+    /// Live cells at the given offsets from `(row, column)`, off-grid ones
+    /// in bounded mode counting as the background state.
+    fn offsets_neighbor_count(&self, row: u32, column: u32, offsets: &[(i64, i64)]) -> u8 {
+        offsets
+            .iter()
+            .map(
+                |&(d_row, d_col)| match self.offset_index(row, column, d_row, d_col) {
+                    Some(idx) => self.cells[idx] as u8,
+                    None => self.background as u8,
+                },
+            )
+            .sum()
+    }
+
+    /// RGBA colour of a cell in `state`; see `render_rgba`.
+    fn state_color(&self, state: u8) -> [u8; 4] {
+        const ALIVE: [u8; 4] = [0, 0, 0, 255];
+        const DEAD: [u8; 4] = [255, 255, 255, 255];
+        const DYING: [u8; 4] = [40, 100, 230, 255];
+        const WIREWORLD: [[u8; 4]; 4] = [
+            DEAD,
+            [40, 100, 230, 255],
+            [220, 50, 40, 255],
+            [240, 190, 40, 255],
+        ];
+        const SANDPILE: [[u8; 4]; 5] = [
+            DEAD,
+            [250, 220, 120, 255],
+            [240, 150, 50, 255],
+            [200, 60, 30, 255],
+            ALIVE,
+        ];
+        const FOREST_FIRE: [[u8; 4]; 3] = [DEAD, [30, 140, 50, 255], [240, 90, 20, 255]];
+        const WATOR: [[u8; 4]; 3] = [[20, 60, 140, 255], [250, 200, 40, 255], [90, 90, 90, 255]];
+        match self.rule {
+            Automaton::Wireworld(_) => return WIREWORLD[state as usize],
+            Automaton::ForestFire(_) => return FOREST_FIRE[state as usize],
+            Automaton::WaTor(_) => return WATOR[state as usize],
+            Automaton::Sandpile(_) => return SANDPILE[state as usize],
+            _ => {}
+        }
+        match state {
+            0 => DEAD,
+            1 => ALIVE,
+            state => {
+                let faded = (state - 2) as u32;
+                let dying_states = (self.rule.states() - 2) as u32;
+                let mut color = DYING;
+                for (channel, &dead) in color.iter_mut().zip(&DEAD) {
+                    let (from, to) = (*channel as u32, dead as u32);
+                    *channel = (from + (to - from) * faded / dying_states) as u8;
+                }
+                color
+            }
+        }
+    }
+
+    fn write_rgba_frame(&self, cell_size: u32, out: &mut Vec<u8>) {
+        for row in 0..self.height {
+            let start = out.len();
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                let color = match self.cell_color(idx) {
+                    0 => self.state_color(self.cell_state(idx)),
+                    color => colored::COLOR_RGBA[color as usize - 1],
+                };
+                for _ in 0..cell_size {
+                    out.extend_from_slice(&color);
+                }
+            }
+            // Repeat the finished pixel row for the rest of the cell height.
+            let end = out.len();
+            for _ in 1..cell_size {
+                out.extend_from_within(start..end);
+            }
+        }
+    }
+
+    /// Allocate fresh, all-dead per-cell buffers for the current size.
+    fn reallocate(&mut self) {
+        let size = (self.width * self.height) as usize;
+        self.generation = 0;
+        self.cells = FixedBitSet::with_capacity(size);
+        self.temp_cells = FixedBitSet::with_capacity(size);
+        self.prev_cells = FixedBitSet::with_capacity(size);
+        self.ages = vec![0; size];
+        if self.heatmap.is_some() {
+            self.heatmap = Some(vec![0; size]);
+        }
+        if self.states.is_some() {
+            self.states = Some(vec![0; size]);
+        }
+        if self.sand.is_some() {
+            self.sand = Some(vec![0; size]);
+        }
+        if self.sea.is_some() {
+            self.sea = Some(vec![Creature::default(); size]);
+        }
+        if self.colors.is_some() {
+            self.colors = Some(vec![0; size]);
+        }
+        if self.shared.is_some() {
+            self.shared = Some(SharedCells::new(&self.cells));
+        }
+        self.ants.clear();
+        self.undo.clear();
+        self.rewind.clear();
+    }
+
+    /// Copy every per-cell buffer into one sized for `width` by `height`,
+    /// moving cell `(row, col)` to `(row + d_row, col + d_col)`. Cells moved
+    /// off the new board are dropped and new cells start dead.
+    fn remap(&mut self, width: u32, height: u32, d_row: i64, d_col: i64) {
+        let size = (width * height) as usize;
+        let mut cells = FixedBitSet::with_capacity(size);
+        let mut prev_cells = FixedBitSet::with_capacity(size);
+        let mut ages = vec![0; size];
+        let mut heatmap = self.heatmap.as_ref().map(|_| vec![0; size]);
+        let mut states = self.states.as_ref().map(|_| vec![0; size]);
+        let mut sand = self.sand.as_ref().map(|_| vec![0; size]);
+        let mut sea = self.sea.as_ref().map(|_| vec![Creature::default(); size]);
+        let mut colors = self.colors.as_ref().map(|_| vec![0; size]);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let (new_row, new_col) = (row as i64 + d_row, col as i64 + d_col);
+                if new_row < 0 || new_col < 0 || new_row >= height as i64 || new_col >= width as i64
+                {
+                    continue;
+                }
+                let old = self.get_index(row, col);
+                let new = new_row as usize * width as usize + new_col as usize;
+                cells.set(new, self.cells[old]);
+                prev_cells.set(new, self.prev_cells[old]);
+                ages[new] = self.ages[old];
+                if let (Some(new_heat), Some(old_heat)) = (&mut heatmap, &self.heatmap) {
+                    new_heat[new] = old_heat[old];
+                }
+                if let (Some(new_states), Some(old_states)) = (&mut states, &self.states) {
+                    new_states[new] = old_states[old];
+                }
+                if let (Some(new_sand), Some(old_sand)) = (&mut sand, &self.sand) {
+                    new_sand[new] = old_sand[old];
+                }
+                if let (Some(new_sea), Some(old_sea)) = (&mut sea, &self.sea) {
+                    new_sea[new] = old_sea[old];
+                }
+                if let (Some(new_colors), Some(old_colors)) = (&mut colors, &self.colors) {
+                    new_colors[new] = old_colors[old];
+                }
+            }
+        }
+        self.remap_ants(width, height, d_row, d_col);
+        self.undo.clear();
+        self.rewind.clear();
+        self.width = width;
+        self.height = height;
+        self.cells = cells;
+        self.prev_cells = prev_cells;
+        self.temp_cells = FixedBitSet::with_capacity(size);
+        self.ages = ages;
+        self.heatmap = heatmap;
+        self.states = states;
+        self.sand = sand;
+        self.sea = sea;
+        self.colors = colors;
+        if self.shared.is_some() {
+            self.shared = Some(SharedCells::new(&self.cells));
+        }
+    }
+
+    fn save_state(&self) -> SavedState {
+        SavedState {
+            generation: self.generation,
+            cells: self.cells.clone(),
+            prev_cells: self.prev_cells.clone(),
+            ages: self.ages.clone(),
+            heatmap: self.heatmap.clone(),
+            states: self.states.clone(),
+            noise: self.noise.clone(),
+            ants: self.ants.clone(),
+            sand: self.sand.clone(),
+            sea: self.sea.clone(),
+            colors: self.colors.clone(),
+        }
+    }
+
+    fn restore_state(&mut self, state: SavedState) {
+        self.generation = state.generation;
+        self.cells = state.cells;
+        self.prev_cells = state.prev_cells;
+        self.ages = state.ages;
+        self.heatmap = state.heatmap;
+        self.states = state.states;
+        self.noise = state.noise;
+        self.ants = state.ants;
+        self.sand = state.sand;
+        self.sea = state.sea;
+        self.colors = state.colors;
+    }
+
+    /// Whether `pattern` appears cell for cell with its top-left corner at
+    /// `(row, col)`. Windows crossing an edge wrap in toroidal mode and
+    /// never match in bounded mode.
+    fn matches_at(&self, pattern: &Pattern, row: u32, col: u32) -> bool {
+        if self.boundary == BoundaryMode::Bounded
+            && (row + pattern.rows > self.height || col + pattern.cols > self.width)
+        {
+            return false;
+        }
+        for d_row in 0..pattern.rows {
+            let cell_row = (row + d_row) % self.height;
+            for d_col in 0..pattern.cols {
+                let cell_col = (col + d_col) % self.width;
+                if self.cells[self.get_index(cell_row, cell_col)] != pattern.get(d_row, d_col) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn contains_pattern(&self, orientations: &[Pattern]) -> bool {
+        (0..self.height).any(|row| {
+            (0..self.width).any(|col| {
+                orientations
+                    .iter()
+                    .any(|pattern| self.matches_at(pattern, row, col))
+            })
+        })
+    }
+
+    /// Compute the generation after the current one: the live cells into
+    /// `next`, which must be as long as `cells`, and every cell's state
+    /// into `states` if given.
+    fn next_generation_into(&self, next: &mut FixedBitSet, states: Option<&mut [u8]>) {
+        match &self.rule {
+            Automaton::Life(rule) if states.is_none() && self.swar_applies() => {
+                self.evolve_swar(*rule, next)
+            }
+            Automaton::Colored(rule) if self.swar_applies() => self.evolve_swar(rule.life(), next),
+            Automaton::Life(rule) => match &self.life_table {
+                Some(table) => self.evolve(table, next, states),
+                None => self.evolve(rule, next, states),
+            },
+            Automaton::Colored(rule) => match &self.life_table {
+                Some(table) => self.evolve(table, next, states),
+                None => self.evolve(&rule.life(), next, states),
+            },
+            Automaton::Isotropic(rule) => self.evolve(rule, next, states),
+            Automaton::LargerThanLife(rule) => self.evolve(rule, next, states),
+            Automaton::Table(rule) => self.evolve(rule, next, states),
+            Automaton::Margolus(rule) => self.evolve(rule, next, states),
+            Automaton::Wireworld(rule) => self.evolve(rule, next, states),
+            Automaton::Elementary(rule) => self.evolve(rule, next, states),
+            Automaton::ForestFire(rule) => self.evolve(rule, next, states),
+            Automaton::RuleFile(rule) => self.evolve(&**rule, next, states),
+            Automaton::Sandpile(_) => sandpile::paint_sand(&self.toppled(), next, states),
+            Automaton::WaTor(rule) => wator::paint_sea(&self.swum(*rule).0, next, states),
+        }
+    }
+
+    /// Advance one generation without recording it.
+    fn step(&mut self) {
+        let mut next = std::mem::take(&mut self.temp_cells);
+        let mut last_tick = None;
+        if !self.ants.is_empty() {
+            next.clone_from(&self.cells);
+            self.move_ants(&mut next);
+        } else if self.sand.is_some() {
+            let grains = self.toppled();
+            sandpile::paint_sand(&grains, &mut next, self.states.as_deref_mut());
+            self.sand = Some(grains);
+        } else if let Automaton::WaTor(rule) = self.rule {
+            let (species, creatures) = self.swum(rule);
+            wator::paint_sea(&species, &mut next, self.states.as_deref_mut());
+            self.sea = Some(creatures);
+        } else if let Some(rule) = self.hashlife_rule() {
+            self.neighbor_counts = None;
+            self.hashlife_into(rule, 0, &mut next);
+        } else {
+            let life_like = matches!(self.rule, Automaton::Life(_) | Automaton::Colored(_));
+            if life_like && !(self.states.is_none() && self.swar_applies()) {
+                self.update_neighbor_counts();
+            } else {
+                self.neighbor_counts = None;
+            }
+            let mut states = self.states.as_ref().map(|states| vec![0; states.len()]);
+            self.next_generation_into(&mut next, states.as_deref_mut());
+            if states.is_none() && self.noise.is_none() {
+                last_tick = Some(self.last_tick(&next));
+            }
+            if let Some(mut noise) = self.noise.take() {
+                self.apply_noise(&mut noise, &mut next, states.as_deref_mut());
+                self.noise = Some(noise);
+            }
+            self.states = states;
+            if let Automaton::Colored(rule) = self.rule {
+                self.colors = Some(self.recolored(rule, &next));
+            }
+        }
+        self.last_tick = last_tick;
+        self.finish_step(next, 1);
+    }
+
+    /// Make `next`, `generations` on from the current board, the current
+    /// board, ageing cells and adding to the heat map as for one tick.
+    fn finish_step(&mut self, next: FixedBitSet, generations: u64) {
+        self.temp_cells = next;
+        for (idx, age) in self.ages.iter_mut().enumerate() {
+            *age = if self.cells[idx] && self.temp_cells[idx] {
+                age.saturating_add(1)
+            } else {
+                0
+            };
+        }
+        if let Some(heatmap) = &mut self.heatmap {
+            for idx in self.temp_cells.ones() {
+                heatmap[idx] = heatmap[idx].saturating_add(1);
+            }
+        }
+        // Rotate the buffers rather than copying: the current generation
+        // becomes the previous one, the new one becomes current, and the
+        // old previous generation is left as scratch for the next tick.
+        std::mem::swap(&mut self.prev_cells, &mut self.cells);
+        std::mem::swap(&mut self.cells, &mut self.temp_cells);
+        self.generation = self.generation.saturating_add(generations);
+        if let Some(shared) = &mut self.shared {
+            shared.publish(&self.cells);
+        }
+    }
+
     /// Get the dead and alive values of the entire universe.
     pub fn get_cells(&self) -> Vec<Cell> {
         let mut res = vec![];
@@ -130,64 +917,357 @@ impl Universe {
         res
     }
 
+    /// The live cell that has survived the most consecutive generations, as
+    /// `(row, col, age)`, or `None` if nothing is alive. Ties go to the first
+    /// cell in row-major order.
+    pub fn oldest_cell(&self) -> Option<(u32, u32, u64)> {
+        let mut oldest: Option<(usize, u32)> = None;
+        for idx in self.cells.ones() {
+            let age = self.ages[idx];
+            if oldest.is_none_or(|(_, best)| age > best) {
+                oldest = Some((idx, age));
+            }
+        }
+        oldest.map(|(idx, age)| {
+            let idx = idx as u32;
+            (idx / self.width, idx % self.width, age as u64)
+        })
+    }
+
+    /// A universe just large enough for `pattern`, at least 1 by 1, with
+    /// the pattern's live cells set and everything else as `new` leaves it.
+    fn from_pattern(pattern: &Pattern) -> Universe {
+        let mut universe = Universe::new();
+        universe.set_width(pattern.cols.max(1));
+        universe.set_height(pattern.rows.max(1));
+        for row in 0..pattern.rows {
+            for col in 0..pattern.cols {
+                if pattern.get(row, col) {
+                    let idx = universe.get_index(row, col);
+                    universe.cells.insert(idx);
+                }
+            }
+        }
+        universe
+    }
+
+    /// Overwrite the cells under `pattern` with its top-left corner at
+    /// `(row, col)`, recording each one. Cells past an edge wrap or are
+    /// dropped according to the boundary mode.
+    fn stamp_pattern(&mut self, row: u32, col: u32, pattern: &Pattern) {
+        for d_row in 0..pattern.rows {
+            for d_col in 0..pattern.cols {
+                let alive = pattern.get(d_row, d_col);
+                if let Some(idx) = self.offset_index(row, col, d_row as i64, d_col as i64) {
+                    self.record(Op::Set(idx as u32, alive));
+                    self.touch(idx);
+                    self.cells.set(idx, alive);
+                }
+            }
+        }
+    }
+
+    /// Copy out the bounding box of the live cells. Empty when nothing is
+    /// alive.
+    fn extract_pattern(&self) -> Pattern {
+        let (mut min_row, mut min_col) = (u32::MAX, u32::MAX);
+        let (mut max_row, mut max_col) = (0, 0);
+        for idx in self.cells.ones() {
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            min_row = min_row.min(row);
+            max_row = max_row.max(row);
+            min_col = min_col.min(col);
+            max_col = max_col.max(col);
+        }
+        if min_row == u32::MAX {
+            return Pattern::new(0, 0);
+        }
+
+        let mut pattern = Pattern::new(max_row - min_row + 1, max_col - min_col + 1);
+        for idx in self.cells.ones() {
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            pattern.set(row - min_row, col - min_col, true);
+        }
+        pattern
+    }
+
     /// Set cells to be alive in a universe by passing the row and column
     /// of each cell as an array.
     pub fn set_cells(&mut self, cells: &[(u32, u32)]) {
+        self.begin_edit();
         for (row, col) in cells.iter().cloned() {
             let idx = self.get_index(row, col);
+            self.touch(idx);
             self.cells.set(idx, true);
         }
+        self.end_edit();
     }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 impl Universe {
     pub fn tick(&mut self) {
-        self.temp_cells.set_range(.., false);
-        for row in 0..self.height {
-            for col in 0..self.width {
-                let idx = self.get_index(row, col);
-                let cell = self.cells[idx];
-                let live_neighbors = self.live_neighbor_count(row, col);
-                // log!(
-                //     "cell[{}, {}] is initially {:?} and has {} live neighbors",
-                //     row,
-                //     col,
-                //     cell,
-                //     live_neighbors
-                // );
-                self.temp_cells.set(
-                    idx,
-                    match (cell, live_neighbors) {
-                        (true, x) if x < 2 => false,
-                        (true, 2) | (true, 3) => true,
-                        (true, x) if x > 3 => false,
-                        (false, 3) => true,
-                        (otherwise, _) => otherwise,
-                    },
-                );
-                // log!("    it becomes {:?}", next[idx]);
-            }
-        }
-        self.cells.clone_from(&self.temp_cells);
+        self.record(Op::Tick);
+        self.remember();
+        self.step();
+    }
+
+    /// Tick `ticks` times, timing the run with `performance.now()`, and
+    /// return the rate in ticks per second, for comparing builds and
+    /// settings. Returns infinity if the run was too quick to measure.
+    pub fn benchmark_ticks(&mut self, ticks: u32) -> f64 {
+        let start = utils::now();
+        for _ in 0..ticks {
+            self.tick();
+        }
+        ticks as f64 * 1000.0 / (utils::now() - start)
+    }
+
+    /// Tick up to `generations` times in one call and return how many
+    /// ticks ran. Stops early after a tick that leaves no cells alive if
+    /// `set_stop_when_extinct` is on, or one that leaves the cells as they
+    /// were if `set_stop_when_stable` is.
+    pub fn tick_n(&mut self, generations: u32) -> u32 {
+        for ran in 1..=generations {
+            self.tick();
+            if self.settled() {
+                return ran;
+            }
+        }
+        generations
+    }
+
+    /// Tick until `budget` milliseconds have passed, timed with
+    /// `performance.now()`, and return how many ticks ran, for running as
+    /// fast as a frame allows. The last tick may finish after the budget.
+    /// Stops early under the same conditions as `tick_n`.
+    pub fn tick_for_millis(&mut self, budget: f64) -> u32 {
+        let deadline = utils::now() + budget;
+        let mut ran = 0;
+        while utils::now() < deadline {
+            self.tick();
+            ran += 1;
+            if self.settled() {
+                break;
+            }
+        }
+        ran
+    }
+
+    pub fn stop_when_extinct(&self) -> bool {
+        self.stop_when_extinct
+    }
+
+    /// Have `tick_n` stop once the board dies out. Off by default.
+    pub fn set_stop_when_extinct(&mut self, stop: bool) {
+        self.stop_when_extinct = stop;
+    }
+
+    pub fn stop_when_stable(&self) -> bool {
+        self.stop_when_stable
+    }
+
+    /// Have `tick_n` stop once a tick changes no cells, as on a board of
+    /// still lifes. Only live cells are compared, so the dying states of a
+    /// Generations rule, sand and turmites don't count. Off by default.
+    pub fn set_stop_when_stable(&mut self, stop: bool) {
+        self.stop_when_stable = stop;
+    }
+
+    /// Tick until `state_hash` equals `target`, checking the current state
+    /// first and ticking at most `max_steps` times. Returns the generation
+    /// at which the hash matched and leaves the board there, or `None` if it
+    /// never matched (after running all `max_steps` ticks).
+    pub fn tick_until_hash(&mut self, target: u64, max_steps: u32) -> Option<u64> {
+        for step in 0..=max_steps {
+            if step > 0 {
+                self.tick();
+            }
+            if self.state_hash() == target {
+                return Some(self.generation);
+            }
+        }
+        None
+    }
+
+    /// Force the given cells to the given states, then tick.
+    ///
+    /// `cells` holds flat cell indices (`row * width + col`) and `states`
+    /// the matching states (0 dead, anything else alive). The injection is
+    /// applied before neighbours are counted, so it takes part in this
+    /// generation's transition.
+    pub fn tick_with_injection(&mut self, cells: &[u32], states: &[u8]) -> Result<(), Error> {
+        if cells.len() != states.len() {
+            return Err(Error::new("cells and states must have the same length"));
+        }
+        let size = self.width * self.height;
+        if let Some(idx) = cells.iter().find(|&&idx| idx >= size) {
+            return Err(Error::new(format!("cell index {} out of range", idx)));
+        }
+        for (&idx, &state) in cells.iter().zip(states) {
+            self.record(Op::Set(idx, state != 0));
+            self.cells.set(idx as usize, state != 0);
+        }
+        self.tick();
+        Ok(())
+    }
+
+    /// First generation, counting the current one as 0, in which the
+    /// `rows` by `cols` target pattern (one byte per cell, row by row, 0
+    /// dead) appears anywhere on the board in any rotation or reflection.
+    /// The target matches cell for cell, including its dead cells.
+    ///
+    /// Ticks at most `max_steps` times, stopping early once `cancel` is
+    /// cancelled, and always restores the board afterwards. `None` if the
+    /// pattern doesn't appear before then or if `cells` isn't
+    /// `rows * cols` bytes long.
+    pub fn find_pattern(
+        &mut self,
+        rows: u32,
+        cols: u32,
+        cells: &[u8],
+        max_steps: u32,
+        cancel: &CancelToken,
+    ) -> Option<u64> {
+        let orientations = Pattern::from_bytes(rows, cols, cells)?.orientations();
+        let saved = self.save_state();
+        let mut found = None;
+        for generation in 0..=max_steps {
+            if cancel.is_cancelled() {
+                break;
+            }
+            if generation > 0 {
+                self.step();
+            }
+            if self.contains_pattern(&orientations) {
+                found = Some(generation as u64);
+                break;
+            }
+        }
+        self.restore_state(saved);
+        found
+    }
+
+    /// How many more generations the cell at `(row, col)` stays alive
+    /// before it first dies, looking at most `max_steps` ahead. Returns 0
+    /// for a dead cell and `max_steps` if it never dies within the window.
+    ///
+    /// Runs the simulation speculatively and then restores the board and
+    /// every per-cell statistic exactly.
+    pub fn cell_lifespan(&mut self, row: u32, col: u32, max_steps: u32) -> Result<u32, Error> {
+        let idx = self.checked_index(row, col)?;
+        if !self.cells[idx] {
+            return Ok(0);
+        }
+        let saved = self.save_state();
+        let mut lifespan = max_steps;
+        for step in 0..max_steps {
+            self.step();
+            if !self.cells[idx] {
+                lifespan = step;
+                break;
+            }
+        }
+        self.restore_state(saved);
+        Ok(lifespan)
     }
 
     pub fn new() -> Universe {
+        Universe::with_size(256, 256, None)
+    }
+
+    /// Create a `width` by `height` universe, filled according to `fill`
+    /// (by default the same striped pattern as `new`).
+    pub fn with_size(width: u32, height: u32, fill: Option<FillMode>) -> Universe {
         utils::set_panic_hook();
-        let width = 256;
-        let height = 256;
         let size = (width * height) as usize;
-        let mut cells = FixedBitSet::with_capacity(size);
-        let temp_cells = FixedBitSet::with_capacity(size);
-        for i in 0..size {
-            cells.set(i, i % 2 == 0 || i % 7 == 0);
-        }
-        Universe {
+        let mut universe = Universe {
             width,
             height,
-            cells,
-            temp_cells
+            generation: 0,
+            cells: FixedBitSet::with_capacity(size),
+            temp_cells: FixedBitSet::with_capacity(size),
+            prev_cells: FixedBitSet::with_capacity(size),
+            ages: vec![0; size],
+            heatmap: None,
+            states: None,
+            sand: None,
+            sea: None,
+            colors: None,
+            noise: None,
+            rng: Rng::new(utils::random_seed()),
+            ants: Vec::new(),
+            last_tick: None,
+            neighbor_counts: None,
+            engine: Engine::Flat,
+            hashlife: None,
+            #[cfg(all(feature = "gpu", web_sys_unstable_apis, target_arch = "wasm32"))]
+            gpu: None,
+            recording: None,
+            shared: None,
+            ghost: None,
+            snapshots: Snapshots::new(),
+            undo: UndoStack::default(),
+            rewind: Rewind::default(),
+            history_budget: usize::MAX,
+            pending_load: None,
+            frozen_border: 0,
+            boundary: BoundaryMode::Toroidal,
+            background: false,
+            include_center: false,
+            stop_when_extinct: false,
+            stop_when_stable: false,
+            neighborhood: NeighborhoodKind::Moore,
+            rule: Automaton::Life(LifeRule::CONWAY),
+            life_table: Some(LifeTable::new(LifeRule::CONWAY)),
+        };
+        match fill.unwrap_or(FillMode::Pattern) {
+            FillMode::Pattern => {
+                for i in 0..size {
+                    universe.cells.set(i, i % 2 == 0 || i % 7 == 0);
+                }
+            }
+            FillMode::Clear => {}
+            FillMode::Random => universe.reset_random(),
+        }
+        universe.prev_cells.clone_from(&universe.cells);
+        universe
+    }
+
+    /// Create a universe from one byte per cell, row by row (0 dead,
+    /// anything else alive). Errors unless there are `width * height`
+    /// bytes.
+    pub fn from_cells(width: u32, height: u32, cells: &[u8]) -> Result<Universe, Error> {
+        if cells.len() != (width * height) as usize {
+            return Err(Error::new(format!(
+                "expected {} cells, got {}",
+                width * height,
+                cells.len()
+            )));
+        }
+        let mut universe = Universe::with_size(width, height, Some(FillMode::Clear));
+        for (idx, &state) in cells.iter().enumerate() {
+            universe.cells.set(idx, state != 0);
         }
+        universe.prev_cells.clone_from(&universe.cells);
+        Ok(universe)
+    }
+
+    /// Create a universe from packed cell words in the layout described on
+    /// `cells`. Errors unless there are `ceil(width * height / 32)` words.
+    pub fn from_words(width: u32, height: u32, words: &[u32]) -> Result<Universe, Error> {
+        let mut universe = Universe::with_size(width, height, Some(FillMode::Clear));
+        universe.set_cells_from_words(words)?;
+        universe.prev_cells.clone_from(&universe.cells);
+        Ok(universe)
+    }
+
+    /// An independent copy of this universe, for branching a simulation
+    /// without touching the original. Everything is copied: cells, the
+    /// generation counter, per-cell statistics, settings, and any ghost,
+    /// recording or load in progress.
+    pub fn duplicate(&self) -> Universe {
+        self.clone()
     }
 
     /// Set the width of the universe.
@@ -195,10 +1275,7 @@ impl Universe {
     /// Resets all cells to the dead state.
     pub fn set_width(&mut self, width: u32) {
         self.width = width;
-        let size = (width * self.height) as usize;
-        let mut cells = FixedBitSet::with_capacity(size);
-        cells.set_range(.., false);
-        self.cells = cells;
+        self.reallocate();
     }
 
     /// Set the height of the universe.
@@ -206,52 +1283,376 @@ impl Universe {
     /// Resets all cells to the dead state.
     pub fn set_height(&mut self, height: u32) {
         self.height = height;
-        let size = (self.width * height) as usize;
-        let mut cells = FixedBitSet::with_capacity(size);
-        cells.set_range(.., false);
-        self.cells = cells;
+        self.reallocate();
+    }
+
+    /// Change the size of the universe, keeping the cells as `mode` says.
+    ///
+    /// The preserving modes carry over cell ages, the heatmap and the
+    /// generation count along with the cells; `ResizeMode::Clear` resets
+    /// them all. Under every mode the rule, the settings, the random
+    /// stream from `set_seed` and any noise with its own stream carry on
+    /// where they were, so a seeded run stays reproducible across a
+    /// resize. Undo and step-back history are forgotten.
+    pub fn resize(&mut self, width: u32, height: u32, mode: ResizeMode) {
+        let (d_row, d_col) = match mode {
+            ResizeMode::PreserveTopLeft => (0, 0),
+            ResizeMode::PreserveCentered => (
+                (height as i64 - self.height as i64) / 2,
+                (width as i64 - self.width as i64) / 2,
+            ),
+            ResizeMode::Clear => {
+                self.width = width;
+                self.height = height;
+                return self.reallocate();
+            }
+        };
+        self.remap(width, height, d_row, d_col);
     }
 
-    pub fn toggle_cell(&mut self, row: u32, col: u32) {
-        let idx = self.get_index(row, col);
+    /// Freeze a band of `thickness` cells along every edge.
+    ///
+    /// Frozen cells are set to the background state (dead by default) and
+    /// stay there on every tick, acting as walls so patterns can neither
+    /// wrap around nor interact across the edges. Pass `0` to remove the
+    /// border again.
+    pub fn set_frozen_border(&mut self, thickness: u32) {
+        self.frozen_border = thickness;
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.in_frozen_border(row, col) {
+                    let idx = self.get_index(row, col);
+                    self.cells.set(idx, self.background);
+                }
+            }
+        }
+    }
+
+    pub fn toggle_cell(&mut self, row: u32, col: u32) -> Result<(), Error> {
+        let idx = self.checked_index(row, col)?;
+        self.record(Op::Toggle(row, col));
+        self.begin_edit();
+        self.touch(idx);
         let cell_state = self.cells[idx];
         self.cells.set(idx, !cell_state);
+        self.end_edit();
+        Ok(())
+    }
+
+    /// Set the cell at `(row, col)` to `state`, whatever it was before.
+    pub fn set_cell(&mut self, row: u32, col: u32, state: Cell) -> Result<(), Error> {
+        let idx = self.checked_index(row, col)?;
+        self.record(Op::Set(idx as u32, state == Cell::Alive));
+        self.begin_edit();
+        self.touch(idx);
+        self.cells.set(idx, state == Cell::Alive);
+        self.end_edit();
+        Ok(())
     }
 
+    /// Set cells to be alive from a flat array of `row, col` pairs, the
+    /// JavaScript counterpart of `set_cells`. Errors, leaving the board
+    /// untouched, if the array has an odd length or a cell is off the board.
+    pub fn set_cells_flat(&mut self, cells: &[u32]) -> Result<(), Error> {
+        if !cells.len().is_multiple_of(2) {
+            return Err(Error::new(format!(
+                "expected row, col pairs, got {} values",
+                cells.len()
+            )));
+        }
+        let pairs: Vec<(u32, u32)> = cells.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+        for &(row, col) in &pairs {
+            self.checked_index(row, col)?;
+        }
+        self.set_cells(&pairs);
+        Ok(())
+    }
+
+    /// Kill the cell at `(row, col)`.
+    pub fn clear_cell(&mut self, row: u32, col: u32) -> Result<(), Error> {
+        self.set_cell(row, col, Cell::Dead)
+    }
+
+    /// The whole board as one byte per cell (0 dead, 1 alive), row by row.
+    /// The JavaScript counterpart of `get_cells`, and the inverse of
+    /// `from_cells`.
+    pub fn get_cells_bytes(&self) -> Vec<u8> {
+        (0..(self.width * self.height) as usize)
+            .map(|idx| self.cells[idx] as u8)
+            .collect()
+    }
+
+    /// State of the cell at `(row, col)`.
+    pub fn get_cell(&self, row: u32, col: u32) -> Result<Cell, Error> {
+        let idx = self.checked_index(row, col)?;
+        Ok(if self.cells[idx] {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        })
+    }
+
+    /// Set every cell in the given rectangle alive or dead. The rectangle
+    /// is clipped to the board.
+    pub fn fill_region(&mut self, row: u32, col: u32, width: u32, height: u32, alive: bool) {
+        let end_row = row.saturating_add(height).min(self.height);
+        let end_col = col.saturating_add(width).min(self.width);
+        self.begin_edit();
+        for r in row..end_row {
+            for c in col..end_col {
+                self.put_state(self.get_index(r, c), alive as u8);
+            }
+        }
+        self.end_edit();
+    }
+
+    /// States of the cells in the given rectangle, one byte per cell
+    /// (0 dead, 1 alive), row by row. The rectangle is clipped to the board,
+    /// so the result may be smaller than `width * height`.
+    pub fn get_cells_in_rect(&self, row: u32, col: u32, width: u32, height: u32) -> Vec<u8> {
+        let end_row = row.saturating_add(height).min(self.height);
+        let end_col = col.saturating_add(width).min(self.width);
+        let mut cells = Vec::new();
+        for r in row..end_row {
+            for c in col..end_col {
+                cells.push(self.cells[self.get_index(r, c)] as u8);
+            }
+        }
+        cells
+    }
+
+    pub fn boundary_mode(&self) -> BoundaryMode {
+        self.boundary
+    }
+
+    pub fn set_boundary_mode(&mut self, mode: BoundaryMode) {
+        self.boundary = mode;
+    }
+
+    pub fn background(&self) -> bool {
+        self.background
+    }
+
+    /// Choose the state of "empty" space: what `reset_clear` fills the
+    /// board with, what the frozen border holds, and what off-grid
+    /// neighbours count as in bounded mode. An alive background lets a
+    /// rule's dual dynamics be studied. Existing cells are left untouched.
+    pub fn set_background(&mut self, alive: bool) {
+        self.background = alive;
+    }
+
+    pub fn include_center(&self) -> bool {
+        self.include_center
+    }
+
+    /// Count a cell's own state towards its neighbour total, turning the
+    /// outer-totalistic rule into a totalistic one whose totals run from 0
+    /// to 9. Off by default, which gives standard Conway behaviour.
+    pub fn set_include_center(&mut self, include: bool) {
+        self.include_center = include;
+    }
+
+    /// Choose which cells count as neighbours for Life-like and
+    /// Generations rules. Rules written in terms of the 3x3 block, such as
+    /// isotropic rules and transition tables, aren't affected. Counts above
+    /// 9 can't appear in a rulestring, so the radius-2 neighbourhood never
+    /// gives births or survivals on 10 or more neighbours.
+    pub fn set_neighborhood(&mut self, kind: NeighborhoodKind) {
+        self.neighborhood = kind;
+    }
+
+    pub fn neighborhood(&self) -> NeighborhoodKind {
+        self.neighborhood
+    }
+
+    /// Fill the board with the background state.
     pub fn reset_clear(&mut self) {
-        self.cells.set_range(.., false);
+        self.begin_edit();
+        for idx in (0..self.cells.len()).step_by(u32::BITS as usize) {
+            self.touch(idx);
+        }
+        self.cells.set_range(.., self.background);
+        self.end_edit();
+    }
+
+    /// Number of generations ticked since the board was created or last
+    /// resized or loaded.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// 64-bit FNV-1a hash of the dimensions and cells, then of every
+    /// cell's state under rules with more than two, stable across runs and
+    /// platforms. Equal boards hash equally, so a known pattern's hash can
+    /// be checked in tests or used as a cache key.
+    pub fn state_hash(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        let fnv = |hash: u64, byte: u8| (hash ^ byte as u64).wrapping_mul(PRIME);
+        let words = self.cells.as_slice();
+        let hash = [self.width, self.height]
+            .iter()
+            .chain(words)
+            .flat_map(|word| word.to_le_bytes())
+            .fold(OFFSET, fnv);
+        match self.states {
+            Some(_) => (0..self.cells.len())
+                .map(|idx| self.cell_state(idx))
+                .fold(hash, fnv),
+            None => hash,
+        }
+    }
+
+    /// Whether `other` has the same size and every cell in the same state.
+    /// Settings, rule and generation are not compared.
+    pub fn equals(&self, other: &Universe) -> bool {
+        self.count_differences(other) == 0
+    }
+
+    /// Number of cells whose state differs from the same cell in `other`,
+    /// or `u32::MAX` if their sizes differ. Compares the packed words
+    /// directly, cell by cell only where either board has dying states.
+    pub fn count_differences(&self, other: &Universe) -> u32 {
+        if (self.width, self.height) != (other.width, other.height) {
+            return u32::MAX;
+        }
+        if self.states.is_none() && other.states.is_none() {
+            return hamming_distance(self.cells.as_slice(), other.cells.as_slice());
+        }
+        (0..self.cells.len())
+            .filter(|&idx| self.cell_state(idx) != other.cell_state(idx))
+            .count() as u32
+    }
+
+    /// Number of live cells, whatever the background.
+    pub fn population(&self) -> u32 {
+        self.cells.count_ones(..) as u32
     }
 
+    /// Number of cells in each state, indexed by state value. Standard Life
+    /// has two states, so this is `[dead, alive]`; Generations rules add
+    /// one count per dying state.
+    pub fn state_counts(&self) -> Vec<u32> {
+        if self.states.is_none() {
+            let alive = self.population();
+            return vec![self.width * self.height - alive, alive];
+        }
+        let mut counts = vec![0; self.rule.states() as usize];
+        for idx in 0..self.cells.len() {
+            counts[self.cell_state(idx) as usize] += 1;
+        }
+        counts
+    }
+
+    /// Make each cell alive with probability one half, drawing from the
+    /// universe's random stream; see `set_seed`.
     pub fn reset_random(&mut self) {
+        for idx in 0..self.cells.len() {
+            self.cells.set(idx, self.rng.next_u64() >> 63 == 1);
+        }
+    }
+
+    /// Restart the universe's random stream from `seed`, so `reset_random`
+    /// and noise set up afterwards without a seed of its own repeat
+    /// exactly. Without a seed the stream differs every time. The stream
+    /// carries on across resizes, loads and rule changes.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Fill each quadrant randomly with its own density of live cells,
+    /// reproducibly for a given `seed`.
+    ///
+    /// `densities` holds four probabilities in the order top-left,
+    /// top-right, bottom-left, bottom-right, each clamped to `0..=1`. The
+    /// board is split at `height / 2` and `width / 2`, so with odd
+    /// dimensions the bottom and right quadrants get the extra row or
+    /// column. Cells in the frozen border are left alone.
+    pub fn reset_random_quadrants(&mut self, densities: &[f64], seed: u64) -> Result<(), Error> {
+        if densities.len() != 4 {
+            return Err(Error::new(format!(
+                "expected 4 densities, got {}",
+                densities.len()
+            )));
+        }
+        self.fill_random(densities, seed);
+        Ok(())
+    }
+
+    /// Randomly refill each quadrant with its own density of live cells,
+    /// as `reset_random_quadrants`. `densities` must hold four values.
+    fn fill_random(&mut self, densities: &[f64], seed: u64) {
+        let mut rng = Rng::new(seed);
+        let (mid_row, mid_col) = (self.height / 2, self.width / 2);
         for row in 0..self.height {
             for col in 0..self.width {
+                if self.in_frozen_border(row, col) {
+                    continue;
+                }
+                let quadrant = 2 * (row >= mid_row) as usize + (col >= mid_col) as usize;
+                let density = densities[quadrant].clamp(0.0, 1.0);
                 let idx = self.get_index(row, col);
-                self.cells
-                    .set(idx, if Math::random() < 0.5 { true } else { false });
+                self.cells.set(idx, rng.next_f64() < density);
             }
         }
+        self.ages.iter_mut().for_each(|age| *age = 0);
     }
 
-    pub fn insert_glider_at_pos(&mut self, row: u32, col: u32) {
-        for d_row in [self.height - 1, 0, 1].iter().cloned() {
-            for d_col in [self.width - 1, 0, 1].iter().cloned() {
-                let cell_row = (d_row + row) % self.height;
-                let cell_col = (d_col + col) % self.width;
-                let idx = self.get_index(cell_row, cell_col);
-                let is_alive = if (d_row == self.height - 1 && d_col == 0)
-                    || (d_row == 0 && d_col == self.width - 1)
-                    || d_row == 1
-                {
-                    true
-                } else {
-                    false
-                };
-                self.cells.set(idx, is_alive);
+    /// Randomly redistribute the live cells across the grid, keeping the
+    /// population unchanged. The same `seed` always gives the same layout.
+    ///
+    /// Cells in the frozen border are never chosen, and live ones there
+    /// stay where they are.
+    pub fn shuffle(&mut self, seed: u64) {
+        let mut candidates = Vec::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if !self.in_frozen_border(row, col) {
+                    candidates.push(self.get_index(row, col));
+                }
+            }
+        }
+        let population = candidates.iter().filter(|&&idx| self.cells[idx]).count();
+
+        // Partial Fisher–Yates: only the first `population` slots matter.
+        let mut rng = Rng::new(seed);
+        for i in 0..population {
+            let j = i + rng.below((candidates.len() - i) as u64) as usize;
+            candidates.swap(i, j);
+        }
+
+        for &idx in &candidates {
+            self.cells.set(idx, false);
+            self.ages[idx] = 0;
+        }
+        for &idx in &candidates[..population] {
+            self.cells.set(idx, true);
+        }
+    }
+
+    /// Insert a glider centred on `(row, col)`. Cells that fall off the
+    /// grid wrap around in toroidal mode and are dropped in bounded mode.
+    pub fn insert_glider_at_pos(&mut self, row: u32, col: u32) -> Result<(), Error> {
+        self.checked_index(row, col)?;
+        self.record(Op::Glider(row, col));
+        self.begin_edit();
+        for d_row in [-1i64, 0, 1].iter().cloned() {
+            for d_col in [-1i64, 0, 1].iter().cloned() {
+                let is_alive =
+                    (d_row == -1 && d_col == 0) || (d_row == 0 && d_col == -1) || d_row == 1;
+                self.stamp_cell(row, col, d_row, d_col, is_alive);
             }
         }
+        self.end_edit();
+        Ok(())
     }
 
-    pub fn insert_pulsar_at_pos(&mut self, row: u32, col: u32) {
+    /// Insert a pulsar centred on `(row, col)`. Cells that fall off the
+    /// grid wrap around in toroidal mode and are dropped in bounded mode.
+    pub fn insert_pulsar_at_pos(&mut self, row: u32, col: u32) -> Result<(), Error> {
+        self.checked_index(row, col)?;
+        self.record(Op::Pulsar(row, col));
+        self.begin_edit();
         let hor_row = [
             false, false, true, true, true, false, false, false, true, true, true, false, false,
         ];
@@ -263,20 +1664,68 @@ impl Universe {
             hor_row, empty_row, ver_row, ver_row, ver_row, hor_row, empty_row, hor_row, ver_row,
             ver_row, ver_row, empty_row, hor_row,
         ];
-        for (d_row, row_cells) in rows.iter().cloned().enumerate() {
-            let cell_row =
-                (((d_row as u32 + self.height - 6) % self.height) as u32 + row) % self.height;
-            let row_idx = self.get_row_index(cell_row) + col as usize;
-            for idx in 0..13 {
-                self.cells.set(row_idx + idx - 6, row_cells[idx])
+        for (d_row, row_cells) in rows.iter().enumerate() {
+            for (d_col, &alive) in row_cells.iter().enumerate() {
+                self.stamp_cell(row, col, d_row as i64 - 6, d_col as i64 - 6, alive);
             }
         }
+        self.end_edit();
+        Ok(())
     }
 
     pub fn render(&self) -> String {
         self.to_string()
     }
 
+    /// The board as RGBA pixels, row by row, `cell_size` pixels square per
+    /// cell, ready for an `ImageData`. Live cells are black and dead cells
+    /// white; dying cells of Generations rules such as Brian's Brain are
+    /// blue, fading towards white as they die, Wireworld cells are white,
+    /// blue, red and yellow for empty, head, tail and conductor, and
+    /// sandpiles run from white through yellow, orange and red for 0 to 3
+    /// grains to black for cells about to topple, forest fires show green
+    /// trees and orange fire, and Wa-Tor shows yellow fish and grey sharks
+    /// in blue water. Under Immigration and QuadLife live cells are red,
+    /// blue, green or yellow by colour.
+    pub fn render_rgba(&self, cell_size: u32) -> Vec<u8> {
+        let mut out =
+            Vec::with_capacity((self.width * self.height * cell_size * cell_size * 4) as usize);
+        self.write_rgba_frame(cell_size, &mut out);
+        out
+    }
+
+    /// Tick `total_steps` times, capturing an RGBA frame of the starting
+    /// state and then after every `stride`-th tick (a `stride` of 0 is
+    /// treated as 1, capturing every generation).
+    ///
+    /// The result starts with a 12-byte header of little-endian `u32`s:
+    /// frame count, frame width and frame height in pixels. The frames
+    /// follow back to back, each `width * cell_size` by
+    /// `height * cell_size` pixels of 4 bytes, coloured as by
+    /// `render_rgba`. That is `16 * cell_size²` KiB per frame for a
+    /// 64×64 board, so long captures of large boards should use a larger
+    /// `stride` or be split into several calls.
+    pub fn capture_every(&mut self, total_steps: u32, stride: u32, cell_size: u32) -> Vec<u8> {
+        let stride = stride.max(1);
+        let frames = 1 + total_steps / stride;
+        let frame_width = self.width * cell_size;
+        let frame_height = self.height * cell_size;
+        let frame_len = (frame_width * frame_height * 4) as usize;
+
+        let mut out = Vec::with_capacity(12 + frames as usize * frame_len);
+        out.extend_from_slice(&frames.to_le_bytes());
+        out.extend_from_slice(&frame_width.to_le_bytes());
+        out.extend_from_slice(&frame_height.to_le_bytes());
+        self.write_rgba_frame(cell_size, &mut out);
+        for step in 1..=total_steps {
+            self.tick();
+            if step % stride == 0 {
+                self.write_rgba_frame(cell_size, &mut out);
+            }
+        }
+        out
+    }
+
     pub fn width(&self) -> u32 {
         self.width
     }
@@ -285,19 +1734,249 @@ impl Universe {
         self.height
     }
 
+    /// Live cells on each border line, as `[top, right, bottom, left]`.
+    ///
+    /// Corner cells count towards both lines they sit on. An empty board
+    /// has no border lines, and gives all zeros.
+    pub fn edge_flux(&self) -> Vec<u32> {
+        if self.width == 0 || self.height == 0 {
+            return vec![0; 4];
+        }
+        let last_row = self.height - 1;
+        let last_col = self.width - 1;
+        let row_count = |row| {
+            let start = self.get_row_index(row);
+            self.cells.count_ones(start..start + self.width as usize) as u32
+        };
+        let col_count = |col| {
+            (0..self.height)
+                .filter(|&row| self.cells[self.get_index(row, col)])
+                .count() as u32
+        };
+        vec![
+            row_count(0),
+            col_count(last_col),
+            row_count(last_row),
+            col_count(0),
+        ]
+    }
+
+    /// States of the cells in the square window of the given `radius`
+    /// around `(row, col)`, one byte per cell (0 dead, 1 alive).
+    ///
+    /// The window is `2 * radius + 1` cells wide and tall and is returned
+    /// row by row, top-left first, with `(row, col)` at its centre. Cells
+    /// past an edge wrap around to the opposite side in toroidal mode and
+    /// read as the background in bounded mode. Errors if `(row, col)` is
+    /// off the board or the window is too big to hold.
+    pub fn cells_in_radius(&self, row: u32, col: u32, radius: u32) -> Result<Vec<u8>, Error> {
+        self.checked_index(row, col)?;
+        let side = radius
+            .checked_mul(2)
+            .and_then(|diameter| diameter.checked_add(1))
+            .and_then(|side| (side as usize).checked_mul(side as usize).map(|_| side))
+            .ok_or_else(|| Error::new(format!("radius {} is too large", radius)))?;
+        let mut window = Vec::with_capacity(side as usize * side as usize);
+        for d_row in 0..side as i64 {
+            for d_col in 0..side as i64 {
+                let (d_row, d_col) = (d_row - radius as i64, d_col - radius as i64);
+                window.push(match self.offset_index(row, col, d_row, d_col) {
+                    Some(idx) => self.cells[idx] as u8,
+                    None => self.background as u8,
+                });
+            }
+        }
+        Ok(window)
+    }
+
+    /// Name of the highest symmetry of the live cells' bounding box.
+    ///
+    /// When several symmetries hold, the first match in this order wins:
+    /// `"D4"` (all rotations and reflections), `"C4"` (quarter-turn
+    /// rotation), `"C2"` (half-turn rotation), `"diagonal"` (mirror across
+    /// either diagonal), `"horizontal"` (mirror across a horizontal axis),
+    /// `"vertical"` (mirror across a vertical axis), and finally `"none"`.
+    /// An empty board is reported as `"D4"`.
+    pub fn detect_symmetry(&self) -> String {
+        let pattern = self.extract_pattern();
+        let c4 = pattern.rotate_cw() == pattern;
+        let c2 = pattern.rotate_180() == pattern;
+        let diagonal = pattern.transpose() == pattern || pattern.anti_transpose() == pattern;
+        let horizontal = pattern.flip_rows() == pattern;
+        let vertical = pattern.flip_cols() == pattern;
+
+        let name = if c4 && (diagonal || horizontal) {
+            "D4"
+        } else if c4 {
+            "C4"
+        } else if c2 {
+            "C2"
+        } else if diagonal {
+            "diagonal"
+        } else if horizontal {
+            "horizontal"
+        } else if vertical {
+            "vertical"
+        } else {
+            "none"
+        };
+        name.to_string()
+    }
+
+    /// Start or stop accumulating the activity heatmap. Disabling it frees
+    /// the buffer, so it costs nothing while unused.
+    pub fn set_heatmap_enabled(&mut self, enabled: bool) {
+        self.heatmap = if enabled {
+            let size = (self.width * self.height) as usize;
+            Some(self.heatmap.take().unwrap_or_else(|| vec![0; size]))
+        } else {
+            None
+        };
+    }
+
+    /// Pointer to the activity heatmap: one `u16` per cell, row by row,
+    /// counting the generations in which the cell was alive since the
+    /// heatmap was enabled or last cleared. Counts saturate at `u16::MAX`.
+    /// Null while the heatmap is disabled.
+    pub fn heatmap(&self) -> *const u16 {
+        self.heatmap
+            .as_ref()
+            .map_or(std::ptr::null(), |heatmap| heatmap.as_ptr())
+    }
+
+    pub fn clear_heatmap(&mut self) {
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.iter_mut().for_each(|count| *count = 0);
+        }
+    }
+
+    /// Fill `out` with the number of live cells in each row. `out` must be
+    /// exactly `height` long.
+    pub fn row_populations(&self, out: &mut [u32]) -> Result<(), Error> {
+        if out.len() != self.height as usize {
+            return Err(Error::new(format!(
+                "expected a buffer of {} rows, got {}",
+                self.height,
+                out.len()
+            )));
+        }
+        for (row, count) in out.iter_mut().enumerate() {
+            let start = self.get_row_index(row as u32);
+            *count = self.cells.count_ones(start..start + self.width as usize) as u32;
+        }
+        Ok(())
+    }
+
+    /// What the next `tick` would do to each cell, without changing
+    /// anything: one byte per cell, row by row, 0 for unchanged, 1 for a
+    /// birth and 2 for a death.
+    pub fn preview_next(&self) -> Vec<u8> {
+        let mut next = FixedBitSet::with_capacity(self.cells.len());
+        self.next_generation_into(&mut next, None);
+        (0..self.cells.len())
+            .map(|idx| match (self.cells[idx], next[idx]) {
+                (false, true) => 1,
+                (true, false) => 2,
+                _ => 0,
+            })
+            .collect()
+    }
+
+    /// Number of cells in the given rectangle that differ from the previous
+    /// generation, i.e. that changed in the last tick or were edited since.
+    /// The rectangle is clipped to the board.
+    pub fn region_changed(&self, row: u32, col: u32, width: u32, height: u32) -> u32 {
+        let end_row = row.saturating_add(height).min(self.height);
+        let end_col = col.saturating_add(width).min(self.width);
+        if col >= end_col {
+            return 0;
+        }
+        (row..end_row)
+            .map(|r| {
+                let start = self.get_index(r, col);
+                let end = self.get_index(r, end_col - 1) + 1;
+                utils::count_diff(&self.cells, &self.prev_cells, start..end)
+            })
+            .sum()
+    }
+
+    /// Pointer to the packed cell bits.
+    ///
+    /// Cell `(row, col)` is bit `i % 32` of word `i / 32`, where
+    /// `i = row * width + col`. Rows are packed back to back with no padding,
+    /// so unless `width` is a multiple of 32 a row starts in the middle of a
+    /// word. The buffer is `ceil(width * height / 32)` words long. Use
+    /// `cells_row_aligned` for a layout where every row starts on a word.
     pub fn cells(&self) -> *const u32 {
         self.cells.as_slice().as_ptr()
     }
+
+    /// Overwrite every cell from a packed word buffer laid out like the one
+    /// behind `cells()`. Bits past the last cell are ignored.
+    ///
+    /// Errors if `words` isn't exactly as long as that buffer.
+    pub fn set_cells_from_words(&mut self, words: &[u32]) -> Result<(), Error> {
+        let expected = self.cells.as_slice().len();
+        if words.len() != expected {
+            return Err(Error::new(format!(
+                "expected {} words, got {}",
+                expected,
+                words.len()
+            )));
+        }
+        let size = (self.width * self.height) as usize;
+        self.cells = FixedBitSet::with_capacity_and_blocks(size, words.iter().cloned());
+        if self.temp_cells.len() != size {
+            self.temp_cells = FixedBitSet::with_capacity(size);
+        }
+        self.ages.iter_mut().for_each(|age| *age = 0);
+        Ok(())
+    }
+
+    /// Number of bits per row in the `cells_row_aligned` layout: `width`
+    /// rounded up to a multiple of 32.
+    pub fn row_stride(&self) -> u32 {
+        self.width.div_ceil(32) * 32
+    }
+
+    /// Copy of the cells with every row padded out to a word boundary.
+    ///
+    /// Cell `(row, col)` is bit `i % 32` of word `i / 32`, where
+    /// `i = row * row_stride + col`. Padding bits are always zero.
+    pub fn cells_row_aligned(&self) -> Vec<u32> {
+        let words_per_row = (self.row_stride() / 32) as usize;
+        let mut words = vec![0u32; words_per_row * self.height as usize];
+        for row in 0..self.height {
+            let row_words = &mut words[row as usize * words_per_row..];
+            for col in 0..self.width {
+                if self.cells[self.get_index(row, col)] {
+                    row_words[(col / 32) as usize] |= 1 << (col % 32);
+                }
+            }
+        }
+        words
+    }
+}
+
+impl Default for Universe {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl fmt::Display for Universe {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for line in self.cells.as_slice().chunks(self.width as usize) {
-            for &cell in line {
-                let symbol = if cell == 0 { '◻' } else { '◼' };
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                let symbol = match self.cell_state(idx) {
+                    0 => '◻',
+                    1 => '◼',
+                    _ => '▣',
+                };
                 write!(f, "{}", symbol)?;
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
         Ok(())
     }
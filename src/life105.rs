@@ -0,0 +1,122 @@
+//! Patterns in the Life 1.05 format: a `#Life 1.05` header, `#D`
+//! description lines, an optional rule line, and blocks of `.` and `*`
+//! rows each placed by a `#P x y` line giving its top-left corner.
+//!
+//! The rule line is `#N` for Conway's rule or `#R` followed by the rule
+//! in `survival/birth` form, as in `#R 23/36` for HighLife.
+
+use crate::pattern::Pattern;
+use crate::rule::{Automaton, LifeRule};
+use crate::{Error, Universe};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+const HEADER: &str = "#Life 1.05";
+
+/// A Life 1.05 file read into its live cells, as `(row, col)` pairs, and
+/// its rule if it names one.
+struct Life105 {
+    cells: Vec<(i64, i64)>,
+    rule: Option<Automaton>,
+}
+
+fn decode(text: &str) -> Result<Life105, Error> {
+    let mut file = Life105 {
+        cells: Vec::new(),
+        rule: None,
+    };
+    // Where the next row of the current block goes.
+    let (mut row, mut left) = (0i64, 0i64);
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let malformed = || Error::new(format!("malformed line {}: {:?}", number + 1, line));
+        if let Some(offsets) = line.strip_prefix("#P") {
+            let mut fields = offsets.split_whitespace().map(str::parse::<i32>);
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => (row, left) = (y as i64, x as i64),
+                _ => return Err(malformed()),
+            }
+        } else if line == "#N" {
+            file.rule = Some(Automaton::Life(LifeRule::CONWAY));
+        } else if let Some(rule) = line.strip_prefix("#R") {
+            file.rule = Some(Automaton::parse(rule.trim())?);
+        } else if line.starts_with('#') || line.is_empty() {
+            continue;
+        } else {
+            for (col, c) in line.chars().enumerate() {
+                match c {
+                    '.' => {}
+                    '*' => file.cells.push((row, left + col as i64)),
+                    _ => return Err(malformed()),
+                }
+            }
+            row += 1;
+        }
+    }
+    Ok(file)
+}
+
+/// The `#N` or `#R` line for `rule`. Rules other than two-state Life-like
+/// ones have no Life 1.05 form, so they are written in the notation `rule`
+/// accepts, which this module reads back but other programs won't.
+fn rule_line(rule: &Automaton) -> String {
+    match rule {
+        Automaton::Life(rule) if *rule == LifeRule::CONWAY => "#N".to_string(),
+        Automaton::Life(rule) if rule.states() == 2 => {
+            let counts = |mask: u16| -> String {
+                (0..10)
+                    .filter(|n| mask >> n & 1 == 1)
+                    .map(|n| std::char::from_digit(n, 10).unwrap())
+                    .collect()
+            };
+            format!("#R {}/{}", counts(rule.survival()), counts(rule.birth()))
+        }
+        rule => format!("#R {}", rule),
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Parse a Life 1.05 pattern into a universe just large enough for its
+    /// live cells, running the rule its `#N` or `#R` line gives, or
+    /// Conway's rule without one. The cells are moved so the top-left of
+    /// their bounding box is at `(0, 0)`. Errors if the box spans more
+    /// than 2^24 cells.
+    pub fn from_life105(text: &str) -> Result<Universe, Error> {
+        let file = decode(text)?;
+        let pattern = Pattern::from_live_cells(&file.cells)?;
+        let mut universe = Universe::from_pattern(&pattern);
+        if let Some(rule) = file.rule {
+            universe.apply_rule(rule);
+        }
+        Ok(universe)
+    }
+
+    /// The live cells' bounding box as Life 1.05, in a single block placed
+    /// where it is on the board, with the rule line for the universe's
+    /// rule.
+    pub fn to_life105(&self) -> String {
+        let mut out = format!("{}\n{}\n", HEADER, rule_line(&self.rule));
+        let (mut top, mut left) = (u32::MAX, u32::MAX);
+        for idx in self.cells.ones() {
+            top = top.min(idx as u32 / self.width);
+            left = left.min(idx as u32 % self.width);
+        }
+        if top == u32::MAX {
+            return out;
+        }
+        out.push_str(&format!("#P {} {}\n", left, top));
+        let pattern = self.extract_pattern();
+        for row in 0..pattern.rows {
+            let len = (0..pattern.cols)
+                .rev()
+                .find(|&col| pattern.get(row, col))
+                .map_or(1, |last| last + 1);
+            // Empty rows are written as a single `.`, since blank lines
+            // are skipped on reading.
+            out.extend((0..len).map(|col| if pattern.get(row, col) { '*' } else { '.' }));
+            out.push('\n');
+        }
+        out
+    }
+}
@@ -0,0 +1,74 @@
+//! Patterns in the Life 1.06 format: a `#Life 1.06` header line and then
+//! one live cell per line as `x y`, the column then the row, either of
+//! which may be negative.
+
+use crate::pattern::Pattern;
+use crate::{Error, Universe};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+const HEADER: &str = "#Life 1.06";
+
+/// The live cells as `(row, col)` pairs, in file order.
+fn decode(text: &str) -> Result<Vec<(i64, i64)>, Error> {
+    let mut cells = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        // Only the header is required to be a comment, but other `#`
+        // lines turn up in the wild.
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace().map(str::parse::<i32>);
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => cells.push((y as i64, x as i64)),
+            _ => {
+                return Err(Error::new(format!(
+                    "expected \"x y\" on line {}, got {:?}",
+                    number + 1,
+                    line
+                )))
+            }
+        }
+    }
+    Ok(cells)
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Parse a Life 1.06 coordinate list into a universe just large enough
+    /// for its live cells, under Conway's rule. The cells are moved so the
+    /// top-left of their bounding box is at `(0, 0)`. Errors if the box
+    /// spans more than 2^24 cells.
+    pub fn from_life106(text: &str) -> Result<Universe, Error> {
+        let pattern = Pattern::from_live_cells(&decode(text)?)?;
+        Ok(Universe::from_pattern(&pattern))
+    }
+
+    /// The live cells as a Life 1.06 coordinate list, row by row, with the
+    /// board's top-left corner at `0 0`.
+    pub fn to_life106(&self) -> String {
+        let mut out = format!("{}\n", HEADER);
+        for idx in self.cells.ones() {
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            out.push_str(&format!("{} {}\n", col, row));
+        }
+        out
+    }
+
+    /// Bring to life every cell of a Life 1.06 coordinate list, counting
+    /// from `(row, col)`, leaving other cells as they are. Cells past an
+    /// edge wrap or are dropped according to the boundary mode. Errors,
+    /// changing nothing, if `(row, col)` is off the board or a line isn't
+    /// a pair of coordinates.
+    pub fn insert_life106(&mut self, row: u32, col: u32, text: &str) -> Result<(), Error> {
+        self.checked_index(row, col)?;
+        let cells = decode(text)?;
+        self.begin_edit();
+        for (d_row, d_col) in cells {
+            self.stamp_cell(row, col, d_row, d_col, true);
+        }
+        self.end_edit();
+        Ok(())
+    }
+}
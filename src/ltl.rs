@@ -0,0 +1,198 @@
+use crate::engine::{self, Neighborhood};
+use crate::{BoundaryMode, Universe};
+use std::fmt;
+
+/// Largest neighbourhood radius accepted for Larger than Life rules.
+const MAX_RADIUS: u32 = 50;
+
+/// Shape of a Larger than Life neighbourhood.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    /// Every cell within `radius` rows and columns.
+    Moore,
+    /// Every cell within `radius` steps along rows and columns combined.
+    VonNeumann,
+}
+
+/// A Larger than Life rule: birth and survival depend on whether the
+/// number of live cells within `radius` falls in a range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LtlRule {
+    radius: u32,
+    states: u8,
+    /// Whether a cell counts towards its own neighbourhood.
+    middle: bool,
+    survival: (u32, u32),
+    birth: (u32, u32),
+    shape: Shape,
+}
+
+impl LtlRule {
+    pub fn states(self) -> u8 {
+        self.states
+    }
+
+    /// Parse a rule in the `R5,C0,M1,S34..58,B34..45,NM` notation used by
+    /// Golly: radius, number of states (0 or 2 for two states, more for
+    /// dying states as in Generations rules), whether the middle cell
+    /// counts, the survival and birth ranges, and the neighbourhood shape
+    /// (`NM` Moore or `NN` von Neumann). Letters may be either case.
+    pub fn parse(rule: &str) -> Result<LtlRule, String> {
+        let rule = rule.trim().to_ascii_uppercase();
+        let invalid = || format!("invalid rule {:?}", rule);
+        let fields: Vec<&str> = rule.split(',').map(str::trim).collect();
+        if fields.len() != 6 {
+            return Err(invalid());
+        }
+        let number =
+            |field: &str, prefix: &str| -> Option<u32> { field.strip_prefix(prefix)?.parse().ok() };
+        let range = |field: &str, prefix: &str| -> Option<(u32, u32)> {
+            let mut bounds = field.strip_prefix(prefix)?.splitn(2, "..");
+            let min = bounds.next()?.parse().ok()?;
+            let max = bounds.next()?.parse().ok()?;
+            Some((min, max))
+        };
+
+        let radius = number(fields[0], "R")
+            .filter(|r| (1..=MAX_RADIUS).contains(r))
+            .ok_or_else(invalid)?;
+        let states = match number(fields[1], "C").ok_or_else(invalid)? {
+            0 | 2 => 2,
+            states if states <= u8::MAX as u32 => states as u8,
+            _ => return Err(invalid()),
+        };
+        let middle = match number(fields[2], "M") {
+            Some(0) => false,
+            Some(1) => true,
+            _ => return Err(invalid()),
+        };
+        let survival = range(fields[3], "S").ok_or_else(invalid)?;
+        let birth = range(fields[4], "B").ok_or_else(invalid)?;
+        let shape = match fields[5] {
+            "NM" => Shape::Moore,
+            "NN" => Shape::VonNeumann,
+            _ => return Err(invalid()),
+        };
+        Ok(LtlRule {
+            radius,
+            states,
+            middle,
+            survival,
+            birth,
+            shape,
+        })
+    }
+}
+
+impl fmt::Display for LtlRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "R{},C{},M{},S{}..{},B{}..{},N{}",
+            self.radius,
+            if self.states == 2 { 0 } else { self.states },
+            self.middle as u8,
+            self.survival.0,
+            self.survival.1,
+            self.birth.0,
+            self.birth.1,
+            match self.shape {
+                Shape::Moore => 'M',
+                Shape::VonNeumann => 'N',
+            }
+        )
+    }
+}
+
+impl engine::Rule for LtlRule {
+    #[inline]
+    fn next_state(&self, current: u8, neighborhood: &Neighborhood) -> u8 {
+        let count = neighborhood.total();
+        let in_range = |(min, max): (u32, u32)| min <= count && count <= max;
+        engine::life_like(
+            self.states,
+            current,
+            in_range(self.birth),
+            in_range(self.survival),
+        )
+    }
+
+    fn totals(&self, universe: &Universe) -> Vec<u32> {
+        universe.ltl_counts(self)
+    }
+}
+
+impl Universe {
+    /// Live cells in every cell's Larger than Life neighbourhood, row by
+    /// row.
+    ///
+    /// Works on a copy of the board padded by `radius` on every side with
+    /// wrapped cells (toroidal) or the background state (bounded), so no
+    /// lookup needs to wrap. Moore neighbourhoods are summed in constant
+    /// time from a summed-area table of the padded board; von Neumann ones
+    /// take one row sum per row of the diamond, from per-row prefix sums.
+    fn ltl_counts(&self, rule: &LtlRule) -> Vec<u32> {
+        let r = rule.radius as i64;
+        let (height, width) = (self.height as i64, self.width as i64);
+        let padded_width = (width + 2 * r) as usize;
+        let padded_height = (height + 2 * r) as usize;
+        let alive = |row: i64, col: i64| -> u32 {
+            let on_grid = row >= 0 && col >= 0 && row < height && col < width;
+            match self.boundary {
+                BoundaryMode::Bounded if !on_grid => self.background as u32,
+                _ => {
+                    let idx =
+                        self.get_index(row.rem_euclid(height) as u32, col.rem_euclid(width) as u32);
+                    self.cells[idx] as u32
+                }
+            }
+        };
+
+        // prefix[i][j] sums the padded cells above and left of (i, j),
+        // either over whole rectangles (Moore) or within row i (von Neumann).
+        let stride = padded_width + 1;
+        let mut prefix = vec![0u32; (padded_height + 1) * stride];
+        for i in 0..padded_height {
+            for j in 0..padded_width {
+                let cell = alive(i as i64 - r, j as i64 - r);
+                let left = prefix[(i + 1) * stride + j];
+                let above = match rule.shape {
+                    Shape::Moore => prefix[i * stride + j + 1] - prefix[i * stride + j],
+                    Shape::VonNeumann => 0,
+                };
+                prefix[(i + 1) * stride + j + 1] = left + above + cell;
+            }
+        }
+        let rect = |top: usize, left: usize, bottom: usize, right: usize| -> u32 {
+            prefix[bottom * stride + right] + prefix[top * stride + left]
+                - prefix[top * stride + right]
+                - prefix[bottom * stride + left]
+        };
+        let row_sum = |i: usize, left: usize, right: usize| {
+            prefix[(i + 1) * stride + right] - prefix[(i + 1) * stride + left]
+        };
+
+        let side = 2 * rule.radius as usize + 1;
+        let mut counts = Vec::with_capacity((self.width * self.height) as usize);
+        for row in 0..self.height as usize {
+            for col in 0..self.width as usize {
+                let mut count = match rule.shape {
+                    Shape::Moore => rect(row, col, row + side, col + side),
+                    Shape::VonNeumann => (0..side)
+                        .map(|d| {
+                            let reach =
+                                rule.radius as usize - (d as i64 - r).unsigned_abs() as usize;
+                            let centre = col + rule.radius as usize;
+                            row_sum(row + d, centre - reach, centre + reach + 1)
+                        })
+                        .sum(),
+                };
+                if !rule.middle {
+                    count -= self.cells[self.get_index(row as u32, col as u32)] as u32;
+                }
+                counts.push(count);
+            }
+        }
+        counts
+    }
+}
@@ -0,0 +1,393 @@
+//! Patterns in Golly's macrocell format, which stores a pattern as a
+//! quadtree with identical squares written once, so huge but regular
+//! patterns stay small.
+//!
+//! After an `[M2]` header line and `#` lines (`#R` giving the rule and
+//! `#G` the generation), every line is a node, numbered from 1 in order.
+//! A leaf is an 8x8 square written as rows of `.` and `*`, each ended by
+//! `$`, with trailing dead cells left out. Any larger square is written
+//! `k nw ne sw se`: its size is `2^k`, and its quadrants are the nodes with
+//! those numbers, 0 standing for an empty square. The last node is the
+//! whole pattern, whose centre Golly puts at the origin.
+//!
+//! Only two-state patterns are read; Golly writes multi-state ones with
+//! `1 a b c d` nodes instead of leaves.
+
+use crate::pattern::{Pattern, MAX_CELLS};
+use crate::rule::Automaton;
+use crate::sparse::SparseUniverse;
+use crate::{Error, Universe};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+const HEADER: &str = "[M2] (wasm-game-of-life)";
+
+/// Level of the leaves, which are `2^3 = 8` cells on a side.
+const LEAF_LEVEL: u8 = 3;
+
+/// Level of a `SparseUniverse` chunk, 64 cells on a side.
+const CHUNK_LEVEL: u8 = 6;
+
+/// Largest node level read, keeping coordinates well inside an `i64`.
+const MAX_LEVEL: u8 = 62;
+
+/// Node number 0, the empty square of any size.
+const EMPTY: usize = 0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    /// An 8x8 square, one byte per row with column `i` in bit `i`.
+    Leaf([u8; 8]),
+    /// A `2^level` square made of four nodes one level down: north-west,
+    /// north-east, south-west and south-east.
+    Branch { level: u8, quadrants: [usize; 4] },
+}
+
+impl Node {
+    fn level(&self) -> u8 {
+        match self {
+            Node::Leaf(_) => LEAF_LEVEL,
+            Node::Branch { level, .. } => *level,
+        }
+    }
+}
+
+/// A macrocell file read into its nodes, numbered as in the file.
+struct Macrocell {
+    /// `nodes[0]` stands in for the empty node and is never looked at.
+    nodes: Vec<Node>,
+    rule: Option<Automaton>,
+    generation: u64,
+}
+
+fn parse_leaf(line: &str) -> Option<Node> {
+    let mut rows = [0u8; 8];
+    let (mut row, mut col) = (0, 0);
+    for c in line.chars() {
+        match c {
+            '.' => col += 1,
+            '*' if row < 8 && col < 8 => {
+                rows[row] |= 1 << col;
+                col += 1;
+            }
+            '$' => {
+                row += 1;
+                col = 0;
+            }
+            _ => return None,
+        }
+    }
+    Some(Node::Leaf(rows))
+}
+
+fn decode(text: &str) -> Result<Macrocell, Error> {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    if !lines.next().is_some_and(|line| line.starts_with("[M2]")) {
+        return Err(Error::new("not a macrocell file: no [M2] header"));
+    }
+    let mut file = Macrocell {
+        nodes: vec![Node::Leaf([0; 8])],
+        rule: None,
+        generation: 0,
+    };
+    for line in lines {
+        let malformed = || Error::new(format!("malformed node {}: {:?}", file.nodes.len(), line));
+        if let Some(rule) = line.strip_prefix("#R") {
+            file.rule = Some(Automaton::parse(rule.trim())?);
+        } else if let Some(generation) = line.strip_prefix("#G") {
+            file.generation = generation.trim().parse().map_err(|_| malformed())?;
+        } else if line.starts_with('#') {
+            continue;
+        } else if line.starts_with(['.', '*', '$']) {
+            let leaf = parse_leaf(line).ok_or_else(malformed)?;
+            file.nodes.push(leaf);
+        } else {
+            let fields: Vec<usize> = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| malformed())?;
+            let (level, quadrants) = match fields[..] {
+                [level, nw, ne, sw, se] => (level, [nw, ne, sw, se]),
+                _ => return Err(malformed()),
+            };
+            if level <= LEAF_LEVEL as usize {
+                return Err(Error::new("only two-state macrocell patterns can be read"));
+            }
+            if level > MAX_LEVEL as usize {
+                return Err(malformed());
+            }
+            let fits = |id: usize| {
+                id == EMPTY
+                    || (id < file.nodes.len() && file.nodes[id].level() as usize + 1 == level)
+            };
+            if !quadrants.iter().all(|&id| fits(id)) {
+                return Err(malformed());
+            }
+            file.nodes.push(Node::Branch {
+                level: level as u8,
+                quadrants,
+            });
+        }
+    }
+    Ok(file)
+}
+
+impl Macrocell {
+    /// The last node, the whole pattern, or `None` if there are no nodes.
+    fn root(&self) -> Option<usize> {
+        Some(self.nodes.len() - 1).filter(|&root| root != EMPTY)
+    }
+
+    /// Live cells in the whole pattern, worked out once per node.
+    fn population(&self) -> u64 {
+        let mut counts = vec![0u64; self.nodes.len()];
+        for (id, node) in self.nodes.iter().enumerate().skip(1) {
+            counts[id] = match node {
+                Node::Leaf(rows) => rows.iter().map(|row| row.count_ones() as u64).sum(),
+                Node::Branch { quadrants, .. } => quadrants
+                    .iter()
+                    .fold(0, |sum: u64, &q| sum.saturating_add(counts[q])),
+            };
+        }
+        self.root().map_or(0, |root| counts[root])
+    }
+
+    /// Call `f` with every live cell of node `id`, whose top-left corner
+    /// is at `(row, col)`.
+    fn for_each_cell(&self, id: usize, row: i64, col: i64, f: &mut impl FnMut(i64, i64)) {
+        if id == EMPTY {
+            return;
+        }
+        match self.nodes[id] {
+            Node::Leaf(rows) => {
+                for (d_row, bits) in rows.iter().enumerate() {
+                    for d_col in (0..8).filter(|d_col| bits >> d_col & 1 == 1) {
+                        f(row + d_row as i64, col + d_col);
+                    }
+                }
+            }
+            Node::Branch { level, quadrants } => {
+                let half = 1i64 << (level - 1);
+                for (q, (d_row, d_col)) in
+                    quadrants
+                        .iter()
+                        .zip([(0, 0), (0, half), (half, 0), (half, half)])
+                {
+                    self.for_each_cell(*q, row + d_row, col + d_col, f);
+                }
+            }
+        }
+    }
+
+    /// Every live cell, with the centre of the pattern at the origin as in
+    /// Golly. Errors if there are more than `MAX_CELLS`.
+    fn cells(&self) -> Result<Vec<(i64, i64)>, Error> {
+        let population = self.population();
+        if population > MAX_CELLS {
+            return Err(Error::new(format!(
+                "pattern has {} live cells, more than the {} that can be loaded",
+                population, MAX_CELLS
+            )));
+        }
+        let mut cells = Vec::with_capacity(population as usize);
+        if let Some(root) = self.root() {
+            let origin = -(1i64 << (self.nodes[root].level() - 1));
+            self.for_each_cell(root, origin, origin, &mut |row, col| cells.push((row, col)));
+        }
+        Ok(cells)
+    }
+}
+
+/// Builds the nodes of a pattern for writing, sharing identical ones.
+struct Writer {
+    lines: Vec<String>,
+    numbers: HashMap<Node, usize>,
+}
+
+impl Writer {
+    fn new() -> Writer {
+        Writer {
+            lines: Vec::new(),
+            numbers: HashMap::new(),
+        }
+    }
+
+    /// The number of `node`, written out the first time it is seen.
+    fn number(&mut self, node: Node) -> usize {
+        match node {
+            Node::Leaf(rows) if rows == [0; 8] => return EMPTY,
+            Node::Branch { quadrants, .. } if quadrants == [EMPTY; 4] => return EMPTY,
+            _ => {}
+        }
+        if let Some(&number) = self.numbers.get(&node) {
+            return number;
+        }
+        self.lines.push(match node {
+            Node::Leaf(rows) => rows
+                .iter()
+                .map(|&bits| {
+                    let len = 8 - bits.leading_zeros();
+                    let row: String = (0..len)
+                        .map(|col| if bits >> col & 1 == 1 { '*' } else { '.' })
+                        .collect();
+                    row + "$"
+                })
+                .collect(),
+            Node::Branch { level, quadrants } => {
+                let [nw, ne, sw, se] = quadrants;
+                format!("{} {} {} {} {}", level, nw, ne, sw, se)
+            }
+        });
+        self.numbers.insert(node, self.lines.len());
+        self.lines.len()
+    }
+
+    /// The `2^level` square with its top-left corner at `(row, col)`,
+    /// `leaf` giving the rows of each 8x8 square in it.
+    fn square(
+        &mut self,
+        level: u8,
+        row: i64,
+        col: i64,
+        leaf: &impl Fn(i64, i64) -> [u8; 8],
+    ) -> usize {
+        if level == LEAF_LEVEL {
+            return self.number(Node::Leaf(leaf(row, col)));
+        }
+        let half = 1i64 << (level - 1);
+        let quadrants = [(0, 0), (0, half), (half, 0), (half, half)]
+            .map(|(d_row, d_col)| self.square(level - 1, row + d_row, col + d_col, leaf));
+        self.number(Node::Branch { level, quadrants })
+    }
+
+    fn finish(self, rule: &str, generation: u64) -> String {
+        let mut out = format!("{}\n#R {}\n", HEADER, rule);
+        if generation > 0 {
+            out.push_str(&format!("#G {}\n", generation));
+        }
+        for line in self.lines {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Expand a two-state macrocell pattern into a universe just large
+    /// enough for its live cells, running its `#R` rule, or Conway's rule
+    /// without one, at its `#G` generation. The cells are moved so the
+    /// top-left of their bounding box is at `(0, 0)`.
+    ///
+    /// Errors if the pattern has more than 2^24 live cells or its bounding
+    /// box more than 2^24 cells; a `SparseUniverse` can take patterns that
+    /// are large but sparse.
+    pub fn from_macrocell(text: &str) -> Result<Universe, Error> {
+        let file = decode(text)?;
+        let pattern = Pattern::from_live_cells(&file.cells()?)?;
+        let mut universe = Universe::from_pattern(&pattern);
+        if let Some(rule) = file.rule {
+            universe.apply_rule(rule);
+        }
+        universe.generation = file.generation;
+        Ok(universe)
+    }
+
+    /// The board in macrocell format, as a square whose side is a power of
+    /// two with the board in its top-left corner.
+    pub fn to_macrocell(&self) -> String {
+        let side = self.width.max(self.height).next_power_of_two().max(8);
+        let (width, height) = (self.width as i64, self.height as i64);
+        let leaf = |row: i64, col: i64| {
+            let mut rows = [0u8; 8];
+            for (d_row, bits) in rows.iter_mut().enumerate() {
+                for d_col in 0..8 {
+                    let (r, c) = (row + d_row as i64, col + d_col);
+                    if r < height && c < width && self.cells[self.get_index(r as u32, c as u32)] {
+                        *bits |= 1 << d_col;
+                    }
+                }
+            }
+            rows
+        };
+        let mut writer = Writer::new();
+        writer.square(side.trailing_zeros() as u8, 0, 0, &leaf);
+        writer.finish(&self.rule(), self.generation)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl SparseUniverse {
+    /// Read a two-state macrocell pattern with its centre at the origin,
+    /// as Golly places it, at its `#G` generation. Errors if it has more
+    /// than 2^24 live cells or cells beyond the reach of an `i32`, or if
+    /// its `#R` rule can't run unbounded; see `set_rule`.
+    pub fn from_macrocell(text: &str) -> Result<SparseUniverse, Error> {
+        let file = decode(text)?;
+        let mut sparse = SparseUniverse::new();
+        if let Some(rule) = &file.rule {
+            sparse.set_rule(&rule.to_string())?;
+        }
+        for (row, col) in file.cells()? {
+            match (i32::try_from(row), i32::try_from(col)) {
+                (Ok(row), Ok(col)) => sparse.set(row, col, true),
+                _ => return Err(Error::new("pattern reaches too far from the origin")),
+            }
+        }
+        sparse.set_generation(file.generation);
+        Ok(sparse)
+    }
+
+    /// The pattern in macrocell format, centred on the origin as Golly
+    /// expects.
+    pub fn to_macrocell(&self) -> String {
+        let mut writer = Writer::new();
+        // Each chunk is a square of its own; squares twice the size are
+        // then built from those, level by level, up to the root's
+        // quadrants, which meet at the origin.
+        let mut squares: BTreeMap<(i64, i64), usize> = BTreeMap::new();
+        let mut reach = 1i64;
+        let mut chunks: Vec<_> = self.stored_chunks().collect();
+        chunks.sort_unstable_by_key(|&(key, _)| key);
+        for ((chunk_row, chunk_col), chunk) in chunks {
+            let leaf = |row: i64, col: i64| {
+                let (row, col) = (row.rem_euclid(64) as usize, col.rem_euclid(64));
+                let mut rows = [0u8; 8];
+                for (d_row, bits) in rows.iter_mut().enumerate() {
+                    *bits = (chunk[row + d_row] >> col) as u8;
+                }
+                rows
+            };
+            let (row, col) = (chunk_row as i64 * 64, chunk_col as i64 * 64);
+            let number = writer.square(CHUNK_LEVEL, row, col, &leaf);
+            squares.insert((chunk_row as i64, chunk_col as i64), number);
+            reach = [-chunk_row, chunk_row + 1, -chunk_col, chunk_col + 1]
+                .iter()
+                .fold(reach, |reach, &n| reach.max(n as i64));
+        }
+        let level = CHUNK_LEVEL + 1 + (reach as u64).next_power_of_two().trailing_zeros() as u8;
+        for parent_level in CHUNK_LEVEL + 1..level {
+            let mut parents: BTreeMap<(i64, i64), [usize; 4]> = BTreeMap::new();
+            for ((row, col), number) in squares {
+                let quadrant = (row.rem_euclid(2) * 2 + col.rem_euclid(2)) as usize;
+                let key = (row.div_euclid(2), col.div_euclid(2));
+                parents.entry(key).or_insert([EMPTY; 4])[quadrant] = number;
+            }
+            squares = parents
+                .into_iter()
+                .map(|(key, quadrants)| {
+                    let level = parent_level;
+                    (key, writer.number(Node::Branch { level, quadrants }))
+                })
+                .collect();
+        }
+        let quadrant = |key| squares.get(&key).copied().unwrap_or(EMPTY);
+        let quadrants = [(-1, -1), (-1, 0), (0, -1), (0, 0)].map(quadrant);
+        writer.number(Node::Branch { level, quadrants });
+        writer.finish(&self.rule(), self.generation())
+    }
+}
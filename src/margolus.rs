@@ -0,0 +1,98 @@
+use crate::engine::{self, Neighborhood};
+use crate::Universe;
+use std::fmt;
+
+/// Critters: blocks with exactly two live cells are left alone, the rest
+/// are inverted, and inverted blocks that had three live cells are also
+/// turned half a turn.
+const CRITTERS: [u8; 16] = [15, 14, 13, 3, 11, 5, 6, 1, 7, 9, 10, 2, 12, 4, 8, 0];
+
+/// The billiard ball machine: a lone cell moves to the opposite corner and
+/// two cells on a diagonal bounce onto the other diagonal.
+const BBM: [u8; 16] = [0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15];
+
+/// A block cellular automaton on the Margolus neighbourhood. The board is
+/// cut into 2x2 blocks, shifted one cell down and right on every other
+/// generation, and each block is replaced as a whole.
+///
+/// Blocks are numbered by their live cells: 1 for the top left, 2 for the
+/// top right, 4 for the bottom left and 8 for the bottom right. Toroidal
+/// boards need an even width and height for the blocks to tile them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MargolusRule {
+    /// The block each block turns into.
+    table: [u8; 16],
+}
+
+impl MargolusRule {
+    /// Parse `Critters`, `BBM`, or Golly's notation: `M` followed by the
+    /// 16 blocks each block turns into, comma-separated, such as
+    /// `M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15` for the billiard ball
+    /// machine.
+    pub fn parse(rule: &str) -> Result<MargolusRule, String> {
+        let rule = rule.trim().to_ascii_uppercase();
+        let invalid = || format!("invalid rule {:?}", rule);
+        match rule.as_str() {
+            "CRITTERS" => return Ok(MargolusRule { table: CRITTERS }),
+            "BBM" => return Ok(MargolusRule { table: BBM }),
+            _ => {}
+        }
+        let blocks = rule.strip_prefix('M').ok_or_else(invalid)?;
+        let mut table = [0u8; 16];
+        let mut fields = blocks.split(',').map(str::trim);
+        for entry in table.iter_mut() {
+            *entry = fields
+                .next()
+                .and_then(|field| field.parse().ok())
+                .filter(|&block| block < 16)
+                .ok_or_else(invalid)?;
+        }
+        if fields.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(MargolusRule { table })
+    }
+}
+
+impl fmt::Display for MargolusRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("M")?;
+        for (i, block) in self.table.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", block)?;
+        }
+        Ok(())
+    }
+}
+
+impl engine::Rule for MargolusRule {
+    #[inline]
+    fn next_state(&self, _current: u8, neighborhood: &Neighborhood) -> u8 {
+        let (block, position) = neighborhood.margolus_block();
+        self.table[block as usize] >> position & 1
+    }
+}
+
+impl Universe {
+    /// The Margolus block holding `(row, col)` in the current generation,
+    /// numbered as in `MargolusRule`, and the bit of the cell within it.
+    /// Off-grid cells in bounded mode count as the background state.
+    pub(crate) fn margolus_block(&self, row: u32, col: u32) -> (u8, u8) {
+        let shift = (self.generation % 2) as u32;
+        let d_row = ((row + shift) % 2) as i64;
+        let d_col = ((col + shift) % 2) as i64;
+        let mut block = 0;
+        for i in 0..2 {
+            for j in 0..2 {
+                let alive = match self.offset_index(row, col, i - d_row, j - d_col) {
+                    Some(idx) => self.cells[idx],
+                    None => self.background,
+                };
+                block |= (alive as u8) << (2 * i + j);
+            }
+        }
+        (block, (2 * d_row + d_col) as u8)
+    }
+}
@@ -0,0 +1,143 @@
+use crate::Error;
+
+/// Most cells a pattern read from text may span, so a short file can't
+/// ask for a huge allocation.
+pub const MAX_CELLS: u64 = 1 << 24;
+
+/// A rectangular block of cells, stored row by row.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    pub rows: u32,
+    pub cols: u32,
+    pub cells: Vec<bool>,
+}
+
+impl Pattern {
+    pub fn new(rows: u32, cols: u32) -> Pattern {
+        let size = (rows as usize)
+            .checked_mul(cols as usize)
+            .expect("pattern too large to address");
+        Pattern {
+            rows,
+            cols,
+            cells: vec![false; size],
+        }
+    }
+
+    /// An all-dead pattern read from untrusted input, which errors rather
+    /// than allocating more than `MAX_CELLS` cells.
+    pub fn bounded(rows: u32, cols: u32) -> Result<Pattern, Error> {
+        match (rows as u64).checked_mul(cols as u64) {
+            Some(size) if size <= MAX_CELLS => Ok(Pattern::new(rows, cols)),
+            _ => Err(Error::new(format!(
+                "a {} by {} pattern is larger than {} cells",
+                cols, rows, MAX_CELLS
+            ))),
+        }
+    }
+
+    /// The bounding box of `cells`, given as `(row, col)` pairs that may be
+    /// negative, with those cells alive and its top-left corner at the
+    /// smallest row and column. Errors like `bounded` if the box is larger
+    /// than `MAX_CELLS`.
+    pub fn from_live_cells(cells: &[(i64, i64)]) -> Result<Pattern, Error> {
+        let span = |coords: &mut dyn Iterator<Item = i64>| -> (i64, u32) {
+            let (min, max) = coords.fold((i64::MAX, i64::MIN), |(min, max), coord| {
+                (min.min(coord), max.max(coord))
+            });
+            let len = (max as i128 - min as i128 + 1).clamp(0, u32::MAX as i128);
+            (min, len as u32)
+        };
+        let (top, rows) = span(&mut cells.iter().map(|&(row, _)| row));
+        let (left, cols) = span(&mut cells.iter().map(|&(_, col)| col));
+        let mut pattern = Pattern::bounded(rows, cols)?;
+        for &(row, col) in cells {
+            pattern.set((row - top) as u32, (col - left) as u32, true);
+        }
+        Ok(pattern)
+    }
+
+    /// Build a pattern from one byte per cell, row by row (0 dead, anything
+    /// else alive). `None` if `cells` doesn't hold `rows * cols` bytes.
+    pub fn from_bytes(rows: u32, cols: u32, cells: &[u8]) -> Option<Pattern> {
+        if (rows as usize).checked_mul(cols as usize) != Some(cells.len()) {
+            return None;
+        }
+        Some(Pattern {
+            rows,
+            cols,
+            cells: cells.iter().map(|&c| c != 0).collect(),
+        })
+    }
+
+    pub fn get(&self, row: u32, col: u32) -> bool {
+        self.cells[(row * self.cols + col) as usize]
+    }
+
+    pub fn set(&mut self, row: u32, col: u32, alive: bool) {
+        self.cells[(row * self.cols + col) as usize] = alive;
+    }
+
+    fn map<F>(&self, rows: u32, cols: u32, source: F) -> Pattern
+    where
+        F: Fn(u32, u32) -> (u32, u32),
+    {
+        let mut out = Pattern::new(rows, cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                let (src_row, src_col) = source(row, col);
+                out.set(row, col, self.get(src_row, src_col));
+            }
+        }
+        out
+    }
+
+    /// Rotate a quarter turn clockwise.
+    pub fn rotate_cw(&self) -> Pattern {
+        self.map(self.cols, self.rows, |row, col| (self.rows - 1 - col, row))
+    }
+
+    /// Rotate half a turn.
+    pub fn rotate_180(&self) -> Pattern {
+        self.map(self.rows, self.cols, |row, col| {
+            (self.rows - 1 - row, self.cols - 1 - col)
+        })
+    }
+
+    /// Mirror top to bottom.
+    pub fn flip_rows(&self) -> Pattern {
+        self.map(self.rows, self.cols, |row, col| (self.rows - 1 - row, col))
+    }
+
+    /// Mirror left to right.
+    pub fn flip_cols(&self) -> Pattern {
+        self.map(self.rows, self.cols, |row, col| (row, self.cols - 1 - col))
+    }
+
+    /// Mirror across the main diagonal.
+    pub fn transpose(&self) -> Pattern {
+        self.map(self.cols, self.rows, |row, col| (col, row))
+    }
+
+    /// Mirror across the anti-diagonal.
+    pub fn anti_transpose(&self) -> Pattern {
+        self.map(self.cols, self.rows, |row, col| {
+            (self.rows - 1 - col, self.cols - 1 - row)
+        })
+    }
+
+    /// The distinct patterns among all eight rotations and reflections.
+    pub fn orientations(&self) -> Vec<Pattern> {
+        let mut result: Vec<Pattern> = Vec::with_capacity(8);
+        let mut current = self.clone();
+        for _ in 0..4 {
+            for candidate in [current.flip_cols(), current.clone()].iter() {
+                if !result.contains(candidate) {
+                    result.push(candidate.clone());
+                }
+            }
+            current = current.rotate_cw();
+        }
+        result
+    }
+}
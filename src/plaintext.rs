@@ -0,0 +1,87 @@
+//! Patterns in the plaintext `.cells` format: one line per row, `.` for a
+//! dead cell and `O` for a live one, with `!` starting a comment line.
+//! Rows may stop short; the missing cells are dead.
+
+use crate::pattern::Pattern;
+use crate::{Error, Universe};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+fn decode(text: &str) -> Result<Pattern, Error> {
+    let rows: Vec<&str> = text
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.starts_with('!'))
+        .collect();
+    // Blank lines at the end are just the end of the file; inside the
+    // pattern they are empty rows.
+    let height = rows
+        .iter()
+        .rposition(|row| !row.is_empty())
+        .map_or(0, |last| last + 1);
+    let width = rows
+        .iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut pattern = Pattern::new(height as u32, width as u32);
+    for (row, line) in rows[..height].iter().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                'O' | '*' => pattern.set(row as u32, col as u32, true),
+                c => {
+                    return Err(Error::new(format!(
+                        "unexpected character {:?} in row {}",
+                        c,
+                        row + 1
+                    )))
+                }
+            }
+        }
+    }
+    Ok(pattern)
+}
+
+/// `pattern` in plaintext, with trailing dead cells left off each row.
+fn encode(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    for row in 0..pattern.rows {
+        let len = (0..pattern.cols)
+            .rev()
+            .find(|&col| pattern.get(row, col))
+            .map_or(0, |last| last + 1);
+        out.extend((0..len).map(|col| if pattern.get(row, col) { 'O' } else { '.' }));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Parse a pattern in the plaintext `.cells` format into a universe
+    /// just large enough for it, under Conway's rule. `*` is accepted for
+    /// live cells as well as `O`. Errors on any other character outside a
+    /// comment.
+    pub fn from_plaintext(text: &str) -> Result<Universe, Error> {
+        Ok(Universe::from_pattern(&decode(text)?))
+    }
+
+    /// The live cells' bounding box in the plaintext `.cells` format.
+    pub fn to_plaintext(&self) -> String {
+        encode(&self.extract_pattern())
+    }
+
+    /// Stamp a plaintext pattern with its top-left corner at `(row, col)`,
+    /// overwriting every cell of its bounding box. Cells past an edge wrap
+    /// or are dropped according to the boundary mode, but the corner
+    /// itself must be on the board.
+    pub fn insert_plaintext(&mut self, row: u32, col: u32, text: &str) -> Result<(), Error> {
+        self.checked_index(row, col)?;
+        let pattern = decode(text)?;
+        self.begin_edit();
+        self.stamp_pattern(row, col, &pattern);
+        self.end_edit();
+        Ok(())
+    }
+}
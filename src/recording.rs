@@ -0,0 +1,214 @@
+//! Recording and replaying the edits and ticks applied to a universe.
+//!
+//! A recording starts with a header holding the starting board: a version
+//! byte, then the width, height and number of packed cell words, then the
+//! words themselves, all as little-endian `u32`s. One entry per operation
+//! follows: a tag byte and its little-endian arguments.
+
+use crate::hashlife::MAX_JUMP;
+use crate::{Error, Universe};
+use fixedbitset::FixedBitSet;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+const VERSION: u8 = 1;
+
+/// An operation captured while recording.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Tick,
+    Toggle(u32, u32),
+    Glider(u32, u32),
+    Pulsar(u32, u32),
+    /// A flat cell index forced to a state by `tick_with_injection`.
+    Set(u32, bool),
+    /// `jump` by `2^k` generations.
+    Jump(u32),
+}
+
+impl Op {
+    fn encode(self, out: &mut Vec<u8>) {
+        let (tag, args): (u8, &[u32]) = match self {
+            Op::Tick => (0, &[]),
+            Op::Toggle(row, col) => (1, &[row, col]),
+            Op::Glider(row, col) => (2, &[row, col]),
+            Op::Pulsar(row, col) => (3, &[row, col]),
+            Op::Set(idx, alive) => (4, &[idx, alive as u32]),
+            Op::Jump(k) => (5, &[k]),
+        };
+        out.push(tag);
+        for arg in args {
+            out.extend_from_slice(&arg.to_le_bytes());
+        }
+    }
+}
+
+/// Reads little-endian values off the front of a recording.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Result<u8, Error> {
+        let (&first, rest) = self.bytes.split_first().ok_or_else(truncated)?;
+        self.bytes = rest;
+        Ok(first)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        if self.bytes.len() < 4 {
+            return Err(truncated());
+        }
+        let (word, rest) = self.bytes.split_at(4);
+        self.bytes = rest;
+        Ok(u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+    }
+
+    fn op(&mut self) -> Result<Op, Error> {
+        Ok(match self.u8()? {
+            0 => Op::Tick,
+            1 => Op::Toggle(self.u32()?, self.u32()?),
+            2 => Op::Glider(self.u32()?, self.u32()?),
+            3 => Op::Pulsar(self.u32()?, self.u32()?),
+            4 => Op::Set(self.u32()?, self.u32()? != 0),
+            5 => Op::Jump(self.u32()?),
+            tag => return Err(Error::new(format!("unknown operation {}", tag))),
+        })
+    }
+}
+
+fn truncated() -> Error {
+    Error::new("recording is truncated")
+}
+
+impl Universe {
+    pub(crate) fn record(&mut self, op: Op) {
+        if let Some(recording) = &mut self.recording {
+            op.encode(recording);
+        }
+    }
+
+    fn apply(&mut self, op: Op) -> Result<(), Error> {
+        match op {
+            Op::Tick => self.tick(),
+            Op::Toggle(row, col) => self.toggle_cell(row, col)?,
+            Op::Glider(row, col) => self.insert_glider_at_pos(row, col)?,
+            Op::Pulsar(row, col) => self.insert_pulsar_at_pos(row, col)?,
+            Op::Set(idx, alive) => self.cells.set(idx as usize, alive),
+            Op::Jump(k) => self.jump(k)?,
+        }
+        Ok(())
+    }
+}
+
+/// Check that `op` only touches cells on a `width` by `height` board.
+fn validate(op: Op, width: u32, height: u32) -> Result<(), Error> {
+    let in_range = match op {
+        Op::Tick => true,
+        Op::Jump(k) => k <= MAX_JUMP,
+        Op::Toggle(row, col) | Op::Glider(row, col) | Op::Pulsar(row, col) => {
+            row < height && col < width
+        }
+        Op::Set(idx, _) => (idx as u64) < width as u64 * height as u64,
+    };
+    if in_range {
+        Ok(())
+    } else {
+        Err(Error::new(format!("{:?} is out of range", op)))
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Start recording from the current board, discarding any earlier
+    /// recording.
+    ///
+    /// Captured operations are `tick` (including the ticks run by
+    /// `capture_every`), `toggle_cell`, `insert_glider_at_pos`,
+    /// `insert_pulsar_at_pos`, `jump`, the injections and tick of
+    /// `tick_with_injection`, and the cells set by `insert_rle`,
+    /// `insert_plaintext`, `insert_blob`, `undo` and `redo`. Anything else
+    /// that changes the board, such as the `reset_*` methods, `shuffle` or
+    /// resizing, isn't captured, so a recording spanning those won't replay
+    /// faithfully.
+    pub fn start_recording(&mut self) {
+        let words = self.cells.as_slice();
+        let mut recording = Vec::with_capacity(13 + words.len() * 4);
+        recording.push(VERSION);
+        for value in [self.width, self.height, words.len() as u32].iter() {
+            recording.extend_from_slice(&value.to_le_bytes());
+        }
+        for word in words {
+            recording.extend_from_slice(&word.to_le_bytes());
+        }
+        self.recording = Some(recording);
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recording = None;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// The recording so far, empty if none is in progress.
+    pub fn export_recording(&self) -> Vec<u8> {
+        self.recording.clone().unwrap_or_default()
+    }
+
+    /// Reset the board to a recording's starting state and apply its
+    /// operations in order. Settings such as the boundary mode aren't part
+    /// of a recording, so they should match the ones it was made with.
+    ///
+    /// The board is left untouched if the recording is malformed or an
+    /// operation in it fails.
+    pub fn replay(&mut self, recording: &[u8]) -> Result<(), Error> {
+        let mut reader = Reader { bytes: recording };
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(Error::new(format!(
+                "unsupported recording version {}",
+                version
+            )));
+        }
+        let width = reader.u32()?;
+        let height = reader.u32()?;
+        let size = match width.checked_mul(height) {
+            Some(size) if width != 0 && height != 0 => size as usize,
+            _ => return Err(Error::new(format!("invalid size {} by {}", width, height))),
+        };
+        let word_count = reader.u32()? as usize;
+        if word_count != size.div_ceil(32) {
+            return Err(Error::new("recording header is inconsistent"));
+        }
+        let mut words = Vec::with_capacity(word_count);
+        for _ in 0..word_count {
+            words.push(reader.u32()?);
+        }
+        let mut ops = Vec::new();
+        while !reader.bytes.is_empty() {
+            let op = reader.op()?;
+            validate(op, width, height)?;
+            ops.push(op);
+        }
+
+        // Replay on a copy, so that an operation failing part-way leaves
+        // the board as it was. Replayed operations shouldn't land in a
+        // recording in progress.
+        let mut replayed = self.clone();
+        replayed.recording = None;
+        replayed.width = width;
+        replayed.height = height;
+        replayed.reallocate();
+        replayed.cells = FixedBitSet::with_capacity_and_blocks(size, words);
+        // Nor on the undo stack: the board they leave is a new one.
+        let depth = replayed.undo_depth();
+        replayed.set_undo_depth(0);
+        ops.into_iter().try_for_each(|op| replayed.apply(op))?;
+        replayed.set_undo_depth(depth);
+        replayed.recording = self.recording.take();
+        *self = replayed;
+        Ok(())
+    }
+}
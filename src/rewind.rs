@@ -0,0 +1,181 @@
+//! Stepping the simulation backwards through the last few generations.
+//!
+//! While history is on, every `tick` and `jump` first keeps a frame of
+//! what it is about to change: the packed cells, the generation, the rule,
+//! and the per-cell state of rules that have any, such as dying states or
+//! sand.
+//! Frames live in a ring buffer, so once it is full each tick reuses the
+//! oldest frame's buffers instead of allocating. Cell ages, the heat map
+//! and the random stream behind noise aren't kept, so they don't rewind.
+
+use crate::rule::Automaton;
+use crate::turmite::Ant;
+use crate::wator::Creature;
+use crate::Universe;
+use fixedbitset::FixedBitSet;
+use std::collections::VecDeque;
+use std::mem::{size_of, size_of_val};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// The board before one tick or jump.
+#[derive(Clone)]
+struct Frame {
+    generation: u64,
+    /// The rule the per-cell state is for, which may have been changed
+    /// since.
+    rule: Automaton,
+    cells: FixedBitSet,
+    states: Option<Vec<u8>>,
+    sand: Option<Vec<u32>>,
+    sea: Option<Vec<Creature>>,
+    colors: Option<Vec<u8>>,
+    ants: Vec<Ant>,
+}
+
+impl Frame {
+    /// A frame with empty buffers, for `fill` to copy the board into.
+    fn new(rule: Automaton) -> Frame {
+        Frame {
+            generation: 0,
+            rule,
+            cells: FixedBitSet::new(),
+            states: None,
+            sand: None,
+            sea: None,
+            colors: None,
+            ants: Vec::new(),
+        }
+    }
+
+    /// Copy `universe` into the frame, reusing its buffers where they fit.
+    fn fill(&mut self, universe: &Universe) {
+        self.generation = universe.generation;
+        self.rule.clone_from(&universe.rule);
+        self.cells.clone_from(&universe.cells);
+        self.states.clone_from(&universe.states);
+        self.sand.clone_from(&universe.sand);
+        self.sea.clone_from(&universe.sea);
+        self.colors.clone_from(&universe.colors);
+        self.ants.clone_from(&universe.ants);
+    }
+
+    /// Bytes the frame holds, counting its buffers.
+    fn bytes(&self) -> usize {
+        size_of::<Frame>()
+            + size_of_val(self.cells.as_slice())
+            + self.states.as_deref().map_or(0, size_of_val)
+            + self.sand.as_deref().map_or(0, size_of_val)
+            + self.sea.as_deref().map_or(0, size_of_val)
+            + self.colors.as_deref().map_or(0, size_of_val)
+            + size_of_val(&self.ants[..])
+    }
+}
+
+/// The frames `step_back` can return to, oldest first.
+#[derive(Clone, Default)]
+pub struct Rewind {
+    depth: u32,
+    frames: VecDeque<Frame>,
+}
+
+impl Rewind {
+    /// Forget every frame, as when the board is resized and they no longer
+    /// fit it.
+    pub(crate) fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Bytes the kept frames take up.
+    pub(crate) fn bytes(&self) -> usize {
+        self.frames.iter().map(Frame::bytes).sum()
+    }
+
+    /// Forget the oldest frame. Returns the bytes freed, `None` if there
+    /// was no frame.
+    pub(crate) fn drop_oldest(&mut self) -> Option<usize> {
+        self.frames.pop_front().map(|frame| frame.bytes())
+    }
+}
+
+impl Universe {
+    /// Keep a frame of the board as it is, if history is on, for
+    /// `step_back` to return to.
+    pub(crate) fn remember(&mut self) {
+        let depth = self.rewind.depth as usize;
+        if depth == 0 {
+            return;
+        }
+        let recycled = if self.rewind.frames.len() == depth {
+            self.rewind.frames.pop_front()
+        } else {
+            None
+        };
+        let mut frame = recycled.unwrap_or_else(|| Frame::new(self.rule.clone()));
+        frame.fill(self);
+        self.rewind.frames.push_back(frame);
+        self.enforce_history_budget();
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Go back to the board as it was before the last `tick`, `jump` or
+    /// batch of generations run on the GPU, under the rule it had then.
+    /// The previous generation becomes the board itself, so nothing counts
+    /// as changed. Returns false, changing nothing, if there is no history
+    /// left.
+    pub fn step_back(&mut self) -> bool {
+        let frame = match self.rewind.frames.pop_back() {
+            Some(frame) => frame,
+            None => return false,
+        };
+        if frame.rule != self.rule {
+            self.apply_rule(frame.rule);
+        }
+        self.generation = frame.generation;
+        self.cells = frame.cells;
+        self.prev_cells.clone_from(&self.cells);
+        self.states = frame.states;
+        self.sand = frame.sand;
+        self.sea = frame.sea;
+        self.colors = frame.colors;
+        self.ants = frame.ants;
+        if let Some(shared) = &mut self.shared {
+            shared.publish(&self.cells);
+        }
+        true
+    }
+
+    /// Most ticks `step_back` can undo, 0 while history is off.
+    pub fn history_depth(&self) -> u32 {
+        self.rewind.depth
+    }
+
+    /// Keep the last `depth` ticks for `step_back`, dropping the oldest
+    /// ones beyond that. History is off by default, and a depth of 0 turns
+    /// it off again, so ticks don't pay for copying the board.
+    pub fn set_history_depth(&mut self, depth: u32) {
+        self.rewind.depth = depth;
+        let excess = self.rewind.frames.len().saturating_sub(depth as usize);
+        self.rewind.frames.drain(..excess);
+        if depth == 0 {
+            self.rewind.frames.shrink_to_fit();
+        }
+    }
+
+    /// Number of ticks `step_back` can undo right now.
+    pub fn history_len(&self) -> u32 {
+        self.rewind.frames.len() as u32
+    }
+
+    /// Bytes the kept frames take up.
+    pub fn history_bytes(&self) -> u32 {
+        self.rewind.bytes() as u32
+    }
+
+    /// Forget every kept frame, leaving history on.
+    pub fn clear_history(&mut self) {
+        self.rewind.clear();
+    }
+}
@@ -0,0 +1,365 @@
+use crate::pattern::{Pattern, MAX_CELLS};
+use crate::rule::Automaton;
+use crate::{Error, Universe};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    /// At the start of a line, before the header has been seen.
+    LineStart,
+    /// Inside a `#` comment line.
+    Comment,
+    /// Inside the `x = .., y = ..` header line.
+    Header,
+    /// Reading run-length encoded cells.
+    Body,
+    /// After the terminating `!`.
+    Done,
+}
+
+/// Incremental parser for Run Length Encoded patterns, the format used by
+/// LifeWiki and Golly.
+///
+/// Feed the text in chunks of any size as it arrives and call `finish` at
+/// the end. Tokens such as a multi-digit run count may be split across
+/// chunks. Errors are remembered and reported by `finish`.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub struct RleReader {
+    phase: Phase,
+    header: String,
+    header_size: Option<(u32, u32)>,
+    rule: Option<Automaton>,
+    run: Option<u32>,
+    row: u32,
+    col: u32,
+    live: Vec<(u32, u32)>,
+    error: Option<String>,
+}
+
+impl Default for RleReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl RleReader {
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new() -> RleReader {
+        RleReader {
+            phase: Phase::LineStart,
+            header: String::new(),
+            header_size: None,
+            rule: None,
+            run: None,
+            row: 0,
+            col: 0,
+            live: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &str) {
+        for c in chunk.chars() {
+            if self.error.is_some() || self.phase == Phase::Done {
+                return;
+            }
+            self.push_char(c);
+        }
+    }
+
+    /// Build a universe just large enough for the pattern (or the size given
+    /// in the header, if larger) with the pattern in its top-left corner,
+    /// running the header's rule if it has one.
+    pub fn finish(self) -> Result<Universe, Error> {
+        let (pattern, rule) = self.into_pattern()?;
+        let mut universe = Universe::from_pattern(&pattern);
+        if let Some(rule) = rule {
+            universe.apply_rule(rule);
+        }
+        Ok(universe)
+    }
+}
+
+impl RleReader {
+    /// The pattern, as large as its live cells or the size given in the
+    /// header, whichever is larger, and the header's rule if it has one.
+    fn into_pattern(mut self) -> Result<(Pattern, Option<Automaton>), Error> {
+        if self.phase == Phase::Header {
+            self.parse_header();
+        }
+        if let Some(error) = self.error {
+            return Err(Error::new(error));
+        }
+
+        let (mut width, mut height) = self.header_size.unwrap_or((0, 0));
+        for &(row, col) in &self.live {
+            width = width.max(col + 1);
+            height = height.max(row + 1);
+        }
+        let mut pattern = Pattern::bounded(height, width)?;
+        for &(row, col) in &self.live {
+            pattern.set(row, col, true);
+        }
+        Ok((pattern, self.rule))
+    }
+
+    fn fail(&mut self, message: String) {
+        self.error.get_or_insert(message);
+    }
+
+    fn push_char(&mut self, c: char) {
+        match self.phase {
+            Phase::LineStart => match c {
+                '#' => self.phase = Phase::Comment,
+                'x' | 'X' if self.header_size.is_none() => {
+                    self.header.push(c);
+                    self.phase = Phase::Header;
+                }
+                c if c.is_whitespace() => {}
+                c => {
+                    self.phase = Phase::Body;
+                    self.push_char(c);
+                }
+            },
+            Phase::Comment => {
+                if c == '\n' {
+                    self.phase = Phase::LineStart;
+                }
+            }
+            Phase::Header => {
+                if c == '\n' {
+                    self.parse_header();
+                    self.phase = Phase::LineStart;
+                } else {
+                    self.header.push(c);
+                }
+            }
+            Phase::Body => self.push_body_char(c),
+            Phase::Done => {}
+        }
+    }
+
+    fn push_body_char(&mut self, c: char) {
+        if let Some(digit) = c.to_digit(10) {
+            let run = self.run.unwrap_or(0);
+            match run.checked_mul(10).and_then(|r| r.checked_add(digit)) {
+                Some(run) => self.run = Some(run),
+                None => self.fail("run count too large".to_string()),
+            }
+            return;
+        }
+        if c.is_whitespace() {
+            return;
+        }
+
+        let run = self.run.take().unwrap_or(1);
+        match c {
+            'b' | '.' => self.col = self.col.saturating_add(run),
+            '$' => {
+                self.row = self.row.saturating_add(run);
+                self.col = 0;
+            }
+            '!' => self.phase = Phase::Done,
+            c if c.is_ascii_alphabetic() => {
+                if self.live.len() as u64 + run as u64 > MAX_CELLS {
+                    self.fail(format!("pattern has more than {} live cells", MAX_CELLS));
+                    return;
+                }
+                for col in self.col..self.col.saturating_add(run) {
+                    self.live.push((self.row, col));
+                }
+                self.col = self.col.saturating_add(run);
+            }
+            c => self.fail(format!("unexpected character {:?} in pattern", c)),
+        }
+    }
+
+    fn parse_header(&mut self) {
+        let header = std::mem::take(&mut self.header);
+        let (mut width, mut height, mut rule) = (None, None, None);
+        for field in header.split(',') {
+            let mut parts = field.splitn(2, '=');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next().map(str::trim);
+            match (key, value) {
+                ("x", Some(value)) => width = value.parse::<u32>().ok(),
+                ("y", Some(value)) => height = value.parse::<u32>().ok(),
+                ("rule", Some(value)) => rule = Some(value),
+                _ => {}
+            }
+        }
+        match (width, height) {
+            (Some(width), Some(height)) => self.header_size = Some((width, height)),
+            _ => return self.fail(format!("malformed header line {:?}", header)),
+        }
+        if let Some(rule) = rule {
+            match Automaton::parse(rule) {
+                Ok(parsed) => self.rule = Some(parsed),
+                Err(_) => self.fail(format!("unsupported rule {:?}", rule)),
+            }
+        }
+    }
+}
+
+/// Longest body line written, as recommended for RLE files.
+const MAX_LINE_LEN: usize = 70;
+
+/// Collects RLE tokens, merging runs and wrapping lines.
+struct RleWriter {
+    out: String,
+    line_len: usize,
+    pending: Option<(char, u32)>,
+}
+
+impl RleWriter {
+    fn new(header: String) -> RleWriter {
+        RleWriter {
+            out: header,
+            line_len: 0,
+            pending: None,
+        }
+    }
+
+    fn push(&mut self, tag: char, run: u32) {
+        match &mut self.pending {
+            Some((pending_tag, pending_run)) if *pending_tag == tag => *pending_run += run,
+            _ => {
+                self.flush();
+                self.pending = Some((tag, run));
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some((tag, run)) = self.pending.take() {
+            let token = if run == 1 {
+                tag.to_string()
+            } else {
+                format!("{}{}", run, tag)
+            };
+            if self.line_len + token.len() > MAX_LINE_LEN {
+                self.out.push('\n');
+                self.line_len = 0;
+            }
+            self.line_len += token.len();
+            self.out.push_str(&token);
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.push('!', 1);
+        self.flush();
+        self.out.push('\n');
+        self.out
+    }
+}
+
+/// Encode `pattern` as RLE with an `x`, `y` and `rule` header. Trailing
+/// dead cells in a row and trailing empty rows are left out.
+pub(crate) fn encode(pattern: &Pattern, rule: &str) -> String {
+    let header = format!(
+        "x = {}, y = {}, rule = {}\n",
+        pattern.cols, pattern.rows, rule
+    );
+    let mut writer = RleWriter::new(header);
+    let mut empty_rows = 0;
+    for row in 0..pattern.rows {
+        let last_alive = (0..pattern.cols).rev().find(|&col| pattern.get(row, col));
+        let last_alive = match last_alive {
+            Some(col) => col,
+            None => {
+                empty_rows += 1;
+                continue;
+            }
+        };
+        if row > 0 {
+            writer.push('$', empty_rows + 1);
+        }
+        empty_rows = 0;
+        for col in 0..=last_alive {
+            writer.push(if pattern.get(row, col) { 'o' } else { 'b' }, 1);
+        }
+    }
+    writer.finish()
+}
+
+impl Universe {
+    /// The cells of the `height` by `width` region at `(row, col)`, or
+    /// `None` if it runs off the board.
+    fn region_pattern(&self, row: u32, col: u32, height: u32, width: u32) -> Option<Pattern> {
+        if row.checked_add(height)? > self.height || col.checked_add(width)? > self.width {
+            return None;
+        }
+        let mut pattern = Pattern::new(height, width);
+        for d_row in 0..height {
+            for d_col in 0..width {
+                let idx = self.get_index(row + d_row, col + d_col);
+                pattern.set(d_row, d_col, self.cells[idx]);
+            }
+        }
+        Some(pattern)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Parse a whole RLE pattern at once. See `RleReader` for streaming.
+    pub fn from_rle(rle: &str) -> Result<Universe, Error> {
+        let mut reader = RleReader::new();
+        reader.feed(rle);
+        reader.finish()
+    }
+
+    /// Stamp an RLE pattern, header and all, with its top-left corner at
+    /// `(row, col)`, overwriting every cell of the box the header gives (or
+    /// of the pattern, if larger). Cells past an edge wrap or are dropped
+    /// according to the boundary mode, but the corner itself must be on
+    /// the board. The header's rule is ignored; the universe keeps its own.
+    pub fn insert_rle(&mut self, row: u32, col: u32, rle: &str) -> Result<(), Error> {
+        self.checked_index(row, col)?;
+        let mut reader = RleReader::new();
+        reader.feed(rle);
+        let (pattern, _) = reader.into_pattern()?;
+        self.begin_edit();
+        self.stamp_pattern(row, col, &pattern);
+        self.end_edit();
+        Ok(())
+    }
+
+    /// The live cells' bounding box as RLE, with an `x`, `y` and `rule`
+    /// header, ready to save or paste into Golly or LifeWiki.
+    pub fn to_pattern(&self) -> String {
+        encode(&self.extract_pattern(), &self.rule())
+    }
+
+    /// The whole board as RLE, with the board's size in the header, so it
+    /// loads back at the same size with `from_rle`.
+    pub fn to_rle(&self) -> String {
+        self.region_pattern(0, 0, self.height, self.width)
+            .map(|pattern| encode(&pattern, &self.rule()))
+            .unwrap_or_default()
+    }
+
+    /// The `height` by `width` region with its top-left corner at
+    /// `(row, col)` as RLE, with the region's size in the header. Errors
+    /// unless the region lies on the board.
+    pub fn region_to_rle(
+        &self,
+        row: u32,
+        col: u32,
+        height: u32,
+        width: u32,
+    ) -> Result<String, Error> {
+        let pattern = self
+            .region_pattern(row, col, height, width)
+            .ok_or_else(|| {
+                Error::new(format!(
+                    "region of {} by {} at ({}, {}) is outside the {} by {} board",
+                    height, width, row, col, self.width, self.height
+                ))
+            })?;
+        Ok(encode(&pattern, &self.rule()))
+    }
+}
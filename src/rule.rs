@@ -0,0 +1,574 @@
+use crate::colored::ColoredLife;
+use crate::elementary::ElementaryRule;
+use crate::engine::{self, Neighborhood};
+use crate::forest::ForestFire;
+use crate::hensel::IsotropicRule;
+use crate::ltl::LtlRule;
+use crate::margolus::MargolusRule;
+use crate::rng::Rng;
+use crate::rule_file::RuleFile;
+use crate::sandpile::Sandpile;
+use crate::stochastic::COUNTS;
+use crate::wator::WaTor;
+use crate::wireworld::Wireworld;
+use crate::{Error, FillMode, Universe};
+use std::fmt;
+use std::sync::Arc;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Well-known Life-like and Generations rules, for offering a choice
+/// without B/S notation.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// B3/S23, Conway's Game of Life.
+    Conway = 0,
+    /// B36/S23, Life plus a small replicator.
+    HighLife = 1,
+    /// B3678/S34678, symmetric under swapping live and dead cells.
+    DayAndNight = 2,
+    /// B2/S, where every cell dies after one generation.
+    Seeds = 3,
+    /// B3/S012345678, where live cells never die.
+    LifeWithoutDeath = 4,
+    /// B3/S12345, which grows maze-like corridors.
+    Maze = 5,
+    /// B4678/S35678, which smooths noise into blobs.
+    Anneal = 6,
+    /// B2/S/C3, where every live cell spends a generation dying.
+    BriansBrain = 7,
+}
+
+impl Rule {
+    const ALL: [Rule; 8] = [
+        Rule::Conway,
+        Rule::HighLife,
+        Rule::DayAndNight,
+        Rule::Seeds,
+        Rule::LifeWithoutDeath,
+        Rule::Maze,
+        Rule::Anneal,
+        Rule::BriansBrain,
+    ];
+
+    fn rulestring(self) -> &'static str {
+        match self {
+            Rule::Conway => "B3/S23",
+            Rule::HighLife => "B36/S23",
+            Rule::DayAndNight => "B3678/S34678",
+            Rule::Seeds => "B2/S",
+            Rule::LifeWithoutDeath => "B3/S012345678",
+            Rule::Maze => "B3/S12345",
+            Rule::Anneal => "B4678/S35678",
+            Rule::BriansBrain => "B2/S/C3",
+        }
+    }
+
+    fn life_rule(self) -> LifeRule {
+        LifeRule::parse(self.rulestring()).unwrap()
+    }
+}
+
+/// A Life-like rule: which neighbour counts bring a dead cell to life and
+/// which keep a live cell alive. Bit `n` of each mask stands for a count
+/// of `n`.
+///
+/// With more than two `states` this is a Generations rule: a live cell
+/// that doesn't survive passes through states `2..states` before it is
+/// dead again, counting as neither alive nor dead on the way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LifeRule {
+    birth: u16,
+    survival: u16,
+    states: u8,
+}
+
+impl LifeRule {
+    pub const CONWAY: LifeRule = LifeRule {
+        birth: 1 << 3,
+        survival: 1 << 2 | 1 << 3,
+        states: 2,
+    };
+
+    /// Number of cell states, 2 unless this is a Generations rule.
+    pub fn states(self) -> u8 {
+        self.states
+    }
+
+    /// Neighbour counts that bring a dead cell to life, as a mask.
+    pub(crate) fn birth(self) -> u16 {
+        self.birth
+    }
+
+    /// Neighbour counts that keep a live cell alive, as a mask.
+    pub(crate) fn survival(self) -> u16 {
+        self.survival
+    }
+
+    /// Parse a rule in `B36/S23` notation, in either order, or in the older
+    /// `23/36` survival/birth notation. Letters may be either case. The
+    /// names of the `Rule` presets, and `LIFE` for Conway's rule, are also
+    /// accepted, ignoring case.
+    ///
+    /// Generations rules add a state count, as in `B2/S/C3` or `/2/3`
+    /// (Brian's Brain); `G` may be used instead of `C`.
+    ///
+    /// Counts run from 0 to 9; 9 can only be reached with `include_center`.
+    pub fn parse(rule: &str) -> Result<LifeRule, String> {
+        let rule = rule.trim().to_ascii_uppercase();
+        if rule == "LIFE" {
+            return Ok(LifeRule::CONWAY);
+        }
+        if let Some(preset) = Rule::ALL
+            .iter()
+            .find(|preset| format!("{:?}", preset).to_ascii_uppercase() == rule)
+        {
+            return LifeRule::parse(preset.rulestring());
+        }
+        let invalid = || format!("invalid rule {:?}", rule);
+        let (birth, survival, states) = split_rulestring(&rule).ok_or_else(invalid)?;
+        Ok(LifeRule {
+            birth: parse_counts(birth).ok_or_else(invalid)?,
+            survival: parse_counts(survival).ok_or_else(invalid)?,
+            states,
+        })
+    }
+}
+
+/// Split an upper-case rulestring in `B../S..`, `S../B..` or `../..`
+/// notation, with an optional `/C..` or `/..` state count, into its birth
+/// part, survival part and number of states.
+pub(crate) fn split_rulestring(rule: &str) -> Option<(&str, &str, u8)> {
+    let parts: Vec<&str> = rule.split('/').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+    let lettered = |part: &&str| part.starts_with(|c: char| c.is_ascii_alphabetic());
+    let (birth, survival, states) = if parts.iter().all(lettered) {
+        let (mut birth, mut survival, mut states) = (None, None, None);
+        for part in &parts {
+            let slot = match &part[..1] {
+                "B" => &mut birth,
+                "S" => &mut survival,
+                "C" | "G" => &mut states,
+                _ => return None,
+            };
+            if slot.replace(&part[1..]).is_some() {
+                return None;
+            }
+        }
+        (birth?, survival?, states)
+    } else if parts.iter().any(lettered) {
+        return None;
+    } else {
+        (parts[1], parts[0], parts.get(2).cloned())
+    };
+    let states = match states {
+        Some(states) => states.parse::<u8>().ok().filter(|&n| n >= 2)?,
+        None => 2,
+    };
+    Some((birth, survival, states))
+}
+
+/// An arbitrary two-state rule on the 3x3 block around each cell, given as
+/// the next state for every one of the 512 possible blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TableRule {
+    /// One bit per block mask, as laid out by `neighborhood_mask`.
+    table: [u64; 8],
+}
+
+/// Prefix of the textual form of a `TableRule`.
+const TABLE_PREFIX: &str = "TABLE:";
+
+impl TableRule {
+    /// Build a rule from 512 next states, nonzero meaning alive.
+    pub fn from_bytes(states: &[u8]) -> Option<TableRule> {
+        if states.len() != 512 {
+            return None;
+        }
+        let mut table = [0u64; 8];
+        for (mask, &state) in states.iter().enumerate() {
+            table[mask / 64] |= ((state != 0) as u64) << (mask % 64);
+        }
+        Some(TableRule { table })
+    }
+
+    /// Parse the `table:` form written by `Display`: the prefix followed
+    /// by the table as 128 hexadecimal digits, four blocks per digit with
+    /// the lowest block mask in the lowest bit of the first digit.
+    pub fn parse(rule: &str) -> Result<TableRule, String> {
+        let rule = rule.trim().to_ascii_uppercase();
+        let invalid = || format!("invalid rule {:?}", rule);
+        let digits = rule.strip_prefix(TABLE_PREFIX).ok_or_else(invalid)?;
+        if digits.len() != 128 {
+            return Err(invalid());
+        }
+        let mut table = [0u64; 8];
+        for (i, c) in digits.chars().enumerate() {
+            let nibble = c.to_digit(16).ok_or_else(invalid)? as u64;
+            table[i / 16] |= nibble << (4 * (i % 16));
+        }
+        Ok(TableRule { table })
+    }
+}
+
+impl fmt::Display for TableRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&TABLE_PREFIX.to_ascii_lowercase())?;
+        for i in 0..128 {
+            let nibble = self.table[i / 16] >> (4 * (i % 16)) & 0xf;
+            write!(f, "{:x}", nibble)?;
+        }
+        Ok(())
+    }
+}
+
+/// Any rule the engine can run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Automaton {
+    Life(LifeRule),
+    Isotropic(IsotropicRule),
+    LargerThanLife(LtlRule),
+    Table(TableRule),
+    Margolus(MargolusRule),
+    Wireworld(Wireworld),
+    Elementary(ElementaryRule),
+    Sandpile(Sandpile),
+    ForestFire(ForestFire),
+    WaTor(WaTor),
+    Colored(ColoredLife),
+    RuleFile(Arc<RuleFile>),
+}
+
+impl Automaton {
+    /// Parse a rule in any notation accepted by `LifeRule::parse`,
+    /// `IsotropicRule::parse`, `LtlRule::parse`, `TableRule::parse`,
+    /// `MargolusRule::parse`, `ElementaryRule::parse`, `ForestFire::parse`,
+    /// `WaTor::parse` or `ColoredLife::parse`, or `Wireworld` or
+    /// `Sandpile`.
+    pub fn parse(rule: &str) -> Result<Automaton, String> {
+        LifeRule::parse(rule).map(Automaton::Life).or_else(|error| {
+            IsotropicRule::parse(rule)
+                .map(Automaton::Isotropic)
+                .or_else(|_| LtlRule::parse(rule).map(Automaton::LargerThanLife))
+                .or_else(|_| TableRule::parse(rule).map(Automaton::Table))
+                .or_else(|_| MargolusRule::parse(rule).map(Automaton::Margolus))
+                .or_else(|_| Wireworld::parse(rule).map(Automaton::Wireworld))
+                .or_else(|_| ElementaryRule::parse(rule).map(Automaton::Elementary))
+                .or_else(|_| Sandpile::parse(rule).map(Automaton::Sandpile))
+                .or_else(|_| ForestFire::parse(rule).map(Automaton::ForestFire))
+                .or_else(|_| WaTor::parse(rule).map(Automaton::WaTor))
+                .or_else(|_| ColoredLife::parse(rule).map(Automaton::Colored))
+                .or(Err(error))
+        })
+    }
+
+    /// Number of cell states, more than 2 if live cells die gradually.
+    pub fn states(&self) -> u8 {
+        match self {
+            Automaton::Life(rule) => rule.states(),
+            Automaton::Isotropic(rule) => rule.states(),
+            Automaton::LargerThanLife(rule) => rule.states(),
+            Automaton::Table(_)
+            | Automaton::Margolus(_)
+            | Automaton::Elementary(_)
+            | Automaton::Colored(_) => 2,
+            Automaton::ForestFire(_) | Automaton::WaTor(_) => 3,
+            Automaton::Wireworld(_) => 4,
+            Automaton::Sandpile(_) => 5,
+            Automaton::RuleFile(rule) => rule.states(),
+        }
+    }
+}
+
+impl fmt::Display for Automaton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Automaton::Life(rule) => rule.fmt(f),
+            Automaton::Isotropic(rule) => rule.fmt(f),
+            Automaton::LargerThanLife(rule) => rule.fmt(f),
+            Automaton::Table(rule) => rule.fmt(f),
+            Automaton::Margolus(rule) => rule.fmt(f),
+            Automaton::Wireworld(rule) => rule.fmt(f),
+            Automaton::Elementary(rule) => rule.fmt(f),
+            Automaton::Sandpile(rule) => rule.fmt(f),
+            Automaton::ForestFire(rule) => rule.fmt(f),
+            Automaton::WaTor(rule) => rule.fmt(f),
+            Automaton::Colored(rule) => rule.fmt(f),
+            Automaton::RuleFile(rule) => rule.fmt(f),
+        }
+    }
+}
+
+impl engine::Rule for LifeRule {
+    #[inline]
+    fn next_state(&self, current: u8, neighborhood: &Neighborhood) -> u8 {
+        let count = neighborhood.live_count();
+        let born = self.birth >> count & 1 == 1;
+        let survives = self.survival >> count & 1 == 1;
+        engine::life_like(self.states, current, born, survives)
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+/// A Life-like rule as the next state for every current state and live
+/// count, built once when the rule is set so each cell is a single lookup
+/// rather than a test of the birth and survival masks and of decay.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LifeTable {
+    /// Entry `COUNTS * state + count`, with a row long enough for the
+    /// largest count any neighbourhood gives.
+    next: Vec<u8>,
+}
+
+impl LifeTable {
+    pub fn new(rule: LifeRule) -> LifeTable {
+        let next = (0..rule.states)
+            .flat_map(|state| {
+                (0..COUNTS).map(move |count| {
+                    let born = rule.birth >> count & 1 == 1;
+                    let survives = rule.survival >> count & 1 == 1;
+                    engine::life_like(rule.states, state, born, survives)
+                })
+            })
+            .collect();
+        LifeTable { next }
+    }
+}
+
+impl engine::Rule for LifeTable {
+    #[inline]
+    fn next_state(&self, current: u8, neighborhood: &Neighborhood) -> u8 {
+        // States the rule doesn't have die, as in `engine::life_like`.
+        let entry = COUNTS * current as usize + neighborhood.live_count() as usize;
+        self.next.get(entry).copied().unwrap_or(0)
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+impl engine::Rule for TableRule {
+    #[inline]
+    fn next_state(&self, _current: u8, neighborhood: &Neighborhood) -> u8 {
+        let mask = neighborhood.block();
+        (self.table[mask as usize / 64] >> (mask % 64) & 1) as u8
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+/// Turn a string of distinct digits into a count mask.
+fn parse_counts(digits: &str) -> Option<u16> {
+    let mut mask = 0u16;
+    for c in digits.chars() {
+        let bit = 1 << c.to_digit(10)?;
+        if mask & bit != 0 {
+            return None;
+        }
+        mask |= bit;
+    }
+    Some(mask)
+}
+
+impl fmt::Display for LifeRule {
+    /// Canonical `B3/S23` form, with the counts in ascending order, or
+    /// `B2/S/C3` form for a Generations rule.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = |mask: u16| -> String {
+            (0..10)
+                .filter(|n| mask >> n & 1 == 1)
+                .map(|n| std::char::from_digit(n, 10).unwrap())
+                .collect()
+        };
+        write!(f, "B{}/S{}", counts(self.birth), counts(self.survival))?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
+
+impl Universe {
+    /// Switch rules, building the lookup table of Life-like rules and
+    /// allocating the dying-state buffer, the grain counts,
+    /// the Wa-Tor counters and the cell colours if the new rule needs them
+    /// and dropping them if not. Dying states only carry over to a rule of
+    /// the same kind with as many states; otherwise those cells go dead.
+    pub(crate) fn apply_rule(&mut self, rule: Automaton) {
+        self.life_table = match &rule {
+            Automaton::Life(rule) => Some(LifeTable::new(*rule)),
+            Automaton::Colored(rule) => Some(LifeTable::new(rule.life())),
+            _ => None,
+        };
+        self.sand = match &rule {
+            Automaton::Sandpile(_) => self
+                .sand
+                .take()
+                .or_else(|| Some(vec![0; (self.width * self.height) as usize])),
+            _ => None,
+        };
+        self.sea = match &rule {
+            Automaton::WaTor(wator) => Some(self.sea.take().unwrap_or_else(|| {
+                (0..(self.width * self.height) as usize)
+                    .map(|_| wator.newborn())
+                    .collect()
+            })),
+            _ => None,
+        };
+        self.colors = match &rule {
+            Automaton::Colored(_) => self
+                .colors
+                .take()
+                .or_else(|| Some(vec![0; (self.width * self.height) as usize])),
+            _ => None,
+        };
+        let same_states = std::mem::discriminant(&rule) == std::mem::discriminant(&self.rule)
+            && rule.states() == self.rule.states();
+        if rule.states() == 2 {
+            self.states = None;
+        } else if self.states.is_none() || !same_states {
+            self.states = Some(vec![0; (self.width * self.height) as usize]);
+        }
+        self.rule = rule;
+    }
+
+    /// Put the cell at `idx` in `state`, which the rule must have.
+    pub(crate) fn put_state(&mut self, idx: usize, state: u8) {
+        self.touch(idx);
+        self.cells.set(idx, state == 1);
+        if let Some(states) = &mut self.states {
+            states[idx] = state;
+        }
+        if let (Some(sea), Automaton::WaTor(rule)) = (&mut self.sea, &self.rule) {
+            sea[idx] = rule.newborn();
+        }
+    }
+
+    /// State of the cell at `idx`. Live cells are tracked by `cells`, so
+    /// edits made there take precedence over the dying-state buffer.
+    pub(crate) fn cell_state(&self, idx: usize) -> u8 {
+        if self.cells[idx] {
+            return 1;
+        }
+        match &self.states {
+            Some(states) if states[idx] >= 2 => states[idx],
+            _ => 0,
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// The current rule in canonical notation: `B3/S23` for Life-like
+    /// rules, `B2/S/C3` for Generations rules, `B2-a/S12` for isotropic
+    /// non-totalistic rules, `R5,C0,M1,S34..58,B34..45,NM` for Larger than
+    /// Life rules, `table:` followed by 128 hexadecimal digits for
+    /// transition tables, `M` followed by 16 blocks for Margolus block
+    /// rules, and `W` followed by the rule number for elementary cellular
+    /// automata.
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Switch to a rule such as `"B36/S23"` (HighLife), `"B2/S"` (Seeds),
+    /// `"B3/S2-i34q"` (tlife), `"R5,C0,M1,S34..58,B34..45,NM"` (Bosco's
+    /// rule), `"Critters"`, `"Wireworld"`, `"W110"`, `"Sandpile"` or
+    /// `"QuadLife"`. See
+    /// `LifeRule::parse`, `IsotropicRule::parse`, `LtlRule::parse`,
+    /// `MargolusRule::parse` and `ElementaryRule::parse` for the accepted
+    /// notations. The board is left as it is.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), Error> {
+        let rule = Automaton::parse(rule)?;
+        self.apply_rule(rule);
+        Ok(())
+    }
+
+    /// Switch to an arbitrary two-state rule given as the next state of a
+    /// cell for each of the 512 possible 3x3 blocks around it, nonzero
+    /// meaning alive. Entry `i` is for the block that has the cell at
+    /// `(d_row, d_col)` from its top-left corner alive exactly when bit
+    /// `3 * d_row + d_col` of `i` is set, so bit 4 is the cell itself. In
+    /// bounded mode off-grid cells take the background state.
+    ///
+    /// Errors unless `table` has 512 entries. The board is left as it is.
+    pub fn set_transition_table(&mut self, table: &[u8]) -> Result<(), Error> {
+        let rule = TableRule::from_bytes(table)
+            .ok_or_else(|| Error::new(format!("expected 512 entries, got {}", table.len())))?;
+        self.apply_rule(Automaton::Table(rule));
+        Ok(())
+    }
+
+    /// Switch to one of the built-in rules. The board is left as it is.
+    pub fn set_rule_preset(&mut self, preset: Rule) {
+        self.apply_rule(Automaton::Life(preset.life_rule()));
+    }
+
+    /// The state of every cell, one byte per cell, row by row: 0 dead,
+    /// 1 alive, and for a Generations rule `2..states` dying, larger
+    /// values having been dying for longer. Under Wireworld they are
+    /// empty, electron head, electron tail and conductor, and under
+    /// Sandpile the number of grains up to 4. Forest fires have empty
+    /// cells, trees and burning trees, and Wa-Tor has water, fish and
+    /// sharks.
+    pub fn cell_states(&self) -> Vec<u8> {
+        (0..(self.width * self.height) as usize)
+            .map(|idx| self.cell_state(idx))
+            .collect()
+    }
+
+    /// Put the cell at `(row, col)` in `state`, numbered as in
+    /// `cell_states`. Errors if the cell is off the board or the current
+    /// rule has no such state. Unlike `set_cell`, this isn't recorded.
+    pub fn set_cell_state(&mut self, row: u32, col: u32, state: u8) -> Result<(), Error> {
+        let idx = self.checked_index(row, col)?;
+        if state >= self.rule.states() {
+            return Err(Error::new(format!(
+                "state {} is not one of the {} states of {}",
+                state,
+                self.rule.states(),
+                self.rule
+            )));
+        }
+        self.begin_edit();
+        self.put_state(idx, state);
+        self.end_edit();
+        Ok(())
+    }
+
+    /// A board running Brian's Brain, seeded so it gets going well: a
+    /// random square in the middle, a quarter of the board across and a
+    /// third full, that throws off spaceships in every direction. The
+    /// same `seed` always gives the same start.
+    pub fn brians_brain(width: u32, height: u32, seed: u64) -> Universe {
+        let mut universe = Universe::with_size(width, height, Some(FillMode::Clear));
+        universe.apply_rule(Automaton::Life(Rule::BriansBrain.life_rule()));
+        let mut rng = Rng::new(seed);
+        let (rows, cols) = (height / 4, width / 4);
+        for row in (height - rows) / 2..(height + rows) / 2 {
+            for col in (width - cols) / 2..(width + cols) / 2 {
+                let idx = universe.get_index(row, col);
+                universe.cells.set(idx, rng.next_f64() < 1.0 / 3.0);
+            }
+        }
+        universe.prev_cells.clone_from(&universe.cells);
+        universe
+    }
+
+    /// The built-in rule matching the current one, if any.
+    pub fn rule_preset(&self) -> Option<Rule> {
+        Rule::ALL
+            .iter()
+            .cloned()
+            .find(|preset| Automaton::Life(preset.life_rule()) == self.rule)
+    }
+}
@@ -0,0 +1,428 @@
+//! Golly rule files: an `@RULE` line naming the rule, then an `@TABLE` or
+//! `@TREE` section saying how it works. Other sections, such as
+//! `@COLORS` and `@ICONS`, are skipped.
+//!
+//! A table lists transitions, each giving the states of a cell and its
+//! neighbours and the state it goes to. A tree is a decision tree over
+//! the same states. Either way the rule is compiled into a flat table with
+//! an entry for every combination of states, so a cell's next state is a
+//! single lookup.
+
+use crate::engine::{self, Neighborhood};
+use crate::rule::Automaton;
+use crate::{Error, Universe};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Most entries a compiled rule may have, which allows up to 6 states on
+/// the Moore neighbourhood or 27 on the von Neumann neighbourhood.
+const MAX_TABLE: usize = 1 << 24;
+
+/// The cells a rule file's rule looks at besides the cell itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Neighbors {
+    /// The eight cells around it.
+    Moore,
+    /// The four cells above, left, right and below it.
+    VonNeumann,
+}
+
+impl Neighbors {
+    fn count(self) -> usize {
+        match self {
+            Neighbors::Moore => 8,
+            Neighbors::VonNeumann => 4,
+        }
+    }
+
+    /// For each neighbour of a table transition, which go clockwise from
+    /// the one above, its place in the order of a tree: north-west,
+    /// north-east, south-west, south-east, north, west, east, south on the
+    /// Moore neighbourhood, and north, west, east, south on the von Neumann
+    /// neighbourhood.
+    fn tree_order(self) -> &'static [usize] {
+        match self {
+            Neighbors::Moore => &[4, 1, 6, 3, 7, 2, 5, 0],
+            Neighbors::VonNeumann => &[0, 2, 3, 1],
+        }
+    }
+}
+
+/// A rule loaded from a Golly rule file, compiled into a table of next
+/// states.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleFile {
+    name: String,
+    states: u8,
+    neighbors: Neighbors,
+    /// The next state for every combination of states of the neighbours,
+    /// in the order of a tree, then of the cell, each a digit in base
+    /// `states` with the first neighbour most significant.
+    next: Vec<u8>,
+}
+
+/// Split a line into its tokens: numbers and variable names, separated by
+/// commas or whitespace.
+fn tokens(line: &str) -> Vec<&str> {
+    line.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+/// `key:value` or `key=value`, with `key` matched ignoring case.
+fn setting<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let (name, value) = line.split_once([':', '='])?;
+    name.trim().eq_ignore_ascii_case(key).then(|| value.trim())
+}
+
+/// The flat table's size for `states` states on `neighbors`, if it isn't
+/// too large.
+fn table_size(states: u8, neighbors: Neighbors) -> Result<usize, String> {
+    (0..=neighbors.count())
+        .try_fold(1usize, |size, _| size.checked_mul(states as usize))
+        .filter(|&size| size <= MAX_TABLE)
+        .ok_or_else(|| {
+            format!(
+                "{} states are too many to compile on {} neighbours",
+                states,
+                neighbors.count()
+            )
+        })
+}
+
+/// Every arrangement of `values`, the neighbours clockwise from the one
+/// above, that the table's `symmetries` treat as the same.
+fn arrangements(values: &[u8], symmetries: &str) -> Vec<Vec<u8>> {
+    let n = values.len();
+    if symmetries == "permute" {
+        // Every distinct ordering, stepping through them in lexicographic
+        // order from the sorted one.
+        let mut current = values.to_vec();
+        current.sort_unstable();
+        let mut all = vec![current.clone()];
+        while let Some(i) = (1..n).rev().find(|&i| current[i - 1] < current[i]) {
+            let j = (i..n).rev().find(|&j| current[j] > current[i - 1]).unwrap();
+            current.swap(i - 1, j);
+            current[i..].reverse();
+            all.push(current.clone());
+        }
+        return all;
+    }
+    let (step, reflect) = match symmetries {
+        "rotate4" => (n / 4, false),
+        "rotate8" => (1, false),
+        "reflect_horizontal" => (n, true),
+        "rotate4reflect" => (n / 4, true),
+        "rotate8reflect" => (1, true),
+        _ => (n, false),
+    };
+    let mut all = Vec::new();
+    for turn in (0..n).step_by(step) {
+        let rotated: Vec<u8> = (0..n).map(|i| values[(i + turn) % n]).collect();
+        if reflect {
+            all.push((0..n).map(|i| rotated[(n - i) % n]).collect());
+        }
+        all.push(rotated);
+    }
+    all
+}
+
+impl RuleFile {
+    /// Parse a Golly rule file with an `@TABLE` or `@TREE` section on the
+    /// Moore or von Neumann neighbourhood.
+    pub fn parse(text: &str) -> Result<RuleFile, String> {
+        let mut name = None;
+        let mut sections: HashMap<String, Vec<&str>> = HashMap::new();
+        let mut section = None;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if let Some(header) = line.strip_prefix('@') {
+                let mut words = header.split_whitespace();
+                let heading = words.next().unwrap_or("").to_ascii_uppercase();
+                if heading == "RULE" {
+                    name = words.next().map(str::to_string);
+                }
+                section = Some(sections.entry(heading).or_default());
+            } else if let Some(lines) = &mut section {
+                if !line.is_empty() {
+                    lines.push(line);
+                }
+            }
+        }
+        let name = name.ok_or("rule file has no @RULE line")?;
+        let (states, neighbors, next) = match (sections.get("TABLE"), sections.get("TREE")) {
+            (Some(table), _) => compile_table(table)?,
+            (None, Some(tree)) => compile_tree(tree)?,
+            (None, None) => return Err(format!("rule {} has no @TABLE or @TREE", name)),
+        };
+        Ok(RuleFile {
+            name,
+            states,
+            neighbors,
+            next,
+        })
+    }
+
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+}
+
+/// Compile the lines of an `@TABLE` section into `(states, neighbours,
+/// table)`. Earlier transitions take priority, and cells no transition
+/// covers stay as they are.
+fn compile_table(lines: &[&str]) -> Result<(u8, Neighbors, Vec<u8>), String> {
+    let mut states = None;
+    let mut neighbors = Neighbors::Moore;
+    let mut symmetries = "none".to_string();
+    let mut vars: HashMap<&str, Vec<u8>> = HashMap::new();
+    let mut transitions: Vec<Vec<&str>> = Vec::new();
+    for &line in lines {
+        if let Some(value) = setting(line, "n_states") {
+            states = Some(
+                value
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|&n| n >= 2)
+                    .ok_or_else(|| format!("n_states must be from 2 to 255, not {:?}", value))?,
+            );
+        } else if let Some(value) = setting(line, "neighborhood") {
+            neighbors = match value {
+                "Moore" => Neighbors::Moore,
+                "vonNeumann" => Neighbors::VonNeumann,
+                _ => return Err(format!("unsupported neighborhood {:?}", value)),
+            };
+        } else if let Some(value) = setting(line, "symmetries") {
+            symmetries = value.to_string();
+        } else if let Some(var) = line.strip_prefix("var ") {
+            let (var_name, values) = var
+                .split_once('=')
+                .ok_or_else(|| format!("malformed variable {:?}", line))?;
+            let values = values.trim().trim_start_matches('{').trim_end_matches('}');
+            let mut set = Vec::new();
+            for token in tokens(values) {
+                match (token.parse::<u8>(), vars.get(token)) {
+                    (Ok(state), _) => set.push(state),
+                    (_, Some(values)) => set.extend(values),
+                    _ => return Err(format!("unknown state {:?} in {:?}", token, line)),
+                }
+            }
+            if set.is_empty() {
+                return Err(format!("variable with no states in {:?}", line));
+            }
+            vars.insert(var_name.trim(), set);
+        } else {
+            let expected = neighbors.count() + 2;
+            let mut transition = tokens(line);
+            // Transitions may also be written as a string of digits.
+            if transition.len() == 1 && line.len() == expected {
+                transition = (0..expected).map(|i| &line[i..i + 1]).collect();
+            }
+            if transition.len() != expected {
+                return Err(format!("expected {} states in {:?}", expected, line));
+            }
+            transitions.push(transition);
+        }
+    }
+    let states = states.ok_or("@TABLE has no n_states line")?;
+    let valid = match neighbors {
+        Neighbors::Moore => &[
+            "none",
+            "rotate4",
+            "rotate8",
+            "reflect_horizontal",
+            "rotate4reflect",
+            "rotate8reflect",
+            "permute",
+        ][..],
+        Neighbors::VonNeumann => &[
+            "none",
+            "rotate4",
+            "reflect_horizontal",
+            "rotate4reflect",
+            "permute",
+        ][..],
+    };
+    if !valid.contains(&symmetries.as_str()) {
+        return Err(format!("unsupported symmetries {:?}", symmetries));
+    }
+    if let Some(state) = vars.values().flatten().find(|&&state| state >= states) {
+        return Err(format!("state {} is out of range", state));
+    }
+
+    let size = table_size(states, neighbors)?;
+    let s = states as usize;
+    let mut next: Vec<u8> = (0..size).map(|idx| (idx % s) as u8).collect();
+    for transition in transitions.iter().rev() {
+        // Each variable takes the same value everywhere it appears, so try
+        // every combination of values of the distinct ones.
+        let mut names: Vec<&str> = Vec::new();
+        for &token in transition {
+            if token.parse::<u8>().is_err() && !names.contains(&token) {
+                if !vars.contains_key(token) {
+                    return Err(format!("unknown variable {:?}", token));
+                }
+                names.push(token);
+            }
+        }
+        // Arrangements are only worked out once for each cell and set of
+        // neighbours, which in any order are the same when they permute.
+        let mut seen = HashSet::new();
+        let mut choice = vec![0; names.len()];
+        loop {
+            let value = |token: &str| match token.parse::<u8>() {
+                Ok(state) => state,
+                Err(_) => {
+                    let var = names.iter().position(|&name| name == token).unwrap();
+                    vars[token][choice[var]]
+                }
+            };
+            let values: Vec<u8> = transition.iter().map(|&token| value(token)).collect();
+            if let Some(&state) = values.iter().find(|&&state| state >= states) {
+                return Err(format!("state {} is out of range", state));
+            }
+            let (current, output) = (values[0] as usize, values[values.len() - 1]);
+            let mut around = values[1..values.len() - 1].to_vec();
+            if symmetries == "permute" {
+                around.sort_unstable();
+            }
+            if seen.insert((current, around.clone())) {
+                for arrangement in arrangements(&around, &symmetries) {
+                    let mut in_tree_order = [0u8; 8];
+                    for (&state, &place) in arrangement.iter().zip(neighbors.tree_order()) {
+                        in_tree_order[place] = state;
+                    }
+                    let idx = in_tree_order[..neighbors.count()]
+                        .iter()
+                        .fold(0, |idx, &state| idx * s + state as usize);
+                    next[idx * s + current] = output;
+                }
+            }
+            // Move on to the next combination, like an odometer.
+            let var = (0..names.len()).find(|&var| {
+                choice[var] += 1;
+                if choice[var] < vars[names[var]].len() {
+                    return true;
+                }
+                choice[var] = 0;
+                false
+            });
+            if var.is_none() {
+                break;
+            }
+        }
+    }
+    Ok((states, neighbors, next))
+}
+
+/// Compile the lines of an `@TREE` section into `(states, neighbours,
+/// table)`.
+fn compile_tree(lines: &[&str]) -> Result<(u8, Neighbors, Vec<u8>), String> {
+    let mut states = None;
+    let mut neighbors = None;
+    let mut nodes: Vec<(usize, Vec<usize>)> = Vec::new();
+    for &line in lines {
+        if let Some(value) = setting(line, "num_states") {
+            states = value.parse::<u8>().ok().filter(|&n| n >= 2);
+            if states.is_none() {
+                return Err(format!("num_states must be from 2 to 255, not {:?}", value));
+            }
+        } else if let Some(value) = setting(line, "num_neighbors") {
+            neighbors = Some(match value {
+                "8" => Neighbors::Moore,
+                "4" => Neighbors::VonNeumann,
+                _ => return Err(format!("unsupported num_neighbors {:?}", value)),
+            });
+        } else if setting(line, "num_nodes").is_some() {
+            continue;
+        } else {
+            let states = states.ok_or("@TREE has no num_states line before its nodes")?;
+            let malformed = || format!("malformed node {:?}", line);
+            let fields: Vec<usize> = tokens(line)
+                .into_iter()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| malformed())?;
+            let (&level, children) = fields.split_first().ok_or_else(malformed)?;
+            let fits = |&child: &usize| match level {
+                1 => child < states as usize,
+                _ => child < nodes.len() && nodes[child].0 + 1 == level,
+            };
+            if level == 0 || children.len() != states as usize || !children.iter().all(fits) {
+                return Err(malformed());
+            }
+            nodes.push((level, children.to_vec()));
+        }
+    }
+    let states = states.ok_or("@TREE has no num_states line")?;
+    let neighbors = neighbors.ok_or("@TREE has no num_neighbors line")?;
+    let root = nodes.len().checked_sub(1).ok_or("@TREE has no nodes")?;
+    if nodes[root].0 != neighbors.count() + 1 {
+        return Err(format!(
+            "the root of the @TREE should be at level {}",
+            neighbors.count() + 1
+        ));
+    }
+
+    // The tree's levels go through the neighbours in the table's order, so
+    // filling in the table is a walk down every branch.
+    fn fill(nodes: &[(usize, Vec<usize>)], node: usize, idx: usize, next: &mut Vec<u8>) {
+        let (level, children) = &nodes[node];
+        for (state, &child) in children.iter().enumerate() {
+            let idx = idx * children.len() + state;
+            match level {
+                1 => next[idx] = child as u8,
+                _ => fill(nodes, child, idx, next),
+            }
+        }
+    }
+    let mut next = vec![0; table_size(states, neighbors)?];
+    fill(&nodes, root, 0, &mut next);
+    Ok((states, neighbors, next))
+}
+
+impl fmt::Display for RuleFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl engine::Rule for RuleFile {
+    #[inline]
+    fn next_state(&self, current: u8, neighborhood: &Neighborhood) -> u8 {
+        let s = self.states as usize;
+        let idx = match self.neighbors {
+            Neighbors::Moore => neighborhood
+                .moore_states()
+                .iter()
+                .fold(0, |idx, &state| idx * s + state as usize),
+            Neighbors::VonNeumann => neighborhood
+                .orthogonal_states()
+                .iter()
+                .fold(0, |idx, &state| idx * s + state as usize),
+        };
+        self.next[idx * s + current as usize]
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Switch to the rule in a Golly rule file: an `@RULE` line with the
+    /// rule's name and an `@TABLE` or `@TREE` section on the Moore or von
+    /// Neumann neighbourhood, as Golly's `.rule` files have. `rule` then
+    /// gives the name. Errors if the file can't be read or its rule has
+    /// too many states to compile, more than 6 on the Moore neighbourhood
+    /// or 27 on the von Neumann neighbourhood. The board is left as it is.
+    pub fn load_rule_file(&mut self, text: &str) -> Result<(), Error> {
+        let rule = RuleFile::parse(text)?;
+        self.apply_rule(Automaton::RuleFile(Arc::new(rule)));
+        Ok(())
+    }
+}
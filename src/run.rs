@@ -0,0 +1,174 @@
+//! Long runs that tick a few milliseconds at a time, letting the page
+//! handle input and draw in between.
+
+use crate::{utils, CancelToken, Universe};
+use js_sys::{Function, Promise};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
+
+/// How long each chunk of a run ticks before yielding.
+const CHUNK_MILLIS: f64 = 8.0;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(callback: &Function, millis: i32);
+}
+
+/// Resolves on a later turn of the event loop, after anything already
+/// waiting, such as input events and drawing.
+fn yield_now() -> JsFuture {
+    JsFuture::from(Promise::new(&mut |resolve, _| set_timeout(&resolve, 0)))
+}
+
+/// Where a run had got to after its last chunk.
+#[derive(Clone, Copy, Debug)]
+struct Progress {
+    generation: u64,
+    population: u32,
+    remaining: u32,
+}
+
+impl Progress {
+    fn of(universe: &Universe, remaining: u32) -> Progress {
+        Progress {
+            generation: universe.generation,
+            population: universe.population(),
+            remaining,
+        }
+    }
+}
+
+/// A run started by `Universe::run_async`, for following its progress and
+/// getting the universe back when it is done.
+#[wasm_bindgen]
+pub struct UniverseRun {
+    progress: Rc<Cell<Progress>>,
+    cancel: CancelToken,
+    /// What `on_progress` threw or the GPU failed with, if either did.
+    error: Rc<RefCell<Option<JsValue>>>,
+    done: Promise,
+}
+
+#[wasm_bindgen]
+impl UniverseRun {
+    /// The universe's generation as of the last chunk.
+    pub fn generation(&self) -> u64 {
+        self.progress.get().generation
+    }
+
+    /// The universe's population as of the last chunk.
+    pub fn population(&self) -> u32 {
+        self.progress.get().population
+    }
+
+    /// Ticks still to run, 0 once the run is done, however it ended.
+    pub fn remaining(&self) -> u32 {
+        self.progress.get().remaining
+    }
+
+    /// A token that stops this run when cancelled.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    /// What `on_progress` threw or the GPU failed with to stop the run,
+    /// `undefined` if neither did.
+    pub fn error(&self) -> Option<JsValue> {
+        self.error.borrow().clone()
+    }
+
+    /// Resolves to the universe once the run is done.
+    pub fn done(&self) -> Promise {
+        self.done.clone()
+    }
+}
+
+impl Universe {
+    /// Run one chunk of a run with `remaining` ticks to go, and return how
+    /// many are left after it. Under `Engine::Gpu` a chunk is one batch on
+    /// the GPU, of a single generation if a stop condition is on so it is
+    /// checked after each; otherwise it ticks on the CPU for a few
+    /// milliseconds.
+    async fn run_chunk(&mut self, mut remaining: u32) -> Result<u32, JsValue> {
+        #[cfg(all(feature = "gpu", web_sys_unstable_apis, target_arch = "wasm32"))]
+        if self.on_gpu() {
+            let batch = if self.stop_when_extinct || self.stop_when_stable {
+                1
+            } else {
+                remaining.min(crate::gpu::BATCH)
+            };
+            self.tick_on_gpu(batch).await?;
+            return Ok(if self.settled() { 0 } else { remaining - batch });
+        }
+        let deadline = utils::now() + CHUNK_MILLIS;
+        while remaining > 0 && utils::now() < deadline {
+            self.tick();
+            remaining = if self.settled() { 0 } else { remaining - 1 };
+        }
+        Ok(remaining)
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Tick `generations` times without freezing the page: the ticks run
+    /// in chunks of a few milliseconds, yielding to the event loop after
+    /// each. Stops early under the same conditions as `tick_n`, or once
+    /// the run's `cancel_token` is cancelled.
+    ///
+    /// Under `Engine::Gpu`, with a device from `set_gpu` and a board it
+    /// can run, the generations are worked out on the GPU in batches of up
+    /// to 64, each read back as one step; see `set_engine`.
+    ///
+    /// After each chunk `on_progress` is called, if given, with the number
+    /// of ticks run so far and `generations`. If it throws, or the GPU
+    /// fails, the run stops and the run's `error` gives what went wrong.
+    ///
+    /// The run takes the universe over until it finishes, so this
+    /// universe can't be used meanwhile; `done` on the returned run
+    /// resolves to it, cancelled or not.
+    pub fn run_async(mut self, generations: u32, on_progress: Option<Function>) -> UniverseRun {
+        let progress = Rc::new(Cell::new(Progress::of(&self, generations)));
+        let shared = Rc::clone(&progress);
+        let cancel = CancelToken::default();
+        let cancelled = cancel.clone();
+        let error = Rc::new(RefCell::new(None));
+        let failed = Rc::clone(&error);
+        let done = future_to_promise(async move {
+            let mut remaining = generations;
+            while remaining > 0 && !cancelled.is_cancelled() {
+                remaining = match self.run_chunk(remaining).await {
+                    Ok(remaining) => remaining,
+                    Err(thrown) => {
+                        *failed.borrow_mut() = Some(thrown);
+                        break;
+                    }
+                };
+                shared.set(Progress::of(&self, remaining));
+                if let Some(on_progress) = &on_progress {
+                    let ran = generations - remaining;
+                    let called =
+                        on_progress.call2(&JsValue::NULL, &ran.into(), &generations.into());
+                    if let Err(thrown) = called {
+                        *failed.borrow_mut() = Some(thrown);
+                        break;
+                    }
+                }
+                if remaining > 0 {
+                    yield_now().await?;
+                }
+            }
+            shared.set(Progress::of(&self, 0));
+            Ok(self.into())
+        });
+        UniverseRun {
+            progress,
+            cancel,
+            error,
+            done,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::rule::Automaton;
+use crate::{Error, Universe};
+use fixedbitset::FixedBitSet;
+use std::fmt;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Grains at which a cell topples.
+const THRESHOLD: u32 = 4;
+
+/// The Abelian sandpile model. Every cell holds some grains of sand, and
+/// each tick every cell with at least four topples, sending one grain to
+/// each of its four orthogonal neighbours. Grains sent off a bounded board
+/// are lost; on a toroidal board they have nowhere to go, so large piles
+/// may never settle.
+///
+/// Cell states are palette indices: the number of grains for 0 to 3 and
+/// 4 for cells that are about to topple.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sandpile;
+
+impl Sandpile {
+    pub fn parse(rule: &str) -> Result<Sandpile, String> {
+        if rule.trim().eq_ignore_ascii_case("SANDPILE") {
+            Ok(Sandpile)
+        } else {
+            Err(format!("invalid rule {:?}", rule))
+        }
+    }
+}
+
+impl fmt::Display for Sandpile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Sandpile")
+    }
+}
+
+/// Write the palette index of every cell for `grains` into `states`, and
+/// mark the cells in state 1 in `next`.
+pub(crate) fn paint_sand(grains: &[u32], next: &mut FixedBitSet, mut states: Option<&mut [u8]>) {
+    for (idx, &count) in grains.iter().enumerate() {
+        let state = count.min(THRESHOLD) as u8;
+        next.set(idx, state == 1);
+        if let Some(states) = &mut states {
+            states[idx] = state;
+        }
+    }
+}
+
+impl Universe {
+    /// Grains on every cell after one round of toppling.
+    pub(crate) fn toppled(&self) -> Vec<u32> {
+        let sand = self.sand.as_deref().unwrap_or_default();
+        let mut next = sand.to_vec();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                if sand[idx] < THRESHOLD {
+                    continue;
+                }
+                next[idx] -= THRESHOLD;
+                for (d_row, d_col) in [(-1, 0), (0, -1), (0, 1), (1, 0)] {
+                    if let Some(neighbour) = self.offset_index(row, col, d_row, d_col) {
+                        next[neighbour] = next[neighbour].saturating_add(1);
+                    }
+                }
+            }
+        }
+        next
+    }
+
+    fn require_sandpile(&self) -> Result<(), Error> {
+        match self.rule {
+            Automaton::Sandpile(_) => Ok(()),
+            _ => Err(Error::new(format!(
+                "sand needs the Sandpile rule, not {}",
+                self.rule
+            ))),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Add `amount` grains to the cell at `(row, col)`. They topple on
+    /// the following ticks. A cell holds at most `u32::MAX` grains; any
+    /// more are lost. Errors if the cell is off the board or the
+    /// rule isn't Sandpile.
+    pub fn drop_sand(&mut self, row: u32, col: u32, amount: u32) -> Result<(), Error> {
+        self.require_sandpile()?;
+        let idx = self.checked_index(row, col)?;
+        let sand = self.sand.as_mut().unwrap();
+        sand[idx] = sand[idx].saturating_add(amount);
+        let state = sand[idx].min(THRESHOLD) as u8;
+        self.put_state(idx, state);
+        Ok(())
+    }
+
+    /// Grains on every cell, row by row; empty unless the rule is
+    /// Sandpile.
+    pub fn grains(&self) -> Vec<u32> {
+        self.sand.clone().unwrap_or_default()
+    }
+
+    /// Whether no cell has enough grains to topple.
+    pub fn is_stable(&self) -> bool {
+        self.sand.iter().flatten().all(|&count| count < THRESHOLD)
+    }
+}
@@ -0,0 +1,293 @@
+//! Saving and loading the board in chunks, so huge universes never need to
+//! be held as a single byte buffer.
+//!
+//! A save is a header followed by the packed cell words as little-endian
+//! `u32`s, in the layout described on `Universe::cells`. The header is the
+//! magic bytes `GOLC`, a version byte, then the width, height and word
+//! count as little-endian `u32`s.
+//!
+//! `to_bytes` instead writes the whole state in one blob: the magic bytes
+//! `GOLU` and a version byte, the width and height as little-endian
+//! `u32`s, the generation as a little-endian `u64`, the rule's length as a
+//! `u32` and the rule itself in UTF-8, and then the packed cell words as
+//! above. Under rules with more than two states one byte per cell follows,
+//! giving its state. Then, for the rules that keep them, come each cell's
+//! grains under Sandpile as a little-endian `u32`, its creature's age and
+//! energy under Wa-Tor as two, and its colour under Immigration and
+//! QuadLife as a byte.
+
+use crate::rule::Automaton;
+use crate::wator::Creature;
+use crate::{Error, FillMode, Universe};
+use fixedbitset::FixedBitSet;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+const MAGIC: &[u8; 4] = b"GOLC";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 17;
+
+const STATE_MAGIC: &[u8; 4] = b"GOLU";
+const STATE_VERSION: u8 = 2;
+
+/// A load in progress between `deserialize_begin` and `deserialize_finish`.
+#[derive(Clone)]
+pub struct PendingLoad {
+    width: u32,
+    height: u32,
+    words: Vec<u32>,
+    word_count: usize,
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Reads the fields of a `to_bytes` blob in turn, erroring if it runs out.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.bytes.len() {
+            return Err(Error::new("universe bytes are truncated"));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        self.take(4).map(read_u32)
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        let low = self.u32()? as u64;
+        Ok(low | (self.u32()? as u64) << 32)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    pub fn serialize_header(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        let word_count = self.cells.as_slice().len() as u32;
+        for value in [self.width, self.height, word_count].iter() {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        header
+    }
+
+    /// Bytes of the `chunk_index`-th run of `chunk_size` cell words. The
+    /// last chunk may be shorter; chunks past the end are empty.
+    pub fn serialize_chunk(&self, chunk_index: usize, chunk_size: usize) -> Vec<u8> {
+        let words = self.cells.as_slice();
+        let start = chunk_index.saturating_mul(chunk_size).min(words.len());
+        let end = start.saturating_add(chunk_size).min(words.len());
+        words[start..end]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+
+    /// Start loading a save from its header. The board isn't touched until
+    /// `deserialize_finish` succeeds.
+    pub fn deserialize_begin(&mut self, header: &[u8]) -> Result<(), Error> {
+        if header.len() != HEADER_LEN || &header[..4] != MAGIC {
+            return Err(Error::new("not a universe save header"));
+        }
+        if header[4] != VERSION {
+            return Err(Error::new(format!(
+                "unsupported save version {}",
+                header[4]
+            )));
+        }
+        let width = read_u32(&header[5..]);
+        let height = read_u32(&header[9..]);
+        let word_count = read_u32(&header[13..]) as usize;
+        let consistent = match width.checked_mul(height) {
+            Some(size) => size > 0 && word_count == size.div_ceil(32) as usize,
+            None => false,
+        };
+        if !consistent {
+            return Err(Error::new("save header is inconsistent"));
+        }
+        // The words grow as chunks arrive, so a header alone can't make
+        // us allocate the whole board.
+        self.pending_load = Some(PendingLoad {
+            width,
+            height,
+            words: Vec::new(),
+            word_count,
+        });
+        Ok(())
+    }
+
+    /// Append the next chunk, as produced by `serialize_chunk`. Chunks must
+    /// arrive in order.
+    pub fn deserialize_chunk(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let load = self
+            .pending_load
+            .as_mut()
+            .ok_or_else(|| Error::new("no load in progress"))?;
+        if !bytes.len().is_multiple_of(4) {
+            return Err(Error::new("chunk is not a whole number of words"));
+        }
+        if load.words.len() + bytes.len() / 4 > load.word_count {
+            return Err(Error::new("more data than the header announced"));
+        }
+        load.words.extend(bytes.chunks(4).map(read_u32));
+        Ok(())
+    }
+
+    /// Replace the board with the loaded state.
+    pub fn deserialize_finish(&mut self) -> Result<(), Error> {
+        let load = self
+            .pending_load
+            .take()
+            .ok_or_else(|| Error::new("no load in progress"))?;
+        if load.words.len() != load.word_count {
+            return Err(Error::new(format!(
+                "expected {} words, got {}",
+                load.word_count,
+                load.words.len()
+            )));
+        }
+        self.width = load.width;
+        self.height = load.height;
+        self.reallocate();
+        let size = (self.width * self.height) as usize;
+        self.cells = FixedBitSet::with_capacity_and_blocks(size, load.words);
+        Ok(())
+    }
+
+    /// The whole state as one compact blob: the size, rule, generation and
+    /// cells, each cell's state under rules with more than two, and the
+    /// grains, creatures or colours of the rules that have them. It suits
+    /// `postMessage`, IndexedDB or a file download, and `from_bytes` reads
+    /// it back. Errors under a rule loaded with `load_rule_file`, which
+    /// can't be saved.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        if let Automaton::RuleFile(_) = self.rule {
+            return Err(Error::new(format!(
+                "{} was loaded from a rule file and can't be saved",
+                self.rule
+            )));
+        }
+        let rule = self.rule();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(STATE_MAGIC);
+        bytes.push(STATE_VERSION);
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&self.generation.to_le_bytes());
+        bytes.extend_from_slice(&(rule.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(rule.as_bytes());
+        bytes.extend(
+            self.cells
+                .as_slice()
+                .iter()
+                .flat_map(|word| word.to_le_bytes()),
+        );
+        if self.rule.states() > 2 {
+            bytes.extend((0..(self.width * self.height) as usize).map(|idx| self.cell_state(idx)));
+        }
+        if let Some(sand) = &self.sand {
+            bytes.extend(sand.iter().flat_map(|grains| grains.to_le_bytes()));
+        }
+        if let Some(sea) = &self.sea {
+            for creature in sea {
+                let (age, energy) = creature.counters();
+                bytes.extend_from_slice(&age.to_le_bytes());
+                bytes.extend_from_slice(&energy.to_le_bytes());
+            }
+        }
+        if let Some(colors) = &self.colors {
+            bytes.extend_from_slice(colors);
+        }
+        Ok(bytes)
+    }
+
+    /// Rebuild a universe from `to_bytes`. Errors if the blob is cut short
+    /// or has bytes left over, is from an unknown version, or holds a size,
+    /// rule or cell state that doesn't make sense.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Universe, Error> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != STATE_MAGIC {
+            return Err(Error::new("not a universe save"));
+        }
+        let version = reader.take(1)?[0];
+        if version != STATE_VERSION {
+            return Err(Error::new(format!("unsupported save version {}", version)));
+        }
+        let (width, height) = (reader.u32()?, reader.u32()?);
+        // Empty boards are fine: `to_bytes` writes them like any other.
+        if width.checked_mul(height).is_none() {
+            return Err(Error::new(format!("invalid size {} by {}", width, height)));
+        }
+        let generation = reader.u64()?;
+        let rule_len = reader.u32()? as usize;
+        let rule = std::str::from_utf8(reader.take(rule_len)?)
+            .map_err(|_| Error::new("rule is not valid UTF-8"))?;
+        let rule = Automaton::parse(rule)?;
+        let size = (width * height) as usize;
+        let words = reader.take(size.div_ceil(32) * 4)?;
+        let states = match rule.states() {
+            2 => None,
+            _ => Some(reader.take(size)?),
+        };
+        let sand = match rule {
+            Automaton::Sandpile(_) => Some(reader.take(size * 4)?),
+            _ => None,
+        };
+        let sea = match rule {
+            Automaton::WaTor(_) => Some(reader.take(size * 8)?),
+            _ => None,
+        };
+        let colors = match rule {
+            Automaton::Colored(colored) => Some((colored.colors(), reader.take(size)?)),
+            _ => None,
+        };
+        if !reader.bytes.is_empty() {
+            return Err(Error::new(format!(
+                "{} bytes left over after the universe",
+                reader.bytes.len()
+            )));
+        }
+
+        let mut universe = Universe::with_size(width, height, Some(FillMode::Clear));
+        let states_count = rule.states();
+        universe.apply_rule(rule);
+        universe.generation = generation;
+        let words = words.chunks(4).map(read_u32);
+        universe.cells = FixedBitSet::with_capacity_and_blocks(size, words);
+        for (idx, &state) in states.into_iter().flatten().enumerate() {
+            if state >= states_count {
+                return Err(Error::new(format!("cell state {} is out of range", state)));
+            }
+            if state >= 2 {
+                universe.put_state(idx, state);
+            }
+        }
+        if let Some(bytes) = sand {
+            universe.sand = Some(bytes.chunks(4).map(read_u32).collect());
+        }
+        if let Some(bytes) = sea {
+            let creatures = bytes
+                .chunks(8)
+                .map(|counters| Creature::new(read_u32(counters), read_u32(&counters[4..])));
+            universe.sea = Some(creatures.collect());
+        }
+        if let Some((count, bytes)) = colors {
+            if let Some(&color) = bytes.iter().find(|&&color| color > count) {
+                return Err(Error::new(format!("colour {} is out of range", color)));
+            }
+            universe.colors = Some(bytes.to_vec());
+        }
+        universe.prev_cells.clone_from(&universe.cells);
+        Ok(universe)
+    }
+}
@@ -0,0 +1,310 @@
+//! Splitting a universe into horizontal bands that tick separately, for
+//! running a very large board across several Web Workers.
+//!
+//! Each worker holds one `UniverseShard`, made with
+//! `UniverseShard::from_universe`, and all of them share two
+//! `SharedArrayBuffer`s, viewed as `Uint32Array`s: a halo buffer of
+//! `2 * shards * words_per_row()` words and, for reading the board back, a
+//! board buffer laid out like `Universe::cells_row_aligned`. Every
+//! generation each worker calls `publish_edges`, waits until all of them
+//! have (for instance with `Atomics.wait` on a counter), calls
+//! `sync_halos` and ticks, and waits for the others again before the next
+//! generation. To see the whole board, every worker calls `write_band`
+//! and then `UniverseShard::stitch` builds a `Universe` from the board
+//! buffer. Off the web, `publish_edges_into`, `sync_halos_from` and
+//! `write_band_into` do the same with buffers in Rust memory.
+
+use crate::rule::Automaton;
+use crate::{BoundaryMode, Error, FillMode, NeighborhoodKind, Universe};
+use std::ops::Range;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// One horizontal band of a universe, ticked on its own with a copy of
+/// the rows just above and below it, its halos, kept up to date by
+/// exchanging edge rows with the neighbouring bands.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone)]
+pub struct UniverseShard {
+    /// The band with a halo row above and below it, `rows + 2` rows in
+    /// all. The halos are only meaningful between `set_halos` and `tick`.
+    band: Universe,
+    shards: u32,
+    index: u32,
+    first_row: u32,
+    rows: u32,
+}
+
+impl UniverseShard {
+    /// Row `row` of the band, counting the top halo as row 0, in the
+    /// `cells_row_aligned` layout.
+    fn row_words(&self, row: u32) -> Vec<u32> {
+        let mut words = vec![0u32; self.words_per_row() as usize];
+        for col in 0..self.band.width {
+            if self.band.cells[self.band.get_index(row, col)] {
+                words[(col / 32) as usize] |= 1 << (col % 32);
+            }
+        }
+        words
+    }
+
+    /// Overwrite row `row` of the band, counting the top halo as row 0.
+    fn set_row(&mut self, row: u32, words: &[u32]) {
+        for col in 0..self.band.width {
+            let alive = words[(col / 32) as usize] >> (col % 32) & 1 == 1;
+            let idx = self.band.get_index(row, col);
+            self.band.cells.set(idx, alive);
+        }
+    }
+
+    /// A halo row outside a bounded board.
+    pub(crate) fn background_row(&self) -> Vec<u32> {
+        let word = if self.band.background { !0 } else { 0 };
+        vec![word; self.words_per_row() as usize]
+    }
+
+    /// Errors unless a halo buffer of `len` words is right for this many
+    /// bands.
+    pub(crate) fn check_halo_buffer(&self, len: usize) -> Result<(), Error> {
+        let expected = 2 * self.shards as usize * self.words_per_row() as usize;
+        if len != expected {
+            return Err(Error::new(format!(
+                "expected a halo buffer of {} words, got {}",
+                expected, len
+            )));
+        }
+        Ok(())
+    }
+
+    /// Where this band's edges go in the halo buffer.
+    pub(crate) fn edges_offset(&self) -> usize {
+        2 * self.index as usize * self.words_per_row() as usize
+    }
+
+    /// Where the halos above and below are read from in the halo buffer,
+    /// or `None` for a halo off a bounded board, which takes the
+    /// background state. On a toroidal board the first and last bands are
+    /// neighbours.
+    pub(crate) fn halo_offsets(&self) -> (Option<usize>, Option<usize>) {
+        let words = self.words_per_row() as usize;
+        let (index, shards) = (self.index as usize, self.shards as usize);
+        let toroidal = self.band.boundary == BoundaryMode::Toroidal;
+        // The last row of band `i` starts at word `(2 * i + 1) * words`,
+        // and its first row at `2 * i * words`.
+        let above = match index {
+            0 if !toroidal => None,
+            0 => Some((2 * shards - 1) * words),
+            i => Some((2 * i - 1) * words),
+        };
+        let below = match index + 1 {
+            next if next == shards && !toroidal => None,
+            next if next == shards => Some(0),
+            next => Some(2 * next * words),
+        };
+        (above, below)
+    }
+
+    /// The words the band's rows take up in a board buffer of `len` words.
+    /// Errors if the buffer ends before the band's last row.
+    pub(crate) fn band_range(&self, len: usize) -> Result<Range<usize>, Error> {
+        let words = self.words_per_row() as usize;
+        let start = self.first_row as usize * words;
+        let end = start + self.rows as usize * words;
+        if len < end {
+            return Err(Error::new(format!(
+                "expected a board buffer of at least {} words, got {}",
+                end, len
+            )));
+        }
+        Ok(start..end)
+    }
+
+    /// `publish_edges` into a halo buffer in Rust memory, for sharing
+    /// between threads off the web.
+    pub fn publish_edges_into(&self, shared: &mut [u32]) -> Result<(), Error> {
+        self.check_halo_buffer(shared.len())?;
+        let start = self.edges_offset();
+        let edges = self.edges();
+        shared[start..start + edges.len()].copy_from_slice(&edges);
+        Ok(())
+    }
+
+    /// `sync_halos` from a halo buffer in Rust memory.
+    pub fn sync_halos_from(&mut self, shared: &[u32]) -> Result<(), Error> {
+        self.check_halo_buffer(shared.len())?;
+        let words = self.words_per_row() as usize;
+        let read = |start: Option<usize>| match start {
+            Some(start) => shared[start..start + words].to_vec(),
+            None => self.background_row(),
+        };
+        let (above, below) = self.halo_offsets();
+        let (above, below) = (read(above), read(below));
+        self.set_halos(&above, &below)
+    }
+
+    /// `write_band` into a board buffer in Rust memory.
+    pub fn write_band_into(&self, board: &mut [u32]) -> Result<(), Error> {
+        let range = self.band_range(board.len())?;
+        board[range].copy_from_slice(&self.band());
+        Ok(())
+    }
+
+    fn check_row(&self, words: &[u32]) -> Result<(), Error> {
+        if words.len() != self.words_per_row() as usize {
+            return Err(Error::new(format!(
+                "expected a row of {} words, got {}",
+                self.words_per_row(),
+                words.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl UniverseShard {
+    /// Band `index` of `universe` cut into `shards` bands of as near equal
+    /// height as possible, top to bottom, with the universe's rule and
+    /// settings.
+    ///
+    /// Errors if `index` isn't below `shards`, there are more bands than
+    /// rows, or the rule can't be split: only two-state rules that look
+    /// one cell around each one (Life-like, isotropic and transition-table
+    /// rules, on any neighbourhood but the radius-2 von Neumann one) can,
+    /// without a frozen border, ants or noise.
+    pub fn from_universe(
+        universe: &Universe,
+        shards: u32,
+        index: u32,
+    ) -> Result<UniverseShard, Error> {
+        if index >= shards || shards > universe.height {
+            return Err(Error::new(format!(
+                "can't take band {} of {} from {} rows",
+                index, shards, universe.height
+            )));
+        }
+        let splittable = match universe.rule {
+            Automaton::Life(rule) => rule.states() == 2,
+            Automaton::Isotropic(rule) => rule.states() == 2,
+            Automaton::Table(_) => true,
+            _ => false,
+        } && universe.neighborhood != NeighborhoodKind::VonNeumannRadius2
+            && universe.frozen_border == 0
+            && universe.ants.is_empty()
+            && universe.noise.is_none();
+        if !splittable {
+            return Err(Error::new(format!(
+                "{} can't be split into bands under these settings",
+                universe.rule
+            )));
+        }
+
+        let first_row = index * universe.height / shards;
+        let rows = (index + 1) * universe.height / shards - first_row;
+        let mut band = Universe::with_size(universe.width, rows + 2, Some(FillMode::Clear));
+        band.apply_rule(universe.rule.clone());
+        band.boundary = universe.boundary;
+        band.background = universe.background;
+        band.include_center = universe.include_center;
+        band.neighborhood = universe.neighborhood;
+        band.generation = universe.generation;
+        for row in 0..rows {
+            for col in 0..universe.width {
+                let alive = universe.cells[universe.get_index(first_row + row, col)];
+                let idx = band.get_index(row + 1, col);
+                band.cells.set(idx, alive);
+            }
+        }
+        Ok(UniverseShard {
+            band,
+            shards,
+            index,
+            first_row,
+            rows,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.band.width
+    }
+
+    /// The universe row the band starts at.
+    pub fn first_row(&self) -> u32 {
+        self.first_row
+    }
+
+    /// Rows in the band, not counting halos.
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.band.generation
+    }
+
+    /// Words per row in the halo and board buffers: `width` rounded up to
+    /// a multiple of 32, over 32.
+    pub fn words_per_row(&self) -> u32 {
+        self.band.width.div_ceil(32)
+    }
+
+    /// The band's first row followed by its last, `words_per_row` words
+    /// each, laid out as in `Universe::cells_row_aligned`. These are the
+    /// halos of the bands above and below.
+    pub fn edges(&self) -> Vec<u32> {
+        let mut edges = self.row_words(1);
+        edges.extend(self.row_words(self.rows));
+        edges
+    }
+
+    /// Set the halos to the last row of the band above and the first row
+    /// of the band below, as returned by their `edges`. Errors unless both
+    /// are `words_per_row` words long.
+    pub fn set_halos(&mut self, above: &[u32], below: &[u32]) -> Result<(), Error> {
+        self.check_row(above)?;
+        self.check_row(below)?;
+        self.set_row(0, above);
+        self.set_row(self.rows + 1, below);
+        Ok(())
+    }
+
+    /// Advance the band one generation. The halos must have been set for
+    /// this generation first.
+    pub fn tick(&mut self) {
+        self.band.tick();
+    }
+
+    /// The band's rows, laid out as in `Universe::cells_row_aligned`.
+    pub fn band(&self) -> Vec<u32> {
+        (1..=self.rows)
+            .flat_map(|row| self.row_words(row))
+            .collect()
+    }
+
+    /// The whole `width` by `height` board from a buffer every band has
+    /// written its rows to, laid out as in `Universe::cells_row_aligned`.
+    /// The universe has the default rule and settings, as from
+    /// `Universe::from_words`. Errors unless the buffer has
+    /// `ceil(width / 32) * height` words.
+    pub fn stitch(width: u32, height: u32, board: &[u32]) -> Result<Universe, Error> {
+        let words_per_row = width.div_ceil(32) as usize;
+        if board.len() != words_per_row * height as usize {
+            return Err(Error::new(format!(
+                "expected {} words, got {}",
+                words_per_row * height as usize,
+                board.len()
+            )));
+        }
+        let mut universe = Universe::with_size(width, height, Some(FillMode::Clear));
+        for (row, words) in board.chunks(words_per_row).enumerate() {
+            for col in 0..width {
+                if words[(col / 32) as usize] >> (col % 32) & 1 == 1 {
+                    let idx = universe.get_index(row as u32, col);
+                    universe.cells.insert(idx);
+                }
+            }
+        }
+        universe.prev_cells.clone_from(&universe.cells);
+        Ok(universe)
+    }
+}
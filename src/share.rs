@@ -0,0 +1,122 @@
+//! The whole universe as a short string that fits in a link's hash
+//! fragment: the `to_bytes` blob, run-length compressed and then written
+//! in URL-safe base64 without padding.
+//!
+//! The compression is PackBits: a header byte `n` below 128 is followed by
+//! `n + 1` bytes to copy, and one above 128 by a single byte to repeat
+//! `257 - n` times. Mostly empty boards are mostly zero bytes, which
+//! shrink to two bytes per 128.
+
+use crate::{Error, Universe};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Longest run, literal or repeated, one header byte can cover.
+const MAX_RUN: usize = 128;
+
+fn pack(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let repeats = bytes[i..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|&&byte| byte == bytes[i])
+            .count();
+        if repeats >= 2 {
+            out.push((257 - repeats) as u8);
+            out.push(bytes[i]);
+            i += repeats;
+            continue;
+        }
+        // Copy bytes as they are up to the next pair worth repeating.
+        let start = i;
+        while i < bytes.len()
+            && i - start < MAX_RUN
+            && !(i + 1 < bytes.len() && bytes[i] == bytes[i + 1])
+        {
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&bytes[start..i]);
+    }
+    out
+}
+
+fn unpack(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let truncated = || Error::new("share string is truncated");
+    let mut out = Vec::new();
+    let mut rest = bytes;
+    while let Some((&header, tail)) = rest.split_first() {
+        rest = match header {
+            0..=127 => {
+                let len = header as usize + 1;
+                let literal = tail.get(..len).ok_or_else(truncated)?;
+                out.extend_from_slice(literal);
+                &tail[len..]
+            }
+            128 => tail,
+            _ => {
+                let &byte = tail.first().ok_or_else(truncated)?;
+                out.extend(std::iter::repeat_n(byte, 257 - header as usize));
+                &tail[1..]
+            }
+        };
+    }
+    Ok(out)
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let bits = group.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+            bits | (byte as u32) << (16 - 8 * i)
+        });
+        // Three bytes make four characters; fewer make one more than
+        // there are bytes.
+        for i in 0..=group.len() {
+            out.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, Error> {
+    if text.len() % 4 == 1 {
+        return Err(Error::new("share string has the wrong length"));
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3 + 2);
+    for group in text.as_bytes().chunks(4) {
+        let mut bits = 0u32;
+        for (i, &c) in group.iter().enumerate() {
+            let value = ALPHABET.iter().position(|&a| a == c).ok_or_else(|| {
+                Error::new(format!(
+                    "unexpected character {:?} in share string",
+                    c as char
+                ))
+            })?;
+            bits |= (value as u32) << (18 - 6 * i);
+        }
+        out.extend((0..group.len() - 1).map(|i| (bits >> (16 - 8 * i)) as u8));
+    }
+    Ok(out)
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// The whole state, as `to_bytes` gives it, compressed into a string of
+    /// letters, digits, `-` and `_` that can go in a URL unescaped, such as
+    /// after the `#` of a link. `from_share_string` reads it back. Errors
+    /// when `to_bytes` does.
+    pub fn to_share_string(&self) -> Result<String, Error> {
+        Ok(encode_base64(&pack(&self.to_bytes()?)))
+    }
+
+    /// Rebuild a universe from `to_share_string`. Errors if the string has
+    /// been cut short or altered.
+    pub fn from_share_string(text: &str) -> Result<Universe, Error> {
+        Universe::from_bytes(&unpack(&decode_base64(text.trim())?)?)
+    }
+}
@@ -0,0 +1,126 @@
+//! Sharing the board with a render thread without copying.
+//!
+//! When the module's memory is a `SharedArrayBuffer` (see `threads`), a
+//! universe ticked in a worker can be drawn from another thread straight
+//! out of that memory. `Universe::cells` won't do for this, since the
+//! buffer it points at changes with every tick and is written while the
+//! tick runs. Instead `share_cells` sets aside two buffers that stay put
+//! and copies each new generation into whichever isn't being read, then
+//! flips which one is the front.
+//!
+//! `shared_cells` gives their addresses, their length in words and the
+//! address of two state words: the index of the front buffer, and a
+//! sequence number that is odd while the back buffer is being written.
+//! A reader does
+//!
+//! ```js
+//! const [first, second, words, state] = universe.shared_cells();
+//! const flags = new Uint32Array(memory.buffer, state, 2);
+//! let seq, cells;
+//! do {
+//!   seq = Atomics.load(flags, 1);
+//!   const front = Atomics.load(flags, 0) === 0 ? first : second;
+//!   cells = new Uint32Array(memory.buffer, front, words);
+//!   draw(cells);
+//! } while (Atomics.load(flags, 1) - seq >= 2);
+//! ```
+//!
+//! The front buffer is only written again two publishes later, so a draw
+//! that finishes before the sequence moves on by two saw a whole
+//! generation.
+
+use crate::{Error, Universe};
+use fixedbitset::FixedBitSet;
+use std::sync::atomic::{AtomicU32, Ordering};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Index of the front buffer in `SharedCells::state`.
+const FRONT: usize = 0;
+/// Index of the sequence number in `SharedCells::state`.
+const SEQUENCE: usize = 1;
+
+/// Two copies of the cells at fixed addresses, one being read while the
+/// other is written.
+pub struct SharedCells {
+    buffers: [Box<[u32]>; 2],
+    state: Box<[AtomicU32; 2]>,
+}
+
+impl SharedCells {
+    /// Both buffers holding `cells`, with the first at the front.
+    pub fn new(cells: &FixedBitSet) -> SharedCells {
+        let words: Box<[u32]> = cells.as_slice().into();
+        SharedCells {
+            buffers: [words.clone(), words],
+            state: Box::new([AtomicU32::new(0), AtomicU32::new(0)]),
+        }
+    }
+
+    /// Copy `cells` into the back buffer and make it the front.
+    pub fn publish(&mut self, cells: &FixedBitSet) {
+        let back = 1 - self.state[FRONT].load(Ordering::Relaxed) as usize;
+        self.state[SEQUENCE].fetch_add(1, Ordering::AcqRel);
+        self.buffers[back].copy_from_slice(cells.as_slice());
+        self.state[FRONT].store(back as u32, Ordering::Release);
+        self.state[SEQUENCE].fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+impl Clone for SharedCells {
+    /// New buffers at new addresses, with the same contents.
+    fn clone(&self) -> SharedCells {
+        let front = self.state[FRONT].load(Ordering::Acquire);
+        SharedCells {
+            buffers: self.buffers.clone(),
+            state: Box::new([AtomicU32::new(front), AtomicU32::new(0)]),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Start keeping a copy of the cells in two buffers at fixed addresses
+    /// for another thread to read; see `shared_cells`. Every tick then
+    /// copies the new generation into the buffer not at the front.
+    /// Edits between ticks only show once `publish_cells` is called.
+    pub fn share_cells(&mut self) {
+        if self.shared.is_none() {
+            self.shared = Some(SharedCells::new(&self.cells));
+        }
+    }
+
+    /// Stop keeping the shared buffers, freeing them. Readers must be done
+    /// with them first.
+    pub fn stop_sharing_cells(&mut self) {
+        self.shared = None;
+    }
+
+    /// Copy the current cells into the shared buffers now, for edits made
+    /// since the last tick. Does nothing unless `share_cells` was called.
+    pub fn publish_cells(&mut self) {
+        if let Some(shared) = &mut self.shared {
+            shared.publish(&self.cells);
+        }
+    }
+
+    /// The address of the first shared buffer, the address of the second,
+    /// their length in words, and the address of the two state words: the
+    /// index of the front buffer and the publish sequence number. The
+    /// buffers are laid out as `cells` and keep their addresses until the
+    /// board is resized or loaded over.
+    ///
+    /// Errors unless `share_cells` was called.
+    pub fn shared_cells(&self) -> Result<Vec<u32>, Error> {
+        let shared = self
+            .shared
+            .as_ref()
+            .ok_or_else(|| Error::new("cells aren't shared; call share_cells first"))?;
+        Ok(vec![
+            shared.buffers[0].as_ptr() as u32,
+            shared.buffers[1].as_ptr() as u32,
+            shared.buffers[0].len() as u32,
+            shared.state.as_ptr() as u32,
+        ])
+    }
+}
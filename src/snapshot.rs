@@ -0,0 +1,109 @@
+//! Named save points kept inside the module, so a board can be put aside
+//! and jumped back to without passing it through JavaScript.
+
+use crate::rule::Automaton;
+use crate::{Error, SavedState, Universe};
+use std::collections::BTreeMap;
+use std::mem::{size_of, size_of_val};
+use std::sync::Arc;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// The board as `save_snapshot` found it: its size and rule as well as
+/// its cells, so it can be restored after either has changed.
+pub struct Snapshot {
+    width: u32,
+    height: u32,
+    rule: Automaton,
+    state: SavedState,
+    /// Counts up with each save, so the oldest snapshot can be found.
+    order: u64,
+}
+
+impl Snapshot {
+    /// Bytes the snapshot holds, counting its buffers.
+    fn bytes(&self) -> usize {
+        let state = &self.state;
+        size_of::<Snapshot>()
+            + size_of_val(state.cells.as_slice())
+            + size_of_val(state.prev_cells.as_slice())
+            + size_of_val(&state.ages[..])
+            + state.heatmap.as_deref().map_or(0, size_of_val)
+            + state.states.as_deref().map_or(0, size_of_val)
+            + size_of_val(&state.ants[..])
+            + state.sand.as_deref().map_or(0, size_of_val)
+            + state.sea.as_deref().map_or(0, size_of_val)
+            + state.colors.as_deref().map_or(0, size_of_val)
+    }
+}
+
+/// Snapshots by name. Cloning a universe shares them rather than copying
+/// them; saving under a name in either clone replaces only its own entry.
+pub type Snapshots = BTreeMap<String, Arc<Snapshot>>;
+
+/// Bytes the snapshots take up, counting shared ones in full.
+pub(crate) fn bytes(snapshots: &Snapshots) -> usize {
+    snapshots.values().map(|snapshot| snapshot.bytes()).sum()
+}
+
+/// Forget the snapshot saved first. Returns the bytes freed, `None` if
+/// there was no snapshot.
+pub(crate) fn drop_oldest(snapshots: &mut Snapshots) -> Option<usize> {
+    let name = snapshots
+        .iter()
+        .min_by_key(|(_, snapshot)| snapshot.order)
+        .map(|(name, _)| name.clone())?;
+    snapshots.remove(&name).map(|snapshot| snapshot.bytes())
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Keep a copy of the board, its size, rule, generation and per-cell
+    /// statistics under `name`, replacing any snapshot already called that.
+    pub fn save_snapshot(&mut self, name: &str) {
+        let order = self
+            .snapshots
+            .values()
+            .map(|snapshot| snapshot.order + 1)
+            .max()
+            .unwrap_or(0);
+        let snapshot = Snapshot {
+            width: self.width,
+            height: self.height,
+            rule: self.rule.clone(),
+            state: self.save_state(),
+            order,
+        };
+        self.snapshots.insert(name.to_string(), Arc::new(snapshot));
+        self.enforce_history_budget();
+    }
+
+    /// Put the board back as it was when saved under `name`, resizing it
+    /// and switching rule if need be. The snapshot is kept, so it can be
+    /// restored again. Errors if there is no snapshot called `name`.
+    pub fn restore_snapshot(&mut self, name: &str) -> Result<(), Error> {
+        let snapshot = self
+            .snapshots
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::new(format!("no snapshot called {:?}", name)))?;
+        if (self.width, self.height) != (snapshot.width, snapshot.height) {
+            self.width = snapshot.width;
+            self.height = snapshot.height;
+            self.reallocate();
+        }
+        self.apply_rule(snapshot.rule.clone());
+        self.restore_state(snapshot.state.clone());
+        Ok(())
+    }
+
+    /// The names of the saved snapshots, in alphabetical order.
+    pub fn list_snapshots(&self) -> Vec<String> {
+        self.snapshots.keys().cloned().collect()
+    }
+
+    /// Forget the snapshot called `name`. Returns whether there was one.
+    pub fn delete_snapshot(&mut self, name: &str) -> bool {
+        self.snapshots.remove(name).is_some()
+    }
+}
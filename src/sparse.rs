@@ -0,0 +1,220 @@
+use crate::rule::LifeRule;
+use crate::swar::next_cells;
+use crate::utils;
+use crate::{Error, NeighborhoodKind, Universe};
+use std::collections::{HashMap, HashSet};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Cells on a side of a chunk.
+const CHUNK: i32 = 64;
+
+/// A 64x64 square of cells, one word per row with column `i` in bit `i`.
+type Chunk = [u64; CHUNK as usize];
+
+const EMPTY: Chunk = [0; CHUNK as usize];
+
+/// An unbounded Life-like universe that only stores the 64x64 chunks
+/// with live cells in them, so a few patterns far apart cost no more than
+/// the patterns themselves. Rows and columns run both ways from 0.
+///
+/// Only two-state Life-like rules on the Moore neighbourhood that don't
+/// bring cells to life with no neighbours (no `B0`) can run unbounded.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct SparseUniverse {
+    rule: LifeRule,
+    generation: u64,
+    /// Chunks by `(chunk_row, chunk_col)`; chunk `(0, 0)` covers rows and
+    /// columns 0 to 63. Chunks without live cells are left out.
+    chunks: HashMap<(i32, i32), Chunk>,
+}
+
+/// The chunk holding `(row, col)`, and the cell's place within it.
+fn locate(row: i32, col: i32) -> ((i32, i32), usize, usize) {
+    (
+        (row.div_euclid(CHUNK), col.div_euclid(CHUNK)),
+        row.rem_euclid(CHUNK) as usize,
+        col.rem_euclid(CHUNK) as usize,
+    )
+}
+
+impl SparseUniverse {
+    /// The chunk at `key` one generation on, worked out 64 cells at a time
+    /// from it and its eight neighbours as in `evolve_swar`.
+    fn next_chunk(&self, (chunk_row, chunk_col): (i32, i32)) -> Chunk {
+        let near = |d_row: i32, d_col: i32| {
+            self.chunks
+                .get(&(chunk_row + d_row, chunk_col + d_col))
+                .unwrap_or(&EMPTY)
+        };
+        let around = [-1, 0, 1].map(|d_row| [-1, 0, 1].map(|d_col| near(d_row, d_col)));
+        let (birth, survival) = (self.rule.birth(), self.rule.survival());
+        let mut next = EMPTY;
+        for (row, word) in next.iter_mut().enumerate() {
+            let mut block = [0; 9];
+            for d_row in 0..3 {
+                // Row `row + d_row - 1`, possibly in the chunk above or below.
+                let (chunks, source) = match row + d_row {
+                    0 => (around[0], CHUNK as usize - 1),
+                    r if r == CHUNK as usize + 1 => (around[2], 0),
+                    r => (around[1], r - 1),
+                };
+                let [west, cells, east] = chunks.map(|chunk| chunk[source]);
+                block[3 * d_row] = cells << 1 | west >> 63;
+                block[3 * d_row + 1] = cells;
+                block[3 * d_row + 2] = cells >> 1 | east << 63;
+            }
+            *word = next_cells(block, false, birth, survival);
+        }
+        next
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl SparseUniverse {
+    /// An empty universe under Conway's rule.
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new() -> SparseUniverse {
+        utils::set_panic_hook();
+        SparseUniverse {
+            rule: LifeRule::CONWAY,
+            generation: 0,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// An unbounded copy of the live cells of `universe`, at the same rows
+    /// and columns, under its rule. Errors unless that rule can run
+    /// unbounded on the Moore neighbourhood without cells counting
+    /// themselves.
+    pub fn from_universe(universe: &Universe) -> Result<SparseUniverse, Error> {
+        if universe.neighborhood != NeighborhoodKind::Moore || universe.include_center {
+            return Err(Error::new(
+                "only the Moore neighbourhood without the centre cell can run unbounded",
+            ));
+        }
+        let mut sparse = SparseUniverse::new();
+        sparse.set_rule(&universe.rule())?;
+        for idx in universe.cells.ones() {
+            let (row, col) = (idx as u32 / universe.width, idx as u32 % universe.width);
+            sparse.set(row as i32, col as i32, true);
+        }
+        Ok(sparse)
+    }
+
+    /// Cells on a side of the chunks returned by `chunks` and `chunk`.
+    pub fn chunk_size() -> u32 {
+        CHUNK as u32
+    }
+
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Switch to a two-state Life-like rule without `B0`, in any notation
+    /// `LifeRule::parse` accepts.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), Error> {
+        let rule = LifeRule::parse(rule)?;
+        if rule.states() != 2 || rule.birth() & 1 != 0 {
+            return Err(Error::new(format!(
+                "{} can't run on an unbounded board",
+                rule
+            )));
+        }
+        self.rule = rule;
+        Ok(())
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u64 {
+        self.chunks
+            .values()
+            .flat_map(|chunk| chunk.iter())
+            .map(|word| word.count_ones() as u64)
+            .sum()
+    }
+
+    pub fn get(&self, row: i32, col: i32) -> bool {
+        let (key, row, col) = locate(row, col);
+        self.chunks
+            .get(&key)
+            .is_some_and(|chunk| chunk[row] >> col & 1 == 1)
+    }
+
+    pub fn set(&mut self, row: i32, col: i32, alive: bool) {
+        let (key, row, col) = locate(row, col);
+        if alive {
+            self.chunks.entry(key).or_insert(EMPTY)[row] |= 1 << col;
+        } else if let Some(chunk) = self.chunks.get_mut(&key) {
+            chunk[row] &= !(1 << col);
+            if chunk.iter().all(|&word| word == 0) {
+                self.chunks.remove(&key);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Advance one generation. Only the stored chunks and those next to
+    /// them are evaluated, since nothing can be born further out.
+    pub fn tick(&mut self) {
+        let mut keys = HashSet::new();
+        for &(chunk_row, chunk_col) in self.chunks.keys() {
+            for d_row in -1..=1 {
+                for d_col in -1..=1 {
+                    keys.insert((chunk_row + d_row, chunk_col + d_col));
+                }
+            }
+        }
+        self.chunks = keys
+            .into_iter()
+            .map(|key| (key, self.next_chunk(key)))
+            .filter(|(_, chunk)| chunk.iter().any(|&word| word != 0))
+            .collect();
+        self.generation += 1;
+    }
+
+    /// The chunks with live cells in them, as `(chunk_row, chunk_col)`
+    /// pairs one after the other, in row-major order. Chunk `(r, c)`
+    /// covers rows `r * chunk_size()` to `(r + 1) * chunk_size() - 1`, and
+    /// likewise for columns, so a renderer need only draw these.
+    pub fn chunks(&self) -> Vec<i32> {
+        let mut keys: Vec<_> = self.chunks.keys().copied().collect();
+        keys.sort_unstable();
+        keys.into_iter().flat_map(|(row, col)| [row, col]).collect()
+    }
+
+    /// The cells of chunk `(chunk_row, chunk_col)`, two `u32`s per row
+    /// with column `i` of the chunk in bit `i % 32` of the row's word
+    /// `i / 32`. All zeros for chunks without live cells.
+    pub fn chunk(&self, chunk_row: i32, chunk_col: i32) -> Vec<u32> {
+        let chunk = self.chunks.get(&(chunk_row, chunk_col)).unwrap_or(&EMPTY);
+        chunk
+            .iter()
+            .flat_map(|&word| [word as u32, (word >> 32) as u32])
+            .collect()
+    }
+}
+
+impl SparseUniverse {
+    /// The stored chunks, each with its `(chunk_row, chunk_col)`.
+    pub(crate) fn stored_chunks(&self) -> impl Iterator<Item = ((i32, i32), &Chunk)> {
+        self.chunks.iter().map(|(&key, chunk)| (key, chunk))
+    }
+
+    pub(crate) fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+}
+
+impl Default for SparseUniverse {
+    fn default() -> SparseUniverse {
+        SparseUniverse::new()
+    }
+}
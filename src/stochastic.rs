@@ -0,0 +1,145 @@
+use crate::rng::Rng;
+use crate::{Error, Universe};
+use fixedbitset::FixedBitSet;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Number of possible neighbour counts: up to twelve neighbours in the
+/// radius-2 von Neumann neighbourhood, plus the cell itself with
+/// `include_center`.
+pub(crate) const COUNTS: usize = 14;
+
+/// Probabilities for running a rule stochastically: each birth or
+/// survival the rule calls for only happens with the probability set for
+/// the cell's live neighbour count, and otherwise the cell stays dead or
+/// dies.
+#[derive(Clone)]
+pub struct Noise {
+    /// Indexed by live neighbour count.
+    birth: [f64; COUNTS],
+    survival: [f64; COUNTS],
+    rng: Rng,
+}
+
+impl Noise {
+    fn new(seed: u64) -> Noise {
+        Noise {
+            birth: [1.0; COUNTS],
+            survival: [1.0; COUNTS],
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Whether a transition with probability `p` fires. Certain
+    /// transitions don't draw, so they leave the random stream alone.
+    fn fires(&mut self, p: f64) -> bool {
+        p >= 1.0 || self.rng.next_f64() < p
+    }
+}
+
+impl Universe {
+    /// Veto the births and survivals in `next` that don't fire under
+    /// `noise`. A vetoed survivor dies as it would under the rule, into
+    /// the first dying state of a Generations rule.
+    pub(crate) fn apply_noise(
+        &self,
+        noise: &mut Noise,
+        next: &mut FixedBitSet,
+        mut states: Option<&mut [u8]>,
+    ) {
+        let fired: Vec<usize> = next.ones().collect();
+        for idx in fired {
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            if self.in_frozen_border(row, col) {
+                continue;
+            }
+            let count = self.live_neighbor_count(row, col) as usize;
+            let alive = self.cells[idx];
+            let p = if alive {
+                noise.survival[count]
+            } else {
+                noise.birth[count]
+            };
+            if noise.fires(p) {
+                continue;
+            }
+            next.set(idx, false);
+            if let Some(states) = &mut states {
+                states[idx] = if alive && self.rule.states() > 2 {
+                    2
+                } else {
+                    0
+                };
+            }
+        }
+    }
+
+    /// The noise settings, starting from certain transitions and a seed
+    /// from the universe's random stream if there are none yet.
+    fn noise_mut(&mut self) -> &mut Noise {
+        let rng = &mut self.rng;
+        self.noise.get_or_insert_with(|| Noise::new(rng.next_u64()))
+    }
+}
+
+fn check_probability(neighbors: u8, probability: f64) -> Result<usize, Error> {
+    if neighbors as usize >= COUNTS {
+        return Err(Error::new(format!(
+            "neighbour count {} is more than {}",
+            neighbors,
+            COUNTS - 1
+        )));
+    }
+    if !(0.0..=1.0).contains(&probability) {
+        return Err(Error::new(format!(
+            "probability {} is not between 0 and 1",
+            probability
+        )));
+    }
+    Ok(neighbors as usize)
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Only let the rule bring a cell with `neighbors` live neighbours to
+    /// life with the given probability, for studying how patterns hold up
+    /// under noise. Every other transition stays certain until set.
+    /// Errors if `neighbors` is above 13 or `probability` isn't in `0..=1`.
+    pub fn set_birth_probability(&mut self, neighbors: u8, probability: f64) -> Result<(), Error> {
+        let count = check_probability(neighbors, probability)?;
+        self.noise_mut().birth[count] = probability;
+        Ok(())
+    }
+
+    /// Only let a live cell with `neighbors` live neighbours survive with
+    /// the given probability, where the rule says it survives. See
+    /// `set_birth_probability`.
+    pub fn set_survival_probability(
+        &mut self,
+        neighbors: u8,
+        probability: f64,
+    ) -> Result<(), Error> {
+        let count = check_probability(neighbors, probability)?;
+        self.noise_mut().survival[count] = probability;
+        Ok(())
+    }
+
+    /// Restart the random stream used for stochastic transitions, so runs
+    /// from the same board and seed are identical. Without a seed the
+    /// stream differs every time.
+    pub fn set_noise_seed(&mut self, seed: u64) {
+        match &mut self.noise {
+            Some(noise) => noise.rng = Rng::new(seed),
+            None => self.noise = Some(Noise::new(seed)),
+        }
+    }
+
+    /// Make every transition certain again.
+    pub fn clear_noise(&mut self) {
+        self.noise = None;
+    }
+
+    pub fn is_stochastic(&self) -> bool {
+        self.noise.is_some()
+    }
+}
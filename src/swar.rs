@@ -0,0 +1,287 @@
+use crate::rule::LifeRule;
+use crate::threads;
+use crate::{BoundaryMode, NeighborhoodKind, Universe};
+use fixedbitset::FixedBitSet;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+use core::arch::wasm32::v128;
+
+/// Bits `offset..offset + 64` of `blocks`, bit 0 of the result being bit
+/// `offset`. Bits past the end read as zero.
+#[inline]
+fn read_bits(blocks: &[u32], offset: usize) -> u64 {
+    let (first, shift) = (offset / 32, offset % 32);
+    let wide = (0..3).fold(0u128, |wide, i| {
+        wide | (blocks.get(first + i).copied().unwrap_or(0) as u128) << (32 * i)
+    });
+    (wide >> shift) as u64
+}
+
+/// Set the bits of `blocks` from `offset` that are set in `bits`.
+#[inline]
+fn or_bits(blocks: &mut [u32], offset: usize, bits: u64) {
+    let (first, shift) = (offset / 32, offset % 32);
+    let wide = (bits as u128) << shift;
+    for i in 0..3 {
+        let part = (wide >> (32 * i)) as u32;
+        if part != 0 {
+            blocks[first + i] |= part;
+        }
+    }
+}
+
+/// A bundle of cells handled together, one per bit: 64 in a `u64`, or
+/// 128 in a `v128` with the `simd` feature.
+pub(crate) trait Lanes:
+    Copy + BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + Not<Output = Self>
+{
+    fn zero() -> Self;
+}
+
+impl Lanes for u64 {
+    #[inline]
+    fn zero() -> u64 {
+        0
+    }
+}
+
+/// Two words side by side in a WebAssembly SIMD register.
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+#[derive(Clone, Copy)]
+struct Simd(v128);
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+mod simd_ops {
+    use super::{Lanes, Simd};
+    use core::arch::wasm32::*;
+    use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+    impl BitAnd for Simd {
+        type Output = Simd;
+        #[inline]
+        fn bitand(self, other: Simd) -> Simd {
+            Simd(v128_and(self.0, other.0))
+        }
+    }
+
+    impl BitOr for Simd {
+        type Output = Simd;
+        #[inline]
+        fn bitor(self, other: Simd) -> Simd {
+            Simd(v128_or(self.0, other.0))
+        }
+    }
+
+    impl BitXor for Simd {
+        type Output = Simd;
+        #[inline]
+        fn bitxor(self, other: Simd) -> Simd {
+            Simd(v128_xor(self.0, other.0))
+        }
+    }
+
+    impl Not for Simd {
+        type Output = Simd;
+        #[inline]
+        fn not(self) -> Simd {
+            Simd(v128_not(self.0))
+        }
+    }
+
+    impl Lanes for Simd {
+        #[inline]
+        fn zero() -> Simd {
+            Simd(u64x2(0, 0))
+        }
+    }
+
+    impl Simd {
+        #[inline]
+        pub(super) fn pair(low: u64, high: u64) -> Simd {
+            Simd(u64x2(low, high))
+        }
+
+        #[inline]
+        pub(super) fn split(self) -> (u64, u64) {
+            (
+                u64x2_extract_lane::<0>(self.0),
+                u64x2_extract_lane::<1>(self.0),
+            )
+        }
+    }
+}
+
+/// Add `input` into the bit-sliced counters `planes`, plane `i` holding bit
+/// `i` of each cell's count.
+#[inline]
+fn add<L: Lanes>(planes: &mut [L; 4], input: L) {
+    let mut carry = input;
+    for plane in planes.iter_mut() {
+        let next = *plane & carry;
+        *plane = *plane ^ carry;
+        carry = next;
+    }
+}
+
+/// The next state of every cell in `block[4]` under a Life-like rule with
+/// `birth` and `survival` count masks, given its 3x3 block row by row.
+#[inline]
+pub(crate) fn next_cells<L: Lanes>(
+    block: [L; 9],
+    include_center: bool,
+    birth: u16,
+    survival: u16,
+) -> L {
+    let mut planes = [L::zero(); 4];
+    for (i, &cells) in block.iter().enumerate() {
+        if i != 4 || include_center {
+            add(&mut planes, cells);
+        }
+    }
+    let mut born = L::zero();
+    let mut survives = L::zero();
+    for count in 0..10 {
+        if (birth | survival) >> count & 1 == 0 {
+            continue;
+        }
+        let mut matches = !L::zero();
+        for (bit, &plane) in planes.iter().enumerate() {
+            matches = matches & if count >> bit & 1 == 1 { plane } else { !plane };
+        }
+        if birth >> count & 1 == 1 {
+            born = born | matches;
+        }
+        if survival >> count & 1 == 1 {
+            survives = survives | matches;
+        }
+    }
+    (block[4] & survives) | (!block[4] & born)
+}
+
+/// Whether this build runs `evolve_swar` on WebAssembly SIMD, two words at
+/// a time. That takes the `simd` feature and compiling with
+/// `-C target-feature=+simd128`.
+pub const SIMD: bool = cfg!(all(
+    feature = "simd",
+    target_arch = "wasm32",
+    target_feature = "simd128"
+));
+
+impl Universe {
+    /// Whether `evolve_swar` can stand in for `evolve` under the current
+    /// settings: a plain two-state rule on the Moore neighbourhood with
+    /// no frozen border, on a board with at least one cell.
+    pub(crate) fn swar_applies(&self) -> bool {
+        self.width > 0
+            && self.height > 0
+            && self.states.is_none()
+            && self.neighborhood == NeighborhoodKind::Moore
+            && self.frozen_border == 0
+    }
+
+    /// Compute the next generation under the Life-like `rule` into `next`
+    /// 64 cells at a time, for boards where `swar_applies`.
+    ///
+    /// Each row is copied out as 64-bit words with a one-cell border, as
+    /// in `padded_cells`, so a cell's west, own and east columns are the
+    /// padded row read at offsets 0, 1 and 2. The nine words around a
+    /// stretch of 64 cells are then summed bit-slice by bit-slice into
+    /// four bit planes of neighbour counts, and the rule picks out the
+    /// counts that give birth or survival. With `SIMD` two words go through
+    /// the counting and the rule together. Rows are worked out separately,
+    /// on several threads with the `threads` feature, and then stored.
+    pub(crate) fn evolve_swar(&self, rule: LifeRule, next: &mut FixedBitSet) {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let words_per_row = (width + 2).div_ceil(64) + 1;
+        let blocks = self.cells.as_slice();
+        let background = if self.background { !0 } else { 0 };
+        let toroidal = self.boundary == BoundaryMode::Toroidal;
+
+        // Rows -1 to `height`, each `words_per_row` words long.
+        let mut padded = vec![0u64; (height + 2) * words_per_row];
+        for (i, words) in padded.chunks_mut(words_per_row).enumerate() {
+            let row = match i {
+                0 if toroidal => height - 1,
+                i if i == height + 1 && toroidal => 0,
+                i if i == 0 || i == height + 1 => {
+                    words.iter_mut().for_each(|word| *word = background);
+                    continue;
+                }
+                i => i - 1,
+            };
+            let start = row * width;
+            let cell = |col: usize| blocks[(start + col) / 32] >> ((start + col) % 32) & 1 == 1;
+            let (west, east) = if toroidal {
+                (cell(width - 1), cell(0))
+            } else {
+                (self.background, self.background)
+            };
+            for (m, word) in words.iter_mut().enumerate() {
+                *word = match m {
+                    0 => read_bits(blocks, start) << 1 | west as u64,
+                    m => read_bits(blocks, start + 64 * m - 1),
+                };
+            }
+            // Clear the bits read past the end of the row, then put the
+            // east border cell in place.
+            let end = width + 1;
+            words[end / 64] &= (1u64 << (end % 64)) - 1;
+            words[end / 64 + 1..].iter_mut().for_each(|word| *word = 0);
+            words[end / 64] |= (east as u64) << (end % 64);
+        }
+
+        let (birth, survival) = (rule.birth(), rule.survival());
+        let include_center = self.include_center;
+        let words = width.div_ceil(64);
+        let rows = threads::map_rows(self.height, |row| {
+            let row = row as usize;
+            let rows = [row, row + 1, row + 2].map(|i| &padded[i * words_per_row..]);
+            // The 3x3 block around each of the 64 cells of word `k`.
+            let block = |k: usize| -> [u64; 9] {
+                let mut block = [0; 9];
+                for (i, cells) in block.iter_mut().enumerate() {
+                    let (words, d_col) = (rows[i / 3], i % 3);
+                    let offset = 64 * k + d_col;
+                    let (word, shift) = (offset / 64, offset % 64);
+                    *cells = if shift == 0 {
+                        words[word]
+                    } else {
+                        words[word] >> shift | words[word + 1] << (64 - shift)
+                    };
+                }
+                block
+            };
+            let mut next_words = vec![0; words];
+            let mut k = 0;
+            #[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+            while k + 1 < words {
+                let (low, high) = (block(k), block(k + 1));
+                let pairs = [0, 1, 2, 3, 4, 5, 6, 7, 8].map(|i| Simd::pair(low[i], high[i]));
+                let (low, high) = next_cells(pairs, include_center, birth, survival).split();
+                next_words[k] = low;
+                next_words[k + 1] = high;
+                k += 2;
+            }
+            while k < words {
+                next_words[k] = next_cells(block(k), include_center, birth, survival);
+                k += 1;
+            }
+            next_words
+        });
+
+        let out = next.as_mut_slice();
+        out.iter_mut().for_each(|block| *block = 0);
+        for (row, next_words) in rows.iter().enumerate() {
+            for (k, &bits) in next_words.iter().enumerate() {
+                let cells = width - 64 * k;
+                let bits = if cells < 64 {
+                    bits & ((1u64 << cells) - 1)
+                } else {
+                    bits
+                };
+                or_bits(out, row * width + 64 * k, bits);
+            }
+        }
+    }
+}
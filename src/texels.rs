@@ -0,0 +1,121 @@
+//! Laying a board out for the GPU and reading it back, kept apart from the
+//! WebGPU calls in `gpu` so it builds, and can be checked, without a
+//! device.
+
+use crate::rule::Automaton;
+use crate::{BoundaryMode, Error, NeighborhoodKind, Universe};
+use fixedbitset::FixedBitSet;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Bytes per texel of an `r32uint` texture.
+pub const TEXEL: u32 = 4;
+
+/// The shader's `Params` for `universe`, as the bytes of its uniform
+/// buffer: birth and survival masks, whether the board wraps, the
+/// background and whether a cell counts itself, padded to 32 bytes. Errors
+/// unless it runs a two-state Life-like rule on the Moore neighbourhood
+/// without a frozen border, ants or noise, on a board with at least one
+/// cell.
+pub fn params(universe: &Universe) -> Result<Vec<u8>, Error> {
+    let rule = match universe.rule {
+        Automaton::Life(rule)
+            if rule.states() == 2
+                && universe.neighborhood == NeighborhoodKind::Moore
+                && universe.frozen_border == 0
+                && universe.ants.is_empty()
+                && universe.noise.is_none()
+                && universe.width > 0
+                && universe.height > 0 =>
+        {
+            rule
+        }
+        _ => {
+            return Err(Error::new(format!(
+                "{} can't run on the GPU under these settings",
+                universe.rule
+            )))
+        }
+    };
+    let words = [
+        rule.birth() as u32,
+        rule.survival() as u32,
+        (universe.boundary == BoundaryMode::Toroidal) as u32,
+        universe.background as u32,
+        universe.include_center as u32,
+        0,
+        0,
+        0,
+    ];
+    Ok(words.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
+
+/// One little-endian `r32uint` texel per cell, 1 for live cells.
+pub fn pack(cells: &FixedBitSet) -> Vec<u8> {
+    (0..cells.len())
+        .flat_map(|idx| (cells[idx] as u32).to_le_bytes())
+        .collect()
+}
+
+/// One byte per cell, row by row, from texels whose rows are
+/// `bytes_per_row` bytes apart.
+pub fn unpack(mapped: &[u8], width: usize, height: usize, bytes_per_row: usize) -> Vec<u8> {
+    (0..height)
+        .flat_map(|row| (0..width).map(move |col| (row, col)))
+        .map(|(row, col)| mapped[row * bytes_per_row + col * TEXEL as usize])
+        .collect()
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Whether `Engine::Gpu` can run this board: a two-state Life-like
+    /// rule on the Moore neighbourhood, without a frozen border, ants or
+    /// noise, on a board with at least one cell.
+    pub fn runs_on_gpu(&self) -> bool {
+        params(self).is_ok()
+    }
+
+    /// The cells as `Engine::Gpu` uploads them: one little-endian
+    /// `r32uint` texel per cell, row by row, 1 for live cells, for writing
+    /// to a texture of your own.
+    pub fn gpu_texels(&self) -> Vec<u8> {
+        pack(&self.cells)
+    }
+
+    /// Create a universe from `r32uint` texels laid out as `gpu_texels`
+    /// gives them but with rows `bytes_per_row` bytes apart, as a texture
+    /// copied into a buffer has them. Errors if a row doesn't fit in
+    /// `bytes_per_row` or `texels` is too short for `height` rows.
+    pub fn from_texels(
+        width: u32,
+        height: u32,
+        texels: &[u8],
+        bytes_per_row: u32,
+    ) -> Result<Universe, Error> {
+        let row = width as u64 * TEXEL as u64;
+        if row > bytes_per_row as u64 {
+            return Err(Error::new(format!(
+                "a row of {} texels doesn't fit in {} bytes",
+                width, bytes_per_row
+            )));
+        }
+        let needed = match height {
+            0 => 0,
+            height => (height as u64 - 1) * bytes_per_row as u64 + row,
+        };
+        if (texels.len() as u64) < needed {
+            return Err(Error::new(format!(
+                "expected at least {} bytes of texels, got {}",
+                needed,
+                texels.len()
+            )));
+        }
+        let cells = unpack(
+            texels,
+            width as usize,
+            height as usize,
+            bytes_per_row as usize,
+        );
+        Universe::from_cells(width, height, &cells)
+    }
+}
@@ -0,0 +1,40 @@
+//! Ticking on several threads with the `threads` feature.
+//!
+//! Each tick works its rows out on a Rayon thread pool. Natively that is
+//! Rayon's global pool. On the web it is a pool of Web Workers sharing the
+//! module's memory, which has to be started from JavaScript with
+//! `await init_thread_pool(navigator.hardwareConcurrency)` before the
+//! first tick. Workers can only share memory through a
+//! `SharedArrayBuffer`, which browsers only allow on cross-origin isolated
+//! pages: the page must be served with
+//! `Cross-Origin-Opener-Policy: same-origin` and
+//! `Cross-Origin-Embedder-Policy: require-corp`, and everything it loads
+//! must allow that. The module itself has to be built on nightly with
+//! `RUSTFLAGS="-C target-feature=+atomics,+bulk-memory"` and
+//! `-Z build-std=panic_abort,std`, and bundled with `--target web`.
+//!
+//! Without the feature rows are worked out one after the other as before.
+
+#[cfg(all(feature = "threads", target_arch = "wasm32"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+/// `f` for every row from 0 to `height`, in order, spread across the
+/// thread pool.
+#[cfg(feature = "threads")]
+pub(crate) fn map_rows<T, F>(height: u32, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(u32) -> T + Sync + Send,
+{
+    use rayon::prelude::*;
+    (0..height).into_par_iter().map(f).collect()
+}
+
+/// `f` for every row from 0 to `height`, in order.
+#[cfg(not(feature = "threads"))]
+pub(crate) fn map_rows<T, F>(height: u32, f: F) -> Vec<T>
+where
+    F: Fn(u32) -> T,
+{
+    (0..height).map(f).collect()
+}
@@ -0,0 +1,190 @@
+use crate::{BoundaryMode, Error, Universe};
+use fixedbitset::FixedBitSet;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Which way an ant faces.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    North = 0,
+    East = 1,
+    South = 2,
+    West = 3,
+}
+
+/// What a turmite does on a cell of some colour in some state: paint the
+/// cell, turn by `turn` quarter turns clockwise, and switch state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Transition {
+    write: bool,
+    turn: u8,
+    next: u8,
+}
+
+/// A turmite walking over the board. The board's dead and live cells are
+/// its two colours.
+#[derive(Clone, Debug)]
+pub struct Ant {
+    row: u32,
+    col: u32,
+    /// Quarter turns clockwise from north.
+    direction: u8,
+    state: u8,
+    /// Per state, the transitions on a dead and on a live cell.
+    program: Vec<[Transition; 2]>,
+}
+
+/// Parse a turmite program: either one turn per colour, as in `RL` for
+/// Langton's ant, or Golly's turmite notation, such as
+/// `{{{1, 2, 0}, {0, 8, 0}}}`, with a colour to write, a turn and a next
+/// state for each colour in each state. Turns are `L`, `R`, `N` (none) and
+/// `U` (u-turn) in the first form, and 1 (none), 2 (right), 4 (u-turn) and
+/// 8 (left) in the second.
+fn parse_program(program: &str) -> Result<Vec<[Transition; 2]>, String> {
+    let program = program.trim();
+    let invalid = || format!("invalid turmite program {:?}", program);
+    if !program.starts_with('{') {
+        let turns = program
+            .chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                'N' => Some(0),
+                'R' => Some(1),
+                'U' => Some(2),
+                'L' => Some(3),
+                _ => None,
+            })
+            .collect::<Option<Vec<u8>>>()
+            .filter(|turns| turns.len() == 2)
+            .ok_or_else(invalid)?;
+        let transition = |colour: usize| Transition {
+            write: colour == 0,
+            turn: turns[colour],
+            next: 0,
+        };
+        return Ok(vec![[transition(0), transition(1)]]);
+    }
+
+    let numbers = program
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|field| !field.is_empty())
+        .map(|field| field.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>()
+        .ok_or_else(invalid)?;
+    let states = numbers.len() / 6;
+    if numbers.is_empty() || !numbers.len().is_multiple_of(6) || states > u8::MAX as usize {
+        return Err(invalid());
+    }
+    numbers
+        .chunks(6)
+        .map(|state| {
+            let transition = |triple: &[u32]| -> Option<Transition> {
+                let turn = match triple[1] {
+                    1 => 0,
+                    2 => 1,
+                    4 => 2,
+                    8 => 3,
+                    _ => return None,
+                };
+                Some(Transition {
+                    write: [0, 1].contains(&triple[0]).then(|| triple[0] == 1)?,
+                    turn,
+                    next: (triple[2] < states as u32).then(|| triple[2] as u8)?,
+                })
+            };
+            Some([transition(&state[..3])?, transition(&state[3..])?])
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)
+}
+
+impl Universe {
+    /// Move every ant one step over `next`, a copy of the board, in the
+    /// order they were added. Ants that walk off a bounded board are gone.
+    pub(crate) fn move_ants(&mut self, next: &mut FixedBitSet) {
+        let mut ants = std::mem::take(&mut self.ants);
+        ants.retain_mut(|ant| {
+            let idx = self.get_index(ant.row, ant.col);
+            let transition = ant.program[ant.state as usize][next[idx] as usize];
+            next.set(idx, transition.write);
+            if let Some(states) = &mut self.states {
+                states[idx] = 0;
+            }
+            ant.direction = (ant.direction + transition.turn) % 4;
+            ant.state = transition.next;
+            let (d_row, d_col) = match ant.direction {
+                0 => (-1, 0),
+                1 => (0, 1),
+                2 => (1, 0),
+                _ => (0, -1),
+            };
+            let (row, col) = (ant.row as i64 + d_row, ant.col as i64 + d_col);
+            let (height, width) = (self.height as i64, self.width as i64);
+            if self.boundary == BoundaryMode::Bounded
+                && (row < 0 || col < 0 || row >= height || col >= width)
+            {
+                return false;
+            }
+            ant.row = row.rem_euclid(height) as u32;
+            ant.col = col.rem_euclid(width) as u32;
+            true
+        });
+        self.ants = ants;
+    }
+
+    /// Move the ants along with the board when it is remapped, dropping
+    /// those that end up off it.
+    pub(crate) fn remap_ants(&mut self, width: u32, height: u32, d_row: i64, d_col: i64) {
+        self.ants.retain_mut(|ant| {
+            let (row, col) = (ant.row as i64 + d_row, ant.col as i64 + d_col);
+            ant.row = row as u32;
+            ant.col = col as u32;
+            row >= 0 && col >= 0 && row < height as i64 && col < width as i64
+        });
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Put a turmite on `(row, col)` facing `direction`, running `program`
+    /// from its first state; see `parse_program` for the notation. `RL`
+    /// is Langton's ant.
+    ///
+    /// While any ants are on the board, `tick` moves each of them one step
+    /// instead of applying the rule: an ant reads the cell under it, paints
+    /// it, turns and moves on. Errors if the cell is off the board or the
+    /// program can't be parsed.
+    pub fn add_ant(
+        &mut self,
+        row: u32,
+        col: u32,
+        direction: Direction,
+        program: &str,
+    ) -> Result<(), Error> {
+        self.checked_index(row, col)?;
+        let program = parse_program(program)?;
+        self.ants.push(Ant {
+            row,
+            col,
+            direction: direction as u8,
+            state: 0,
+            program,
+        });
+        Ok(())
+    }
+
+    /// Every ant as `row, col, direction` triples, in the order they were
+    /// added, for drawing them over the board.
+    pub fn ants(&self) -> Vec<u32> {
+        self.ants
+            .iter()
+            .flat_map(|ant| [ant.row, ant.col, ant.direction as u32])
+            .collect()
+    }
+
+    /// Take every ant off the board, so ticks apply the rule again.
+    pub fn clear_ants(&mut self) {
+        self.ants.clear();
+    }
+}
@@ -0,0 +1,238 @@
+//! Undoing and redoing edits to the board.
+//!
+//! While an edit runs, every cell it writes first notes the packed word
+//! holding it, and under multi-state rules its state, as they were. When
+//! the edit ends the ones that changed are kept, before and after, so an
+//! edit costs time and memory in proportion to what it touched rather
+//! than to the board. Ticks aren't edits: undoing after a few ticks puts
+//! back only the cells the edit touched, as they were before it.
+
+use crate::recording::Op;
+use crate::Universe;
+use std::collections::{HashMap, VecDeque};
+use std::mem::{size_of, size_of_val};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Edits kept by default.
+const DEFAULT_DEPTH: u32 = 100;
+
+/// Cells in a packed word.
+const WORD_BITS: u32 = u32::BITS;
+
+/// What one edit changed.
+#[derive(Clone, Debug)]
+struct Diff {
+    /// Word index, then the word before and after the edit.
+    words: Vec<(u32, u32, u32)>,
+    /// Cell index, then its raw state before and after the edit.
+    states: Vec<(u32, u8, u8)>,
+}
+
+impl Diff {
+    fn is_empty(&self) -> bool {
+        self.words.is_empty() && self.states.is_empty()
+    }
+
+    /// Bytes the diff holds, counting its buffers.
+    fn bytes(&self) -> usize {
+        size_of::<Diff>() + size_of_val(&self.words[..]) + size_of_val(&self.states[..])
+    }
+}
+
+/// What the edit under way has touched, as it was before.
+#[derive(Clone, Debug, Default)]
+struct Touched {
+    words: HashMap<u32, u32>,
+    states: HashMap<u32, u8>,
+}
+
+/// Edits that can be undone, most recent last, and ones undone that can
+/// be redone.
+#[derive(Clone, Debug)]
+pub struct UndoStack {
+    depth: u32,
+    undo: VecDeque<Diff>,
+    redo: Vec<Diff>,
+    /// The edit under way, between `begin_edit` and `end_edit`.
+    touched: Option<Touched>,
+}
+
+impl Default for UndoStack {
+    fn default() -> UndoStack {
+        UndoStack {
+            depth: DEFAULT_DEPTH,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            touched: None,
+        }
+    }
+}
+
+impl UndoStack {
+    /// Forget every edit, as when the board is resized and they no longer
+    /// line up with it.
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Bytes the kept edits take up, undone ones included.
+    pub(crate) fn bytes(&self) -> usize {
+        self.undo.iter().chain(&self.redo).map(Diff::bytes).sum()
+    }
+
+    /// Forget the oldest edit, or failing that the undone edit furthest
+    /// from being redone. Returns the bytes freed, `None` if there was
+    /// nothing to forget.
+    pub(crate) fn drop_oldest(&mut self) -> Option<usize> {
+        match self.undo.pop_front() {
+            Some(diff) => Some(diff.bytes()),
+            None if !self.redo.is_empty() => Some(self.redo.remove(0).bytes()),
+            None => None,
+        }
+    }
+}
+
+impl Universe {
+    /// Start noting the cells an edit writes, unless undo is turned off.
+    /// Every write to the board until `end_edit` must `touch` its cell
+    /// first.
+    pub(crate) fn begin_edit(&mut self) {
+        if self.undo.depth > 0 {
+            self.undo.touched = Some(Touched::default());
+        }
+    }
+
+    /// Note the cell at `idx` as it is, if an edit is under way and hasn't
+    /// written it or its word yet.
+    pub(crate) fn touch(&mut self, idx: usize) {
+        if let Some(touched) = &mut self.undo.touched {
+            let word = idx as u32 / WORD_BITS;
+            let cells = self.cells.as_slice();
+            touched.words.entry(word).or_insert(cells[word as usize]);
+            if let Some(states) = &self.states {
+                touched.states.entry(idx as u32).or_insert(states[idx]);
+            }
+        }
+    }
+
+    /// Push what the edit since `begin_edit` changed onto the undo stack,
+    /// dropping the oldest edit if it is full, and forget anything undone.
+    pub(crate) fn end_edit(&mut self) {
+        let touched = match self.undo.touched.take() {
+            Some(touched) => touched,
+            None => return,
+        };
+        let cells = self.cells.as_slice();
+        let words = touched
+            .words
+            .into_iter()
+            .map(|(word, old)| (word, old, cells[word as usize]))
+            .filter(|&(_, old, new)| old != new)
+            .collect();
+        let states = match &self.states {
+            Some(now) => touched
+                .states
+                .into_iter()
+                .map(|(idx, old)| (idx, old, now[idx as usize]))
+                .filter(|&(_, old, new)| old != new)
+                .collect(),
+            None => Vec::new(),
+        };
+        let diff = Diff { words, states };
+        if diff.is_empty() {
+            return;
+        }
+        if self.undo.undo.len() == self.undo.depth as usize {
+            self.undo.undo.pop_front();
+        }
+        self.undo.undo.push_back(diff);
+        self.undo.redo.clear();
+        self.enforce_history_budget();
+    }
+
+    /// Write one side of `diff` to the board: what the edit found if
+    /// `before`, what it left otherwise. Recorded as the cells it sets, so
+    /// a recording replays it without needing the undo stack.
+    fn apply_diff(&mut self, diff: &Diff, before: bool) {
+        for &(word, old, new) in &diff.words {
+            let value = if before { old } else { new };
+            let current = self.cells.as_slice()[word as usize];
+            let mut changed = value ^ current;
+            while changed != 0 {
+                let bit = changed.trailing_zeros();
+                changed &= changed - 1;
+                let idx = word * WORD_BITS + bit;
+                self.record(Op::Set(idx, value >> bit & 1 == 1));
+            }
+            self.cells.as_mut_slice()[word as usize] = value;
+        }
+        // The rule may have changed since, and states it lacks go dead.
+        let count = self.rule.states();
+        if let Some(states) = &mut self.states {
+            for &(idx, old, new) in &diff.states {
+                let state = if before { old } else { new };
+                states[idx as usize] = if state < count { state } else { 0 };
+            }
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Put back the cells the last edit changed, as they were before it.
+    /// Edits are `toggle_cell`, `set_cell`, `clear_cell`, `set_cells`,
+    /// `set_cell_state`, `fill_region`, `reset_clear` and the `insert_*`
+    /// methods. Returns whether there was an edit to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo.undo.pop_back() {
+            Some(diff) => {
+                self.apply_diff(&diff, true);
+                self.undo.redo.push(diff);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Make the last undone edit again. Any new edit forgets what was
+    /// undone. Returns whether there was an edit to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.undo.redo.pop() {
+            Some(diff) => {
+                self.apply_diff(&diff, false);
+                self.undo.undo.push_back(diff);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether there is an edit for `undo` to take back.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.undo.is_empty()
+    }
+
+    /// Whether there is an undone edit for `redo` to make again.
+    pub fn can_redo(&self) -> bool {
+        !self.undo.redo.is_empty()
+    }
+
+    /// Most edits kept for `undo`, 100 unless changed.
+    pub fn undo_depth(&self) -> u32 {
+        self.undo.depth
+    }
+
+    /// Keep at most `depth` edits, forgetting the oldest beyond that. A
+    /// depth of 0 turns undo off, and with it the note each edit otherwise
+    /// takes of the cells it writes.
+    pub fn set_undo_depth(&mut self, depth: u32) {
+        self.undo.depth = depth;
+        let excess = self.undo.undo.len().saturating_sub(depth as usize);
+        self.undo.undo.drain(..excess);
+        if depth == 0 {
+            self.undo.redo.clear();
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use fixedbitset::FixedBitSet;
+use std::ops::Range;
+
 pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function at least once during initialization, and then
@@ -5,6 +8,107 @@ pub fn set_panic_hook() {
     //
     // For more details see
     // https://github.com/rustwasm/console_error_panic_hook#readme
-    #[cfg(feature = "console_error_panic_hook")]
+    #[cfg(all(feature = "console_error_panic_hook", target_arch = "wasm32"))]
     console_error_panic_hook::set_once();
 }
+
+/// Milliseconds from `performance.now()`, or from `Date.now()` where there
+/// is no window, as in a worker.
+#[cfg(target_arch = "wasm32")]
+pub fn now() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map_or_else(js_sys::Date::now, |performance| performance.now())
+}
+
+/// Milliseconds since the Unix epoch, off the web.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0)
+}
+
+/// A random number in `[0, 1)` from `Math.random()`.
+#[cfg(target_arch = "wasm32")]
+pub fn random() -> f64 {
+    js_sys::Math::random()
+}
+
+/// A random number in `[0, 1)`, off the web, from a generator seeded from
+/// the standard library's per-process hash keys.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn random() -> f64 {
+    use crate::rng::Rng;
+    use std::cell::RefCell;
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    thread_local! {
+        static RNG: RefCell<Rng> =
+            RefCell::new(Rng::new(RandomState::new().build_hasher().finish()));
+    }
+    RNG.with(|rng| rng.borrow_mut().next_f64())
+}
+
+/// A seed for operations that weren't given one.
+pub fn random_seed() -> u64 {
+    (random() * u32::MAX as f64) as u64
+}
+
+/// Number of bits in `range` that differ between two equally sized bitsets,
+/// computed a word at a time.
+pub fn count_diff(a: &FixedBitSet, b: &FixedBitSet, range: Range<usize>) -> u32 {
+    let (a, b) = (a.as_slice(), b.as_slice());
+    let mut count = 0;
+    let mut bit = range.start;
+    while bit < range.end {
+        let word = bit / 32;
+        let offset = bit % 32;
+        let len = (32 - offset).min(range.end - bit);
+        let mask = (u32::MAX >> (32 - len)) << offset;
+        count += ((a[word] ^ b[word]) & mask).count_ones();
+        bit += len;
+    }
+    count
+}
+
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encode with the URL-safe base64 alphabet, without padding.
+pub fn base64_url_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..=chunk.len() {
+            out.push(BASE64_URL[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Decode URL-safe base64, with or without padding. `None` if `text`
+/// contains other characters or has an impossible length.
+pub fn base64_url_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    if text.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = BASE64_URL.iter().position(|&b| b == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,250 @@
+use crate::rng;
+use crate::rule::Automaton;
+use crate::{Error, Universe};
+use fixedbitset::FixedBitSet;
+use std::fmt;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Wa-Tor cell states. Fish are state 1, so they are the board's live
+/// cells.
+pub const WATER: u8 = 0;
+pub const FISH: u8 = 1;
+pub const SHARK: u8 = 2;
+
+/// Dewdney's Wa-Tor predator-prey simulation. Each tick every fish and
+/// shark acts once, row by row. A fish swims to a random free orthogonal
+/// neighbour and, once `fish_breed` ticks old, leaves a new fish behind.
+/// A shark loses one energy, swims onto a neighbouring fish if there is
+/// one, gaining `energy` by eating it, or else to a free neighbour, and
+/// breeds the same way after `shark_breed` ticks. Sharks out of energy
+/// die. Newborn sharks start with `energy`.
+///
+/// The random choices come from `seed`, the generation and the cell, so a
+/// run is the same every time it is replayed from the same board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WaTor {
+    fish_breed: u32,
+    shark_breed: u32,
+    energy: u32,
+    seed: u64,
+}
+
+/// The counters of the fish or shark on a cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Creature {
+    /// Ticks since it was born or last bred.
+    age: u32,
+    /// Ticks a shark can still go without eating.
+    energy: u32,
+}
+
+impl Creature {
+    pub(crate) fn new(age: u32, energy: u32) -> Creature {
+        Creature { age, energy }
+    }
+
+    /// Its age and energy, as `new` takes them.
+    pub(crate) fn counters(self) -> (u32, u32) {
+        (self.age, self.energy)
+    }
+}
+
+impl WaTor {
+    /// Errors if any of the counts is 0.
+    pub fn new(fish_breed: u32, shark_breed: u32, energy: u32, seed: u64) -> Result<WaTor, String> {
+        if fish_breed == 0 || shark_breed == 0 || energy == 0 {
+            return Err("breeding times and energy must be at least 1".to_string());
+        }
+        Ok(WaTor {
+            fish_breed,
+            shark_breed,
+            energy,
+            seed,
+        })
+    }
+
+    /// Parse the `WaTor,FB3,SB8,E4,S42` form written by `Display`: fish
+    /// breeding time, shark breeding time, shark energy and seed. Letters
+    /// may be either case.
+    pub fn parse(rule: &str) -> Result<WaTor, String> {
+        let rule = rule.trim().to_ascii_uppercase();
+        let invalid = || format!("invalid rule {:?}", rule);
+        let fields: Vec<&str> = rule.split(',').map(str::trim).collect();
+        if fields.len() != 5 || fields[0] != "WATOR" {
+            return Err(invalid());
+        }
+        let count =
+            |field: &str, prefix: &str| -> Option<u32> { field.strip_prefix(prefix)?.parse().ok() };
+        match (
+            count(fields[1], "FB"),
+            count(fields[2], "SB"),
+            count(fields[3], "E"),
+            fields[4]
+                .strip_prefix('S')
+                .and_then(|seed| seed.parse().ok()),
+        ) {
+            (Some(fish_breed), Some(shark_breed), Some(energy), Some(seed)) => {
+                WaTor::new(fish_breed, shark_breed, energy, seed).map_err(|_| invalid())
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// The counters of a creature placed on the board or just born.
+    pub fn newborn(self) -> Creature {
+        Creature {
+            age: 0,
+            energy: self.energy,
+        }
+    }
+}
+
+impl fmt::Display for WaTor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "WaTor,FB{},SB{},E{},S{}",
+            self.fish_breed, self.shark_breed, self.energy, self.seed
+        )
+    }
+}
+
+/// Write the species on every cell into `states`, and mark the fish in
+/// `next`.
+pub(crate) fn paint_sea(species: &[u8], next: &mut FixedBitSet, mut states: Option<&mut [u8]>) {
+    for (idx, &kind) in species.iter().enumerate() {
+        next.set(idx, kind == FISH);
+        if let Some(states) = &mut states {
+            states[idx] = kind;
+        }
+    }
+}
+
+impl Universe {
+    /// The species and counters on every cell after one tick of `rule`.
+    pub(crate) fn swum(&self, rule: WaTor) -> (Vec<u8>, Vec<Creature>) {
+        let size = self.cells.len();
+        let mut species: Vec<u8> = (0..size).map(|idx| self.cell_state(idx)).collect();
+        let mut creatures = self
+            .sea
+            .clone()
+            .unwrap_or_else(|| vec![Creature::default(); size]);
+        let mut moved = vec![false; size];
+        let seed = rng::mix(rule.seed, self.generation);
+
+        for idx in 0..size {
+            let kind = species[idx];
+            if moved[idx] || kind == WATER {
+                continue;
+            }
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            let neighbours: Vec<usize> = [(-1, 0), (0, -1), (0, 1), (1, 0)]
+                .iter()
+                .filter_map(|&(d_row, d_col)| self.offset_index(row, col, d_row, d_col))
+                .collect();
+            let draw = rng::mix(seed, idx as u64);
+            let pick = |of: u8| -> Option<usize> {
+                let choices: Vec<usize> = neighbours
+                    .iter()
+                    .cloned()
+                    .filter(|&n| species[n] == of)
+                    .collect();
+                (!choices.is_empty()).then(|| choices[(draw % choices.len() as u64) as usize])
+            };
+
+            let mut creature = creatures[idx];
+            creature.age = creature.age.saturating_add(1);
+            let (target, breed) = if kind == FISH {
+                (pick(WATER), rule.fish_breed)
+            } else {
+                creature.energy = creature.energy.saturating_sub(1);
+                let prey = pick(FISH);
+                if prey.is_some() {
+                    creature.energy = creature.energy.saturating_add(rule.energy);
+                } else if creature.energy == 0 {
+                    species[idx] = WATER;
+                    creatures[idx] = Creature::default();
+                    continue;
+                }
+                (prey.or_else(|| pick(WATER)), rule.shark_breed)
+            };
+
+            match target {
+                Some(to) => {
+                    moved[to] = true;
+                    species[to] = kind;
+                    if creature.age >= breed {
+                        creature.age = 0;
+                        creatures[idx] = rule.newborn();
+                    } else {
+                        species[idx] = WATER;
+                        creatures[idx] = Creature::default();
+                    }
+                    creatures[to] = creature;
+                }
+                None => creatures[idx] = creature,
+            }
+        }
+        (species, creatures)
+    }
+
+    fn require_wator(&self) -> Result<WaTor, Error> {
+        match self.rule {
+            Automaton::WaTor(rule) => Ok(rule),
+            _ => Err(Error::new(format!(
+                "the sea needs the Wa-Tor rule, not {}",
+                self.rule
+            ))),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Switch to Wa-Tor with the given breeding times, shark energy and
+    /// seed; see `WaTor`. Errors if any of the counts is 0. The board is
+    /// left as it is, its live cells becoming fish.
+    pub fn set_wator(
+        &mut self,
+        fish_breed: u32,
+        shark_breed: u32,
+        energy: u32,
+        seed: u64,
+    ) -> Result<(), Error> {
+        let rule = WaTor::new(fish_breed, shark_breed, energy, seed)?;
+        self.apply_rule(Automaton::WaTor(rule));
+        Ok(())
+    }
+
+    /// Fill the sea at random, each cell holding a fish with probability
+    /// `fish` and a shark with probability `sharks`, reproducibly for a
+    /// given `seed`. Errors if the rule isn't Wa-Tor.
+    pub fn fill_sea(&mut self, fish: f64, sharks: f64, seed: u64) -> Result<(), Error> {
+        self.require_wator()?;
+        let mut rng = rng::Rng::new(seed);
+        for idx in 0..self.cells.len() {
+            let draw = rng.next_f64();
+            let kind = if draw < fish {
+                FISH
+            } else if draw < fish + sharks {
+                SHARK
+            } else {
+                WATER
+            };
+            self.put_state(idx, kind);
+        }
+        Ok(())
+    }
+
+    /// Each shark's energy, row by row, with 0 for fish and water. Use
+    /// `cell_states` for the species.
+    pub fn shark_energy(&self) -> Vec<u32> {
+        (0..self.cells.len())
+            .map(|idx| match (&self.sea, self.cell_state(idx)) {
+                (Some(sea), SHARK) => sea[idx].energy,
+                _ => 0,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,86 @@
+//! The methods that take or return JavaScript objects, on wasm32 only.
+//! Each one does its work through the plain Rust methods and only moves
+//! data in and out of typed arrays here.
+
+use crate::{Error, GrayScott, Lenia, UniverseShard};
+use js_sys::{Float32Array, Uint32Array};
+use wasm_bindgen::prelude::*;
+
+impl From<Error> for JsValue {
+    fn from(error: Error) -> JsValue {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+#[wasm_bindgen]
+extern "C" {
+    fn alert(s: &str);
+}
+
+#[wasm_bindgen]
+impl GrayScott {
+    /// The concentration of V, one `f32` per cell, row by row, as a view
+    /// straight into wasm memory for uploading to a texture or drawing.
+    /// The view is only valid until the next call into the module that
+    /// allocates, so take a fresh one every frame.
+    pub fn concentration(&self) -> Float32Array {
+        // Safety: the view is handed straight to JavaScript, and the
+        // caveat above is the usual one for views into wasm memory.
+        unsafe { Float32Array::view(self.concentration_slice()) }
+    }
+}
+
+#[wasm_bindgen]
+impl Lenia {
+    /// Every cell's state, row by row, as a view straight into wasm
+    /// memory. The view is only valid until the next call into the module
+    /// that allocates, so take a fresh one every frame.
+    pub fn cells(&self) -> Float32Array {
+        // Safety: the view is handed straight to JavaScript, and the
+        // caveat above is the usual one for views into wasm memory.
+        unsafe { Float32Array::view(self.cells_slice()) }
+    }
+}
+
+#[wasm_bindgen]
+impl UniverseShard {
+    /// Write `edges` into this band's slots of a halo buffer shared by all
+    /// the bands: words `2 * index * words_per_row` onwards. Errors unless
+    /// the buffer is `2 * shards * words_per_row` words long.
+    pub fn publish_edges(&self, shared: &Uint32Array) -> Result<(), Error> {
+        self.check_halo_buffer(shared.length() as usize)?;
+        let start = self.edges_offset() as u32;
+        let edges = self.edges();
+        shared
+            .subarray(start, start + edges.len() as u32)
+            .copy_from(&edges);
+        Ok(())
+    }
+
+    /// Read the halos from the edges the neighbouring bands published to
+    /// `shared`. Off a bounded board the halos take the background state;
+    /// on a toroidal one the first and last bands are neighbours. Errors
+    /// unless the buffer is `2 * shards * words_per_row` words long.
+    pub fn sync_halos(&mut self, shared: &Uint32Array) -> Result<(), Error> {
+        self.check_halo_buffer(shared.length() as usize)?;
+        let words = self.words_per_row();
+        let read = |start: Option<usize>| match start {
+            Some(start) => shared.subarray(start as u32, start as u32 + words).to_vec(),
+            None => self.background_row(),
+        };
+        let (above, below) = self.halo_offsets();
+        let (above, below) = (read(above), read(below));
+        self.set_halos(&above, &below)
+    }
+
+    /// Write `band` into its place in a board buffer shared by all the
+    /// bands, laid out as in `Universe::cells_row_aligned`. Errors if the
+    /// buffer ends before the band's last row.
+    pub fn write_band(&self, board: &Uint32Array) -> Result<(), Error> {
+        let range = self.band_range(board.length() as usize)?;
+        board
+            .subarray(range.start as u32, range.end as u32)
+            .copy_from(&self.band());
+        Ok(())
+    }
+}
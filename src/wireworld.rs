@@ -0,0 +1,140 @@
+use crate::engine::{self, Neighborhood};
+use crate::hensel::CENTRE;
+use crate::rule::Automaton;
+use crate::{Error, Universe};
+use std::fmt;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Wireworld cell states, as reported by `cell_states`. Electron heads
+/// are state 1, so they are the board's live cells.
+pub const EMPTY: u8 = 0;
+pub const HEAD: u8 = 1;
+pub const TAIL: u8 = 2;
+pub const CONDUCTOR: u8 = 3;
+
+/// Brian Silverman's Wireworld: electrons made of a head and a tail run
+/// along conductors. A head becomes a tail, a tail becomes a conductor,
+/// and a conductor becomes a head when one or two of its eight
+/// neighbours are heads. Empty cells stay empty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wireworld;
+
+impl Wireworld {
+    pub fn parse(rule: &str) -> Result<Wireworld, String> {
+        if rule.trim().eq_ignore_ascii_case("WIREWORLD") {
+            Ok(Wireworld)
+        } else {
+            Err(format!("invalid rule {:?}", rule))
+        }
+    }
+}
+
+impl fmt::Display for Wireworld {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Wireworld")
+    }
+}
+
+impl engine::Rule for Wireworld {
+    #[inline]
+    fn next_state(&self, current: u8, neighborhood: &Neighborhood) -> u8 {
+        match current {
+            EMPTY => EMPTY,
+            HEAD => TAIL,
+            TAIL => CONDUCTOR,
+            _ => match (neighborhood.block() & !CENTRE).count_ones() {
+                1 | 2 => HEAD,
+                _ => CONDUCTOR,
+            },
+        }
+    }
+}
+
+/// Built-in Wireworld circuits for `insert_circuit`.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Circuit {
+    /// Passes electrons from left to right and stops them going back.
+    Diode = 0,
+    /// A loop with one electron in it, sending an electron down the wire
+    /// on its right every six generations.
+    Clock = 1,
+    /// Electrons arriving on the top left or bottom left wire alone come
+    /// out on the right; two arriving together cancel out.
+    XorGate = 2,
+}
+
+impl Circuit {
+    /// The layout, row by row: `#` for a conductor, `H` for an electron
+    /// head and `t` for a tail.
+    fn rows(self) -> &'static [&'static str] {
+        match self {
+            Circuit::Diode => &["   ##", "#### ####", "   ##"],
+            Circuit::Clock => &[" Ht", "#  #######", " ##"],
+            Circuit::XorGate => &[
+                "####",
+                "   #",
+                "  ####",
+                "  #  ####",
+                "  ####",
+                "   #",
+                "####",
+            ],
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    /// Lay a conductor on `(row, col)`. Errors if the cell is off the
+    /// board or the rule isn't Wireworld.
+    pub fn paint_conductor(&mut self, row: u32, col: u32) -> Result<(), Error> {
+        self.require_wireworld()?;
+        self.set_cell_state(row, col, CONDUCTOR)
+    }
+
+    /// Put an electron head on `(row, col)`. Errors like
+    /// `paint_conductor`.
+    pub fn paint_electron(&mut self, row: u32, col: u32) -> Result<(), Error> {
+        self.require_wireworld()?;
+        self.set_cell_state(row, col, HEAD)
+    }
+
+    /// Lay out a built-in circuit with its top-left corner at `(row, col)`.
+    /// Cells that fall off the grid wrap around in toroidal mode and are
+    /// dropped in bounded mode. Errors like `paint_conductor`.
+    pub fn insert_circuit(&mut self, circuit: Circuit, row: u32, col: u32) -> Result<(), Error> {
+        self.require_wireworld()?;
+        self.checked_index(row, col)?;
+        self.begin_edit();
+        for (d_row, line) in circuit.rows().iter().enumerate() {
+            for (d_col, c) in line.chars().enumerate() {
+                let state = match c {
+                    '#' => CONDUCTOR,
+                    'H' => HEAD,
+                    't' => TAIL,
+                    _ => continue,
+                };
+                if let Some(idx) = self.offset_index(row, col, d_row as i64, d_col as i64) {
+                    self.put_state(idx, state);
+                }
+            }
+        }
+        self.end_edit();
+        Ok(())
+    }
+}
+
+impl Universe {
+    fn require_wireworld(&self) -> Result<(), Error> {
+        match self.rule {
+            Automaton::Wireworld(_) => Ok(()),
+            _ => Err(Error::new(format!(
+                "circuits need the Wireworld rule, not {}",
+                self.rule
+            ))),
+        }
+    }
+}
@@ -1,12 +1,16 @@
-//! Test suite for the Web and headless browsers.
-
-#![cfg(target_arch = "wasm32")]
+//! Test suite for the Web and headless browsers. Off the web the tests
+//! run as ordinary `#[test]`s, all but those that need JavaScript objects
+//! or the event loop.
 
 extern crate wasm_bindgen_test;
 use wasm_bindgen_test::*;
 
 extern crate wasm_game_of_life;
-use wasm_game_of_life::Universe;
+use wasm_game_of_life::{
+    hamming_distance, BoundaryMode, CancelToken, Cell, Circuit, Direction, Engine, FillMode,
+    GrayScott, GrayScottPreset, Lenia, NeighborhoodKind, ResizeMode, RleReader, Rule,
+    SparseUniverse, Universe, UniverseBuilder, UniverseShard,
+};
 
 wasm_bindgen_test_configure!(run_in_browser);
 
@@ -28,7 +32,7 @@ pub fn expected_spaceship() -> Universe {
     universe
 }
 
-#[wasm_bindgen_test]
+#[wasm_bindgen_test(unsupported = test)]
 pub fn test_tick() {
     // Let's create a smaller Universe with a small spaceship to test!
     let mut input_universe = input_spaceship();
@@ -41,3 +45,2854 @@ pub fn test_tick() {
     input_universe.tick();
     assert_eq!(&input_universe.get_cells(), &expected_universe.get_cells());
 }
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_wraps_through_corners() {
+    // The glider moves one cell diagonally every four generations, so
+    // after 24 it has crossed every edge and corner of the 6x6 torus and
+    // is back where it started.
+    let mut universe = input_spaceship();
+    for _ in 0..24 {
+        universe.tick();
+    }
+    assert_eq!(universe.get_cells(), input_spaceship().get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_empty_boards_tick() {
+    for &(width, height) in [(0, 0), (0, 5), (5, 0)].iter() {
+        let mut universe = Universe::with_size(width, height, Some(FillMode::Clear));
+        assert!(universe.preview_next().is_empty());
+        universe.tick();
+        universe.jump(3).unwrap();
+        universe.set_engine(Engine::Hashlife);
+        universe.tick();
+        assert_eq!(universe.generation(), 10);
+        assert_eq!(universe.population(), 0);
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_render_non_word_aligned_width() {
+    // 30 columns means rows straddle the 32-bit words of the bitset.
+    let mut universe = Universe::new();
+    universe.set_width(30);
+    universe.set_height(3);
+    universe.set_cells(&[(0, 0), (0, 29), (1, 1), (2, 28)]);
+
+    let expected = concat!(
+        "◼◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◼\n",
+        "◻◼◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻\n",
+        "◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◻◼◻\n",
+    );
+
+    assert_eq!(universe.render(), expected);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_cells_row_aligned() {
+    let mut universe = Universe::new();
+    universe.set_width(100);
+    universe.set_height(3);
+    universe.set_cells(&[(0, 0), (0, 99), (1, 31), (1, 32), (2, 64)]);
+
+    assert_eq!(universe.row_stride(), 128);
+    let words = universe.cells_row_aligned();
+    assert_eq!(words.len(), 3 * 4);
+    assert_eq!(&words[0..4], &[1, 0, 0, 1 << 3]);
+    assert_eq!(&words[4..8], &[1 << 31, 1, 0, 0]);
+    assert_eq!(&words[8..12], &[0, 0, 1, 0]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_edge_flux() {
+    let mut universe = Universe::new();
+    universe.set_width(5);
+    universe.set_height(4);
+    universe.set_cells(&[(0, 0), (0, 2), (1, 4), (2, 2), (3, 1), (3, 4)]);
+
+    assert_eq!(universe.edge_flux(), vec![2, 2, 2, 1]);
+
+    universe.set_height(0);
+    assert_eq!(universe.edge_flux(), vec![0, 0, 0, 0]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_frozen_border_stops_wrapping() {
+    // A blinker hugging the left edge would normally wrap onto the right edge.
+    let mut universe = Universe::new();
+    universe.set_width(8);
+    universe.set_height(8);
+    universe.set_frozen_border(1);
+    universe.set_cells(&[(3, 1), (4, 1), (5, 1)]);
+
+    universe.tick();
+    let cells = universe.get_cells();
+    assert_eq!(cells.iter().filter(|&&c| c == Cell::Alive).count(), 2);
+    assert_eq!(cells[4 * 8 + 1], Cell::Alive);
+    assert_eq!(cells[4 * 8 + 2], Cell::Alive);
+    assert_eq!(cells[4 * 8], Cell::Dead);
+
+    universe.set_frozen_border(0);
+    universe.reset_clear();
+    universe.set_cells(&[(3, 0), (4, 0), (5, 0)]);
+    universe.tick();
+    assert_eq!(universe.get_cells()[4 * 8 + 7], Cell::Alive);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_shuffle_keeps_population() {
+    let population = |universe: &Universe| {
+        universe
+            .get_cells()
+            .iter()
+            .filter(|&&c| c == Cell::Alive)
+            .count()
+    };
+
+    let mut a = input_spaceship();
+    let mut b = input_spaceship();
+    a.shuffle(7);
+    b.shuffle(7);
+    assert_eq!(population(&a), 5);
+    assert_eq!(a.get_cells(), b.get_cells());
+
+    b.shuffle(8);
+    assert_eq!(population(&b), 5);
+
+    // A frozen border covering the whole board leaves nothing to move.
+    let mut frozen = input_spaceship();
+    frozen.set_frozen_border(3);
+    frozen.set_cells(&[(1, 2), (2, 3), (3, 1)]);
+    let before = frozen.get_cells();
+    frozen.shuffle(7);
+    assert_eq!(frozen.get_cells(), before);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_cells_in_radius() {
+    let mut universe = Universe::new();
+    universe.set_width(6);
+    universe.set_height(6);
+    universe.set_cells(&[(5, 5), (0, 1), (1, 0)]);
+
+    #[rustfmt::skip]
+    let expected = vec![
+        1, 0, 0,
+        0, 0, 1,
+        0, 1, 0,
+    ];
+    assert_eq!(universe.cells_in_radius(0, 0, 1).unwrap(), expected);
+
+    // Bounded boards read the background past their edges.
+    universe.set_boundary_mode(BoundaryMode::Bounded);
+    #[rustfmt::skip]
+    let expected = vec![
+        0, 0, 0,
+        0, 0, 1,
+        0, 1, 0,
+    ];
+    assert_eq!(universe.cells_in_radius(0, 0, 1).unwrap(), expected);
+    universe.set_background(true);
+    assert_eq!(
+        universe.cells_in_radius(5, 5, 1).unwrap(),
+        [0, 0, 1, 0, 1, 1, 1, 1, 1]
+    );
+
+    assert!(universe.cells_in_radius(6, 0, 1).is_err());
+    assert!(universe.cells_in_radius(0, 6, 1).is_err());
+    assert!(universe.cells_in_radius(0, 0, u32::MAX).is_err());
+    universe.set_height(0);
+    assert!(universe.cells_in_radius(0, 0, 1).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_with_injection() {
+    // Two cells alone would die; injecting a third forms a blinker first.
+    let mut universe = Universe::new();
+    universe.set_width(6);
+    universe.set_height(6);
+    universe.set_cells(&[(1, 2), (2, 2)]);
+
+    universe.tick_with_injection(&[3 * 6 + 2], &[1]).unwrap();
+
+    let mut expected = Universe::new();
+    expected.set_width(6);
+    expected.set_height(6);
+    expected.set_cells(&[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(universe.get_cells(), expected.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_oldest_cell() {
+    let mut universe = Universe::new();
+    universe.set_width(8);
+    universe.set_height(8);
+    assert_eq!(universe.oldest_cell(), None);
+
+    // A blinker whose centre survives every generation.
+    universe.set_cells(&[(4, 5), (5, 5), (6, 5)]);
+    universe.tick();
+    // A block added later is younger than the blinker centre.
+    universe.set_cells(&[(1, 1), (1, 2), (2, 1), (2, 2)]);
+    universe.tick();
+    universe.tick();
+
+    assert_eq!(universe.oldest_cell(), Some((5, 5, 3)));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_detect_symmetry() {
+    let mut universe = Universe::new();
+    universe.set_width(20);
+    universe.set_height(20);
+    universe.insert_pulsar_at_pos(10, 10).unwrap();
+    assert_eq!(universe.detect_symmetry(), "D4");
+
+    // An L-tromino is only symmetric about one diagonal.
+    universe.reset_clear();
+    universe.set_cells(&[(0, 0), (0, 1), (1, 0)]);
+    assert_eq!(universe.detect_symmetry(), "diagonal");
+
+    // The R-pentomino has no symmetry at all.
+    universe.reset_clear();
+    universe.set_cells(&[(0, 1), (0, 2), (1, 0), (1, 1), (2, 1)]);
+    assert_eq!(universe.detect_symmetry(), "none");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_set_cells_from_words() {
+    let mut source = input_spaceship();
+    source.tick();
+    let words = source.cells_row_aligned();
+    assert_eq!(words.len(), 6);
+
+    // A 6x6 board packs into two words: bit `row * 6 + col`.
+    let mut packed = [0u32; 2];
+    for (row, &word) in words.iter().enumerate() {
+        for col in 0..6 {
+            if word & (1 << col) != 0 {
+                let i = row * 6 + col;
+                packed[i / 32] |= 1 << (i % 32);
+            }
+        }
+    }
+
+    let mut target = Universe::new();
+    target.set_width(6);
+    target.set_height(6);
+    target.set_cells_from_words(&packed).unwrap();
+    assert_eq!(target.get_cells(), expected_spaceship().get_cells());
+
+    target.tick();
+    source.tick();
+    assert_eq!(target.get_cells(), source.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_bounded_alive_background() {
+    let mut universe = Universe::new();
+    universe.set_width(4);
+    universe.set_height(4);
+    universe.set_boundary_mode(BoundaryMode::Bounded);
+    universe.set_background(true);
+    assert_eq!(universe.population(), 0);
+
+    // Non-corner edge cells see exactly three live off-grid neighbours.
+    universe.tick();
+    assert_eq!(universe.population(), 8);
+    assert_eq!(universe.get_cells()[0], Cell::Dead);
+    assert_eq!(universe.get_cells()[1], Cell::Alive);
+
+    universe.reset_clear();
+    assert_eq!(universe.population(), 16);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_capture_every() {
+    let mut universe = input_spaceship();
+    let capture = universe.capture_every(4, 2, 2);
+
+    let header: Vec<u32> = capture[..12]
+        .chunks(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    assert_eq!(header, vec![3, 12, 12]);
+    assert_eq!(capture.len(), 12 + 3 * 12 * 12 * 4);
+
+    // Cell (1, 2) of the first frame covers pixels (2..4, 4..6).
+    let first = &capture[12..];
+    let pixel = |row: usize, col: usize| &first[(row * 12 + col) * 4..][..4];
+    assert_eq!(pixel(2, 4), &[0, 0, 0, 255]);
+    assert_eq!(pixel(3, 5), &[0, 0, 0, 255]);
+    assert_eq!(pixel(2, 6), &[255, 255, 255, 255]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_find_pattern() {
+    // An L-tromino becomes a block after one generation.
+    let mut universe = Universe::new();
+    universe.set_width(6);
+    universe.set_height(6);
+    universe.set_cells(&[(2, 2), (2, 3), (3, 2)]);
+    let before = universe.get_cells();
+
+    let block = [1, 1, 1, 1];
+    let never = CancelToken::new();
+    assert_eq!(universe.find_pattern(2, 2, &block, 0, &never), None);
+    assert_eq!(universe.find_pattern(2, 2, &block, 5, &never), Some(1));
+    assert_eq!(universe.get_cells(), before);
+
+    // Matches in any orientation: the tromino itself, rotated.
+    assert_eq!(
+        universe.find_pattern(2, 2, &[0, 1, 1, 1], 5, &never),
+        Some(0)
+    );
+
+    // A cancelled search gives up and still puts the board back.
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    assert_eq!(universe.find_pattern(2, 2, &block, 5, &cancel), None);
+    assert_eq!(universe.get_cells(), before);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_inserters_respect_boundary_mode() {
+    let mut universe = Universe::new();
+    universe.set_width(20);
+    universe.set_height(20);
+
+    universe.insert_pulsar_at_pos(0, 0).unwrap();
+    universe.insert_glider_at_pos(10, 0).unwrap();
+    assert_eq!(universe.population(), 48 + 5);
+    // The pulsar's top-left quarter wraps to the far corner.
+    assert_eq!(universe.get_cells()[14 * 20 + 18], Cell::Alive);
+
+    universe.reset_clear();
+    universe.set_boundary_mode(BoundaryMode::Bounded);
+    universe.insert_pulsar_at_pos(0, 0).unwrap();
+    universe.insert_glider_at_pos(10, 0).unwrap();
+    assert_eq!(universe.population(), 12 + 3);
+    assert_eq!(universe.get_cells()[14 * 20 + 18], Cell::Dead);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_hamming_distance() {
+    assert_eq!(hamming_distance(&[0b1011, 0], &[0b0001, 1 << 31]), 3);
+    assert_eq!(hamming_distance(&[7], &[7]), 0);
+    assert_eq!(hamming_distance(&[7], &[7, 0]), u32::MAX);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_count_differences() {
+    let mut a = input_spaceship();
+    let b = input_spaceship();
+    assert!(a.equals(&b));
+    assert_eq!(a.count_differences(&b), 0);
+
+    a.tick();
+    assert!(!a.equals(&b));
+    let (before, after) = (b.get_cells_bytes(), a.get_cells_bytes());
+    let changed = before.iter().zip(&after).filter(|(x, y)| x != y).count();
+    assert_eq!(a.count_differences(&b), changed as u32);
+    assert!(changed > 0);
+
+    let mut small = input_spaceship();
+    small.set_width(5);
+    assert_eq!(a.count_differences(&small), u32::MAX);
+
+    // Dying cells count as differences even with the same live cells.
+    let mut dying = Universe::with_size(6, 6, Some(FillMode::Clear));
+    dying.set_rule("B2/S/C3").unwrap();
+    let mut empty = Universe::with_size(6, 6, Some(FillMode::Clear));
+    assert!(dying.equals(&empty));
+    dying.set_cell_state(2, 2, 2).unwrap();
+    assert_eq!(dying.count_differences(&empty), 1);
+    empty.set_rule("B2/S/C3").unwrap();
+    assert_eq!(empty.count_differences(&dying), 1);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_include_center() {
+    let block = || {
+        let mut universe = Universe::new();
+        universe.set_width(6);
+        universe.set_height(6);
+        universe.set_cells(&[(2, 2), (2, 3), (3, 2), (3, 3)]);
+        universe
+    };
+
+    // Outer-totalistic: every block cell sees 3 neighbours and survives.
+    let mut outer = block();
+    outer.tick();
+    assert_eq!(outer.get_cells(), block().get_cells());
+
+    // Totalistic: the cell itself makes 4, which is overcrowding.
+    let mut totalistic = block();
+    totalistic.set_include_center(true);
+    totalistic.tick();
+    assert_eq!(totalistic.population(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_rle_reader_streaming() {
+    let rle =
+        "#N Glider with a long run\n#C comment\nx = 14, y = 4, rule = B3/S23\nbo$2bo$3o$12o2bo!\n";
+    let whole = Universe::from_rle(rle).unwrap();
+    assert_eq!(whole.width(), 15);
+    assert_eq!(whole.height(), 4);
+    assert_eq!(whole.population(), 5 + 13);
+
+    // Split at every position, including inside "12o" and the header.
+    for split in 0..rle.len() {
+        let mut reader = RleReader::new();
+        reader.feed(&rle[..split]);
+        reader.feed(&rle[split..]);
+        assert_eq!(reader.finish().unwrap().get_cells(), whole.get_cells());
+    }
+
+    let mut reader = RleReader::new();
+    for c in rle.chars() {
+        reader.feed(&c.to_string());
+    }
+    assert_eq!(reader.finish().unwrap().get_cells(), whole.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_insert_rle_stamps_pattern() {
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    universe.set_cells(&[(1, 1)]);
+    universe
+        .insert_rle(1, 1, "#N Glider\nx = 3, y = 3, rule = B36/S23\nbo$2bo$3o!")
+        .unwrap();
+    assert_eq!(universe.get_cells(), input_spaceship().get_cells());
+    assert_eq!(universe.rule(), "B3/S23");
+    assert!(universe.insert_rle(0, 0, "x = 1, y = 1\n%!").is_err());
+    assert!(universe.insert_rle(6, 0, "o!").is_err());
+
+    // A header or run can't ask for more cells than a pattern may hold.
+    assert!(universe
+        .insert_rle(0, 0, "x = 70000, y = 70000\no!")
+        .is_err());
+    assert!(universe.insert_rle(0, 0, "4000000000o!").is_err());
+    assert_eq!(universe.get_cells(), input_spaceship().get_cells());
+
+    // Stamped cells are recorded, so a recording replays them.
+    let mut recorded = Universe::with_size(6, 6, Some(FillMode::Clear));
+    recorded.start_recording();
+    recorded.insert_rle(1, 1, "bo$2bo$3o!").unwrap();
+    let mut replayed = Universe::with_size(6, 6, Some(FillMode::Clear));
+    replayed.replay(&recorded.export_recording()).unwrap();
+    assert_eq!(replayed.get_cells(), input_spaceship().get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_to_rle_round_trips() {
+    let mut source = Universe::with_size(20, 12, Some(FillMode::Clear));
+    source.set_rule("B36/S23").unwrap();
+    source.insert_glider_at_pos(3, 4).unwrap();
+    source.set_cells(&[(0, 19), (11, 0)]);
+
+    // The whole board comes back at its own size, dead edges and all.
+    let rle = source.to_rle();
+    assert!(rle.contains("x = 20, y = 12, rule = B36/S23"), "{}", rle);
+    let target = Universe::from_rle(&rle).unwrap();
+    assert_eq!((target.width(), target.height()), (20, 12));
+    assert_eq!(target.get_cells(), source.get_cells());
+
+    // A region stamps back where it came from.
+    let region = source.region_to_rle(2, 3, 5, 5).unwrap();
+    let mut target = Universe::with_size(20, 12, Some(FillMode::Clear));
+    target.insert_rle(2, 3, &region).unwrap();
+    source.clear_cell(0, 19).unwrap();
+    source.clear_cell(11, 0).unwrap();
+    assert_eq!(target.get_cells(), source.get_cells());
+
+    assert!(source.region_to_rle(10, 0, 3, 3).is_err());
+    assert!(source.region_to_rle(0, 18, 3, 3).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_plaintext_round_trips() {
+    let text = "!Name: Glider\n!\n.O\n..O\nOOO\n";
+    let glider = Universe::from_plaintext(text).unwrap();
+    assert_eq!((glider.width(), glider.height()), (3, 3));
+    assert_eq!(glider.population(), 5);
+    assert_eq!(input_spaceship().to_plaintext(), ".O\n..O\nOOO\n");
+
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    universe.insert_plaintext(1, 1, text).unwrap();
+    assert_eq!(universe.get_cells(), input_spaceship().get_cells());
+    assert!(Universe::from_plaintext("O.x\n").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_life106_round_trips() {
+    let text = "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n";
+    let glider = Universe::from_life106(text).unwrap();
+    assert_eq!((glider.width(), glider.height()), (3, 3));
+    assert_eq!(glider.population(), 5);
+
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    universe.insert_life106(2, 2, text).unwrap();
+    assert_eq!(universe.get_cells(), input_spaceship().get_cells());
+    assert_eq!(
+        universe.to_life106(),
+        "#Life 1.06\n2 1\n3 2\n1 3\n2 3\n3 3\n"
+    );
+    assert!(universe.insert_life106(0, 0, "1 2 3\n").is_err());
+    assert!(Universe::from_life106("0 0\n100000 100000\n").is_err());
+    assert!(Universe::from_life106("-2147483648 0\n2147483647 0\n").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_life105_round_trips() {
+    let text = "#Life 1.05\n#D Two gliders\n#R 23/36\n#P -1 -1\n.*\n..*\n***\n#P 4 3\n*\n.\n*\n";
+    let universe = Universe::from_life105(text).unwrap();
+    assert_eq!(universe.rule(), "B36/S23");
+    assert_eq!((universe.width(), universe.height()), (6, 7));
+    assert_eq!(universe.population(), 7);
+    assert_eq!(
+        universe.to_life105(),
+        "#Life 1.05\n#R 23/36\n#P 0 0\n.*\n..*\n***\n.\n.....*\n.\n.....*\n"
+    );
+    let again = Universe::from_life105(&universe.to_life105()).unwrap();
+    assert_eq!(again.get_cells(), universe.get_cells());
+
+    assert_eq!(
+        input_spaceship().to_life105(),
+        "#Life 1.05\n#N\n#P 1 1\n.*\n..*\n***\n"
+    );
+    assert!(Universe::from_life105("#P 0\n*\n").is_err());
+    assert!(Universe::from_life105("#P 0 0\n*\n#P 100000 100000\n*\n").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_macrocell_round_trips() {
+    // A glider in the north-east quadrant of a 16x16 root.
+    let text = "[M2] (golly 4.2)\n#R B36/S23\n#G 5\n.*$..*$***$$$$$$\n4 0 1 0 0\n";
+    let universe = Universe::from_macrocell(text).unwrap();
+    assert_eq!(universe.rule(), "B36/S23");
+    assert_eq!(universe.generation(), 5);
+    let glider = Universe::from_plaintext(".O\n..O\nOOO\n").unwrap();
+    assert_eq!(universe.get_cells(), glider.get_cells());
+
+    let again = Universe::from_macrocell(&input_spaceship().to_macrocell()).unwrap();
+    assert_eq!(again.get_cells(), glider.get_cells());
+
+    let sparse = SparseUniverse::from_macrocell(text).unwrap();
+    assert!(sparse.get(-8, 1) && sparse.get(-6, 0));
+    let mut far = SparseUniverse::new();
+    far.set(-1_000_000, 5, true);
+    far.set(3, 2_000_000, true);
+    let far = SparseUniverse::from_macrocell(&far.to_macrocell()).unwrap();
+    assert_eq!(far.population(), 2);
+    assert!(far.get(-1_000_000, 5) && far.get(3, 2_000_000));
+
+    assert!(Universe::from_macrocell("[M2]\n1 0 1 1 0\n").is_err());
+    assert!(Universe::from_macrocell("[M2]\n.*$\n5 1 0 0 0\n").is_err());
+
+    // Two cells 2^32 apart, whose bounding box is too large to count.
+    let mut far = String::from("[M2]\n*$\n");
+    for level in 4..=32 {
+        far.push_str(&format!("{} {} 0 0 0\n", level, level - 3));
+    }
+    far.push_str("33 30 0 0 30\n");
+    assert!(Universe::from_macrocell(&far).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_apgcode_round_trips() {
+    assert_eq!(input_spaceship().apgcode().as_deref(), Some("xq4_153"));
+
+    let mut universe = Universe::new();
+    universe.set_width(12);
+    universe.set_height(12);
+    universe.reset_clear();
+    universe.insert_plaintext(2, 2, "OO\nOO\n").unwrap();
+    assert_eq!(universe.apgcode().as_deref(), Some("xs4_33"));
+    universe.reset_clear();
+    universe.insert_plaintext(4, 3, "OOO\n").unwrap();
+    assert_eq!(universe.apgcode().as_deref(), Some("xp2_7"));
+    assert_eq!(universe.generation(), 0);
+    universe.reset_clear();
+    assert_eq!(universe.apgcode(), None);
+
+    let beehive = Universe::from_apgcode("xs6_696").unwrap();
+    assert_eq!(beehive.to_plaintext(), ".O\nO.O\nO.O\n.O\n");
+    let glider = Universe::from_apgcode("xq4_153").unwrap();
+    assert_eq!(glider.to_plaintext(), "OOO\n..O\n.O\n");
+    let gap = Universe::from_apgcode("xp2_1y11zx1").unwrap();
+    assert_eq!((gap.width(), gap.height()), (7, 6));
+
+    assert!(Universe::from_apgcode("ov_s16").is_err());
+    assert!(Universe::from_apgcode("xs4_3!").is_err());
+
+    // A short code can't ask for a board larger than a pattern may be.
+    let sprawling = format!("xs3_1{}1{}1", "yz".repeat(26), "z".repeat(4000));
+    assert!(Universe::from_apgcode(&sprawling).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_heatmap() {
+    let mut universe = Universe::new();
+    universe.set_width(5);
+    universe.set_height(5);
+    assert!(universe.heatmap().is_null());
+
+    // A blinker: the centre is alive every generation, the tips every other.
+    universe.set_heatmap_enabled(true);
+    universe.set_cells(&[(1, 2), (2, 2), (3, 2)]);
+    for _ in 0..3 {
+        universe.tick();
+    }
+    let heatmap = unsafe { std::slice::from_raw_parts(universe.heatmap(), 25) };
+    assert_eq!(heatmap[2 * 5 + 2], 3);
+    assert_eq!(heatmap[2 * 5 + 1], 2);
+    assert_eq!(heatmap[5 + 2], 1);
+    assert_eq!(heatmap[0], 0);
+
+    universe.clear_heatmap();
+    let heatmap = unsafe { std::slice::from_raw_parts(universe.heatmap(), 25) };
+    assert!(heatmap.iter().all(|&count| count == 0));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_to_pattern_round_trip() {
+    assert_eq!(
+        input_spaceship().to_pattern(),
+        "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+    );
+
+    let mut universe = Universe::new();
+    universe.set_width(40);
+    universe.set_height(40);
+    universe.insert_pulsar_at_pos(10, 10).unwrap();
+    universe.insert_glider_at_pos(30, 30).unwrap();
+    let rle = universe.to_pattern();
+    assert!(rle.lines().all(|line| line.len() <= 70));
+
+    let loaded = Universe::from_rle(&rle).unwrap();
+    assert_eq!(loaded.population(), universe.population());
+    assert_eq!(loaded.to_pattern(), rle);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_region_changed() {
+    let mut universe = Universe::new();
+    universe.set_width(40);
+    universe.set_height(10);
+    // A blinker near the left edge and a still block near the right.
+    universe.set_cells(&[(4, 3), (5, 3), (6, 3)]);
+    universe.set_cells(&[(4, 35), (4, 36), (5, 35), (5, 36)]);
+    universe.tick();
+
+    assert_eq!(universe.region_changed(0, 0, 40, 10), 4);
+    assert_eq!(universe.region_changed(4, 2, 3, 3), 4);
+    assert_eq!(universe.region_changed(0, 30, 100, 100), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_record_and_replay() {
+    let mut universe = Universe::new();
+    universe.set_width(30);
+    universe.set_height(30);
+    universe.insert_pulsar_at_pos(15, 15).unwrap();
+
+    universe.start_recording();
+    universe.tick();
+    universe.insert_glider_at_pos(3, 3).unwrap();
+    universe.toggle_cell(25, 25).unwrap();
+    universe.tick_with_injection(&[0, 1], &[1, 1]).unwrap();
+    for _ in 0..5 {
+        universe.tick();
+    }
+    let recording = universe.export_recording();
+
+    let mut replayed = Universe::new();
+    replayed.replay(&recording).unwrap();
+    assert_eq!(replayed.width(), 30);
+    assert_eq!(replayed.get_cells(), universe.get_cells());
+
+    // A size too big to index, or with no cells, is refused.
+    let header = |width: u32, height: u32, words: u32| {
+        let mut bytes = vec![1];
+        for value in [width, height, words].iter() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    };
+    assert!(replayed.replay(&header(65536, 65536, 0)).is_err());
+    assert!(replayed.replay(&header(0, 5, 0)).is_err());
+
+    // A flat board can't jump 2^30 generations, and failing there after a
+    // tick leaves the board as it was.
+    let mut bad = recording.clone();
+    bad.push(5);
+    bad.extend_from_slice(&30u32.to_le_bytes());
+    let mut target = Universe::with_size(8, 8, Some(FillMode::Clear));
+    target.set_cells(&[(1, 1)]);
+    assert!(target.replay(&bad).is_err());
+    assert_eq!(target.width(), 8);
+    assert_eq!(target.population(), 1);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_cell_lifespan() {
+    let mut universe = Universe::new();
+    universe.set_width(8);
+    universe.set_height(8);
+    universe.set_heatmap_enabled(true);
+    universe.set_cells(&[(3, 4), (4, 4), (5, 4)]);
+    universe.tick();
+    let heatmap_before = unsafe { std::slice::from_raw_parts(universe.heatmap(), 64) }.to_vec();
+    let oldest_before = universe.oldest_cell();
+
+    // The blinker's centre never dies; its tips die on the next tick.
+    assert_eq!(universe.cell_lifespan(4, 4, 10).unwrap(), 10);
+    assert_eq!(universe.cell_lifespan(4, 3, 10).unwrap(), 0);
+    assert_eq!(universe.cell_lifespan(0, 0, 10).unwrap(), 0);
+
+    let heatmap_after = unsafe { std::slice::from_raw_parts(universe.heatmap(), 64) };
+    assert_eq!(heatmap_after, &heatmap_before[..]);
+    assert_eq!(universe.oldest_cell(), oldest_before);
+    assert_eq!(universe.region_changed(0, 0, 8, 8), 4);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_row_populations() {
+    let mut out = [0; 6];
+    input_spaceship().row_populations(&mut out).unwrap();
+    assert_eq!(out, [0, 1, 1, 3, 0, 0]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_ghost_layer() {
+    let mut universe = Universe::new();
+    universe.set_width(4);
+    universe.set_height(3);
+    universe.set_cells(&[(0, 0)]);
+    universe.set_ghost(1, 2, &[1, 1], 2, 3).unwrap();
+
+    assert_eq!(universe.render_with_ghost(), "◼◻◻◻\n◻◻◻◻\n▣◻◻▣\n");
+    universe.tick();
+    assert_eq!(universe.population(), 0);
+
+    universe.clear_ghost();
+    assert_eq!(universe.render_with_ghost(), universe.render());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_spaceship_speed() {
+    let mut glider = Universe::new();
+    glider.set_width(20);
+    glider.set_height(20);
+    glider.set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+    let before = glider.get_cells();
+    assert_eq!(glider.spaceship_speed(), Some("c/4 diagonal".to_string()));
+    assert_eq!(glider.get_cells(), before);
+
+    let mut lwss = Universe::new();
+    lwss.set_width(30);
+    lwss.set_height(10);
+    lwss.set_cells(&[
+        (3, 11),
+        (3, 14),
+        (4, 10),
+        (5, 10),
+        (5, 14),
+        (6, 10),
+        (6, 11),
+        (6, 12),
+        (6, 13),
+    ]);
+    assert_eq!(lwss.spaceship_speed(), Some("c/2 orthogonal".to_string()));
+
+    let mut blinker = Universe::new();
+    blinker.set_width(8);
+    blinker.set_height(8);
+    blinker.set_cells(&[(3, 4), (4, 4), (5, 4)]);
+    assert_eq!(blinker.spaceship_speed(), None);
+
+    // A glider straddling the edges of a small torus still moves c/4.
+    let glider_cells = [(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)];
+    for offset in 0..8 {
+        let mut glider = Universe::with_size(8, 8, Some(FillMode::Clear));
+        let cells: Vec<(u32, u32)> = glider_cells
+            .iter()
+            .map(|&(row, col)| ((row + offset) % 8, (col + offset) % 8))
+            .collect();
+        glider.set_cells(&cells);
+        assert_eq!(glider.spaceship_speed(), Some("c/4 diagonal".to_string()));
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_chunked_serialization_round_trip() {
+    let mut source = Universe::new();
+    source.set_width(50);
+    source.set_height(40);
+    source.insert_pulsar_at_pos(20, 25).unwrap();
+    source.insert_glider_at_pos(5, 5).unwrap();
+
+    // 63 words in chunks of 10 gives 7 chunks, the last one short.
+    let header = source.serialize_header();
+    let mut target = Universe::new();
+    target.deserialize_begin(&header).unwrap();
+    for chunk_index in 0..7 {
+        let chunk = source.serialize_chunk(chunk_index, 10);
+        target.deserialize_chunk(&chunk).unwrap();
+    }
+    assert!(source.serialize_chunk(7, 10).is_empty());
+    target.deserialize_finish().unwrap();
+
+    assert_eq!(target.width(), 50);
+    assert_eq!(target.height(), 40);
+    assert_eq!(target.get_cells(), source.get_cells());
+
+    // A header for a huge board takes no room until its words arrive, and
+    // one whose size overflows is turned down.
+    let header = |width: u32, height: u32, words: u32| {
+        let mut header = b"GOLC\x01".to_vec();
+        for value in [width, height, words] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        header
+    };
+    let huge = 65535u32 * 65535;
+    target
+        .deserialize_begin(&header(65535, 65535, huge.div_ceil(32)))
+        .unwrap();
+    target.deserialize_chunk(&[0; 8]).unwrap();
+    assert!(target.deserialize_finish().is_err());
+    assert_eq!(target.width(), 50);
+    assert!(target.deserialize_begin(&header(70000, 70000, 0)).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_snapshots() {
+    let mut universe = input_spaceship();
+    universe.save_snapshot("start");
+    let start = universe.state_hash();
+    universe.tick();
+    universe.save_snapshot("ticked");
+    let ticked = universe.state_hash();
+    assert_eq!(universe.list_snapshots(), vec!["start", "ticked"]);
+
+    // Size and rule come back along with the cells.
+    universe.set_width(10);
+    universe.set_rule("B2/S/C3").unwrap();
+    universe.restore_snapshot("start").unwrap();
+    assert_eq!(universe.state_hash(), start);
+    assert_eq!(universe.generation(), 0);
+    assert_eq!(universe.rule(), "B3/S23");
+    universe.tick();
+    assert_eq!(universe.state_hash(), ticked);
+
+    // Snapshots survive restoring, and are shared with clones.
+    let clone = universe.clone();
+    universe.restore_snapshot("start").unwrap();
+    universe.restore_snapshot("start").unwrap();
+    assert_eq!(universe.state_hash(), start);
+    assert_eq!(clone.list_snapshots().len(), 2);
+
+    assert!(universe.delete_snapshot("start"));
+    assert!(!universe.delete_snapshot("start"));
+    assert!(universe.restore_snapshot("start").is_err());
+    assert_eq!(universe.list_snapshots(), vec!["ticked"]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_undo_redo() {
+    let mut universe = Universe::with_size(16, 16, Some(FillMode::Clear));
+    assert!(!universe.undo());
+
+    universe.toggle_cell(1, 1).unwrap();
+    universe.insert_glider_at_pos(8, 8).unwrap();
+    let drawn = universe.get_cells_bytes();
+    universe.fill_region(0, 0, 4, 4, true);
+    assert_eq!(universe.population(), 16 + 5);
+    universe.reset_clear();
+    assert_eq!(universe.population(), 0);
+
+    assert!(universe.undo());
+    assert_eq!(universe.population(), 16 + 5);
+    assert!(universe.undo());
+    assert_eq!(universe.get_cells_bytes(), drawn);
+    assert!(universe.redo());
+    assert_eq!(universe.population(), 16 + 5);
+    assert!(universe.undo());
+    assert!(universe.undo());
+    assert!(universe.undo());
+    assert_eq!(universe.population(), 0);
+    assert!(!universe.can_undo());
+
+    // A new edit forgets what was undone.
+    universe.toggle_cell(2, 2).unwrap();
+    assert!(!universe.can_redo());
+
+    // Only the oldest edits past the depth are dropped.
+    universe.set_undo_depth(2);
+    for col in 3..8 {
+        universe.toggle_cell(0, col).unwrap();
+    }
+    assert!(universe.undo());
+    assert!(universe.undo());
+    assert!(!universe.undo());
+    assert_eq!(universe.population(), 1 + 3);
+
+    universe.set_undo_depth(0);
+    universe.toggle_cell(5, 5).unwrap();
+    assert!(!universe.can_undo());
+
+    // Resizing forgets edits that no longer line up with the board.
+    universe.set_undo_depth(10);
+    universe.toggle_cell(5, 6).unwrap();
+    universe.set_width(20);
+    assert!(!universe.can_undo());
+
+    // Undo and redo are recorded as the cells they set, and replaying
+    // leaves nothing to undo.
+    universe.start_recording();
+    universe.insert_glider_at_pos(5, 5).unwrap();
+    universe.toggle_cell(0, 0).unwrap();
+    universe.undo();
+    universe.undo();
+    universe.redo();
+    let recording = universe.export_recording();
+    let mut replayed = Universe::with_size(4, 4, Some(FillMode::Clear));
+    replayed.replay(&recording).unwrap();
+    assert_eq!(replayed.get_cells(), universe.get_cells());
+    assert!(!replayed.can_undo());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_step_back() {
+    let mut universe = input_spaceship();
+    universe.tick();
+    assert!(!universe.step_back());
+    assert_eq!(universe.history_bytes(), 0);
+
+    universe.set_history_depth(3);
+    let hashes: Vec<u64> = (0..5)
+        .map(|_| {
+            let hash = universe.state_hash();
+            universe.tick();
+            hash
+        })
+        .collect();
+    assert_eq!(universe.history_len(), 3);
+    assert!(universe.history_bytes() > 0);
+    for &hash in hashes[2..].iter().rev() {
+        assert!(universe.step_back());
+        assert_eq!(universe.state_hash(), hash);
+    }
+    assert_eq!(universe.generation(), 3);
+    assert!(!universe.step_back());
+
+    // Dying states rewind with the cells.
+    let mut brain = Universe::brians_brain(32, 32, 7);
+    brain.set_history_depth(1);
+    let before = brain.state_hash();
+    brain.tick();
+    assert!(brain.step_back());
+    assert_eq!(brain.state_hash(), before);
+
+    // Stepping back across a change of rule brings the old rule back with
+    // the states it had.
+    let mut generations = Universe::brians_brain(32, 32, 7);
+    generations.set_rule("B2/S/C8").unwrap();
+    generations.set_history_depth(5);
+    for _ in 0..3 {
+        generations.tick();
+    }
+    let before = generations.state_hash();
+    generations.tick();
+    generations.set_rule("B2/S/C3").unwrap();
+    assert!(generations.step_back());
+    assert_eq!(generations.rule(), "B2/S/C8");
+    assert_eq!(generations.state_hash(), before);
+    generations.tick();
+
+    universe.tick();
+    universe.set_history_depth(0);
+    assert!(!universe.step_back());
+    assert_eq!(universe.history_bytes(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_history_memory_budget() {
+    let mut universe = Universe::with_size(64, 64, Some(FillMode::Clear));
+    assert_eq!(universe.history_memory_budget(), usize::MAX);
+    universe.set_history_depth(10);
+    universe.toggle_cell(1, 1).unwrap();
+    for _ in 0..5 {
+        universe.tick();
+    }
+    for name in 0..20 {
+        universe.save_snapshot(&name.to_string());
+    }
+    let used = universe.history_memory_used();
+    assert!(used > 0);
+
+    // Undo goes first, then step-back frames, then the earliest snapshots.
+    universe.set_history_memory_budget(used / 2);
+    assert!(universe.history_memory_used() <= used / 2);
+    assert!(!universe.can_undo());
+    assert_eq!(universe.history_len(), 0);
+    let kept = universe.list_snapshots();
+    assert!(!kept.is_empty() && kept.len() < 20);
+    assert!(kept.contains(&"19".to_string()));
+    assert!(!kept.contains(&"0".to_string()));
+
+    // The cap holds as more is saved.
+    for name in 20..40 {
+        universe.save_snapshot(&name.to_string());
+        universe.tick();
+        assert!(universe.history_memory_used() <= used / 2);
+    }
+    assert!(universe.list_snapshots().contains(&"39".to_string()));
+
+    universe.set_history_memory_budget(usize::MAX);
+    universe.toggle_cell(2, 2).unwrap();
+    assert!(universe.can_undo());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_bytes_round_trip() {
+    let mut source = Universe::with_size(50, 40, Some(FillMode::Clear));
+    source.set_rule("B2/S/C3").unwrap();
+    source.insert_glider_at_pos(5, 5).unwrap();
+    source.tick_n(3);
+    let bytes = source.to_bytes().unwrap();
+    let target = Universe::from_bytes(&bytes).unwrap();
+    assert_eq!((target.width(), target.height()), (50, 40));
+    assert_eq!(target.rule(), "B2/S/C3");
+    assert_eq!(target.generation(), 3);
+    assert_eq!(target.cell_states(), source.cell_states());
+
+    assert!(Universe::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    let mut extra = bytes.clone();
+    extra.push(0);
+    assert!(Universe::from_bytes(&extra).is_err());
+    let mut wrong_version = bytes.clone();
+    wrong_version[4] = 9;
+    assert!(Universe::from_bytes(&wrong_version).is_err());
+    assert!(Universe::from_bytes(&source.serialize_header()).is_err());
+
+    // Grains, creatures and colours come back with the cells.
+    let mut sandpile = Universe::with_size(8, 8, Some(FillMode::Clear));
+    sandpile.set_rule("Sandpile").unwrap();
+    sandpile.drop_sand(3, 3, 100).unwrap();
+    sandpile.tick_n(4);
+    let target = Universe::from_bytes(&sandpile.to_bytes().unwrap()).unwrap();
+    assert_eq!(target.grains(), sandpile.grains());
+
+    let mut sea = Universe::with_size(16, 16, Some(FillMode::Clear));
+    sea.set_rule("WaTor,FB3,SB8,E4,S42").unwrap();
+    sea.fill_sea(0.3, 0.05, 1).unwrap();
+    sea.tick_n(5);
+    let mut target = Universe::from_bytes(&sea.to_bytes().unwrap()).unwrap();
+    assert_eq!(target.shark_energy(), sea.shark_energy());
+    sea.tick_n(5);
+    target.tick_n(5);
+    assert_eq!(target.cell_states(), sea.cell_states());
+
+    let mut quadlife = Universe::with_size(8, 8, Some(FillMode::Clear));
+    quadlife.set_rule("QuadLife").unwrap();
+    quadlife.set_cell_color(2, 2, 1).unwrap();
+    quadlife.set_cell_color(2, 3, 4).unwrap();
+    let mut bytes = quadlife.to_bytes().unwrap();
+    let target = Universe::from_bytes(&bytes).unwrap();
+    assert_eq!(target.cell_colors(), quadlife.cell_colors());
+    let last = bytes.len() - 1;
+    bytes[last] = 5;
+    assert!(Universe::from_bytes(&bytes).is_err());
+
+    // Rule files can't be written out, so they aren't saved by name.
+    let mut loaded = Universe::with_size(8, 8, Some(FillMode::Clear));
+    loaded
+        .load_rule_file("@RULE Still\n@TABLE\nn_states:2\nneighborhood:vonNeumann\n")
+        .unwrap();
+    assert!(loaded.to_bytes().is_err());
+    assert!(loaded.to_share_string().is_err());
+
+    // Boards with no cells read back as they were saved.
+    for &(width, height) in [(0, 0), (0, 5), (5, 0)].iter() {
+        let mut empty = Universe::with_size(width, height, Some(FillMode::Clear));
+        empty.set_rule("B3/S23/C4").unwrap();
+        let target = Universe::from_bytes(&empty.to_bytes().unwrap()).unwrap();
+        assert_eq!((target.width(), target.height()), (width, height));
+        assert_eq!(target.rule(), "B3/S23/C4");
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_share_string_round_trip() {
+    let mut source = Universe::with_size(64, 64, Some(FillMode::Clear));
+    source.insert_glider_at_pos(10, 20).unwrap();
+    source.tick_n(5);
+    let shared = source.to_share_string().unwrap();
+    assert!(shared.len() < 100, "{} characters", shared.len());
+    assert!(shared
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    let target = Universe::from_share_string(&shared).unwrap();
+    assert_eq!(target.get_cells(), source.get_cells());
+    assert_eq!(target.generation(), 5);
+
+    let random = Universe::with_size(7, 5, Some(FillMode::Random));
+    let target = Universe::from_share_string(&random.to_share_string().unwrap()).unwrap();
+    assert_eq!(target.get_cells(), random.get_cells());
+
+    assert!(Universe::from_share_string(&shared[..shared.len() - 2]).is_err());
+    assert!(Universe::from_share_string("R09M!").is_err());
+
+    for &(width, height) in [(0, 0), (0, 5), (5, 0)].iter() {
+        let empty = Universe::with_size(width, height, Some(FillMode::Clear));
+        let target = Universe::from_share_string(&empty.to_share_string().unwrap()).unwrap();
+        assert_eq!((target.width(), target.height()), (width, height));
+    }
+}
+
+#[cfg(feature = "serde")]
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_json_round_trip() {
+    let mut source = Universe::with_size(6, 4, Some(FillMode::Clear));
+    source.set_rule("B2/S/C3").unwrap();
+    source.set_boundary_mode(BoundaryMode::Bounded);
+    source.set_neighborhood(NeighborhoodKind::VonNeumann);
+    source.set_cells(&[(1, 1), (1, 2), (2, 4)]);
+    source.tick();
+    let json = source.to_json();
+    assert!(json.contains("\"boundary\": \"Bounded\""));
+    let target = Universe::from_json(&json).unwrap();
+    assert_eq!(target.to_json(), json);
+    assert_eq!(target.cell_states(), source.cell_states());
+    assert_eq!(target.neighborhood(), NeighborhoodKind::VonNeumann);
+
+    assert!(Universe::from_json(&json.replace("\"width\": 6", "\"width\": 5")).is_err());
+    assert!(Universe::from_json("{}").is_err());
+
+    // A size the cells don't fill is turned down before any board is made.
+    let huge = json
+        .replace("\"width\": 6", "\"width\": 60000")
+        .replace("\"height\": 4", "\"height\": 60000");
+    assert!(Universe::from_json(&huge).is_err());
+
+    for &(width, height) in [(0, 0), (0, 5), (5, 0)].iter() {
+        let empty = Universe::with_size(width, height, Some(FillMode::Clear));
+        let json = empty.to_json();
+        assert_eq!(Universe::from_json(&json).unwrap().to_json(), json);
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_preview_next() {
+    let mut universe = Universe::new();
+    universe.set_width(5);
+    universe.set_height(5);
+    universe.set_cells(&[(1, 2), (2, 2), (3, 2)]);
+
+    #[rustfmt::skip]
+    let expected = vec![
+        0, 0, 0, 0, 0,
+        0, 0, 2, 0, 0,
+        0, 1, 0, 1, 0,
+        0, 0, 2, 0, 0,
+        0, 0, 0, 0, 0,
+    ];
+    assert_eq!(universe.preview_next(), expected);
+    assert_eq!(universe.population(), 3);
+    assert_eq!(universe.get_cells()[5 + 2], Cell::Alive);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_reset_random_quadrants() {
+    let mut universe = Universe::new();
+    universe.set_width(9);
+    universe.set_height(7);
+    universe
+        .reset_random_quadrants(&[1.0, 0.0, -3.0, 2.0], 1)
+        .unwrap();
+
+    // Rows 0..3 and columns 0..4 form the top-left quadrant.
+    let mut rows = [0; 7];
+    universe.row_populations(&mut rows).unwrap();
+    assert_eq!(rows, [4, 4, 4, 5, 5, 5, 5]);
+    assert_eq!(universe.get_cells()[3], Cell::Alive);
+    assert_eq!(universe.get_cells()[4], Cell::Dead);
+
+    let mut again = Universe::new();
+    again.set_width(9);
+    again.set_height(7);
+    let densities = [0.3, 0.5, 0.7, 0.9];
+    universe.reset_random_quadrants(&densities, 42).unwrap();
+    again.reset_random_quadrants(&densities, 42).unwrap();
+    assert_eq!(universe.get_cells(), again.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_state_counts() {
+    assert_eq!(input_spaceship().state_counts(), vec![31, 5]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_activity_radius() {
+    let mut universe = Universe::new();
+    universe.set_width(10);
+    universe.set_height(10);
+    assert_eq!(universe.activity_radius(), 0.0);
+
+    // A block straddling the corner is centred on the wrap-around point.
+    universe.set_cells(&[(0, 0), (0, 9), (9, 0), (9, 9)]);
+    assert!((universe.activity_radius() - 0.5f64.hypot(0.5)).abs() < 1e-9);
+
+    universe.set_boundary_mode(BoundaryMode::Bounded);
+    assert!((universe.activity_radius() - 4.5f64.hypot(4.5)).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_blob_round_trip() {
+    let blob = input_spaceship().extract_blob();
+    assert_eq!(blob, "AwPiAQ");
+
+    let mut universe = Universe::new();
+    universe.set_width(6);
+    universe.set_height(6);
+    universe.insert_blob(&blob, 1, 1).unwrap();
+    assert_eq!(universe.get_cells(), input_spaceship().get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_until_hash() {
+    let mut universe = Universe::new();
+    universe.set_width(8);
+    universe.set_height(8);
+    universe.set_cells(&[(3, 4), (4, 4), (5, 4)]);
+    let vertical = universe.state_hash();
+    universe.tick();
+    let horizontal = universe.state_hash();
+    assert_ne!(vertical, horizontal);
+
+    assert_eq!(universe.tick_until_hash(horizontal, 5), Some(1));
+    assert_eq!(universe.tick_until_hash(vertical, 5), Some(2));
+    assert_eq!(universe.generation(), 2);
+    assert_eq!(universe.state_hash(), vertical);
+
+    assert_eq!(universe.tick_until_hash(0, 3), None);
+    assert_eq!(universe.generation(), 5);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_state_hash_is_stable() {
+    // Pinned so any change to the hash, which callers may have stored,
+    // shows up here.
+    assert_eq!(input_spaceship().state_hash(), 8624492124276253554);
+
+    // Under Generations rules dying cells count too.
+    let mut dying = Universe::with_size(6, 6, Some(FillMode::Clear));
+    dying.set_rule("B2/S/C3").unwrap();
+    let empty = dying.state_hash();
+    dying.set_cell_state(2, 2, 2).unwrap();
+    assert_ne!(dying.state_hash(), empty);
+    assert_eq!(dying.population(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_with_size() {
+    let universe = Universe::with_size(30, 20, None);
+    assert_eq!(universe.width(), 30);
+    assert_eq!(universe.height(), 20);
+    let cells = universe.get_cells();
+    assert_eq!(cells.len(), 600);
+    assert_eq!(cells[14], Cell::Alive);
+    assert_eq!(cells[15], Cell::Dead);
+
+    let clear = Universe::with_size(30, 20, Some(FillMode::Clear));
+    assert_eq!(clear.population(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_from_cells_and_words() {
+    #[rustfmt::skip]
+    let cells = [
+        0, 1, 0,
+        0, 0, 1,
+        1, 1, 1,
+    ];
+    let universe = Universe::from_cells(3, 3, &cells).unwrap();
+    assert_eq!(
+        universe.to_pattern(),
+        "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+    );
+
+    let words = [0b1_1110_0010];
+    let packed = Universe::from_words(3, 3, &words).unwrap();
+    assert_eq!(packed.get_cells(), universe.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_get_cell() {
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    universe.set_cells(&[(1, 2), (2, 3), (5, 5)]);
+    assert_eq!(universe.get_cell(1, 2).unwrap(), Cell::Alive);
+    assert_eq!(universe.get_cell(2, 2).unwrap(), Cell::Dead);
+
+    assert_eq!(universe.get_cells_in_rect(1, 2, 2, 2), vec![1, 0, 0, 1]);
+    assert_eq!(universe.get_cells_in_rect(4, 4, 5, 5), vec![0, 0, 0, 1]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_set_cell() {
+    let mut universe = Universe::with_size(4, 4, Some(FillMode::Clear));
+    universe.set_cell(1, 1, Cell::Alive).unwrap();
+    universe.set_cell(1, 1, Cell::Alive).unwrap();
+    assert_eq!(universe.get_cell(1, 1).unwrap(), Cell::Alive);
+
+    universe.clear_cell(1, 1).unwrap();
+    universe.clear_cell(1, 1).unwrap();
+    assert_eq!(universe.get_cell(1, 1).unwrap(), Cell::Dead);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_set_cells_flat() {
+    let mut flat = Universe::with_size(6, 6, Some(FillMode::Clear));
+    flat.set_cells_flat(&[1, 2, 2, 3, 3, 1, 3, 2, 3, 3])
+        .unwrap();
+
+    let mut pairs = Universe::with_size(6, 6, Some(FillMode::Clear));
+    pairs.set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+    assert_eq!(flat.get_cells(), pairs.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_get_cells_bytes() {
+    let universe = input_spaceship();
+    let bytes = universe.get_cells_bytes();
+    let cells = universe.get_cells();
+    assert_eq!(bytes.len(), cells.len());
+    for (byte, cell) in bytes.iter().zip(&cells) {
+        assert_eq!(*byte, *cell as u8);
+    }
+
+    let copy = Universe::from_cells(6, 6, &bytes).unwrap();
+    assert_eq!(copy.get_cells(), cells);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_resize() {
+    let mut universe = Universe::with_size(4, 4, Some(FillMode::Clear));
+    universe.set_cells(&[(0, 0), (3, 3)]);
+
+    universe.resize(6, 5, ResizeMode::PreserveTopLeft);
+    assert_eq!((universe.width(), universe.height()), (6, 5));
+    assert_eq!(universe.get_cell(0, 0).unwrap(), Cell::Alive);
+    assert_eq!(universe.get_cell(3, 3).unwrap(), Cell::Alive);
+    assert_eq!(universe.population(), 2);
+
+    universe.resize(8, 8, ResizeMode::PreserveCentered);
+    assert_eq!(universe.get_cell(1, 1).unwrap(), Cell::Alive);
+    assert_eq!(universe.get_cell(4, 4).unwrap(), Cell::Alive);
+
+    universe.resize(3, 3, ResizeMode::PreserveCentered);
+    assert_eq!(universe.get_cells_bytes(), vec![0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    universe.tick();
+
+    universe.resize(3, 3, ResizeMode::Clear);
+    assert_eq!(universe.population(), 0);
+    assert_eq!(universe.generation(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_coordinates_rejects_off_board() {
+    let mut universe = Universe::with_size(4, 3, Some(FillMode::Clear));
+    assert!(universe.toggle_cell(3, 0).is_err());
+    assert!(universe.set_cell(0, 4, Cell::Alive).is_err());
+    assert!(universe.get_cell(3, 3).is_err());
+    assert!(universe.insert_glider_at_pos(5, 5).is_err());
+    assert!(universe.set_cells_flat(&[0, 0, 9, 9]).is_err());
+    assert_eq!(universe.population(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_builder() {
+    let universe = Universe::builder()
+        .width(40)
+        .height(30)
+        .fill(FillMode::Random)
+        .density(0.3)
+        .seed(42)
+        .boundary_mode(BoundaryMode::Bounded)
+        .frozen_border(2)
+        .build();
+    assert_eq!((universe.width(), universe.height()), (40, 30));
+    assert_eq!(universe.boundary_mode(), BoundaryMode::Bounded);
+    assert_eq!(universe.get_cells_in_rect(0, 0, 40, 2), vec![0; 80]);
+    let population = universe.population();
+    assert!(population > 200 && population < 400);
+
+    let again = UniverseBuilder::new()
+        .width(40)
+        .height(30)
+        .fill(FillMode::Random)
+        .density(0.3)
+        .seed(42)
+        .boundary_mode(BoundaryMode::Bounded)
+        .frozen_border(2)
+        .build();
+    assert_eq!(again.get_cells(), universe.get_cells());
+
+    let filled = Universe::builder()
+        .width(5)
+        .height(5)
+        .fill(FillMode::Clear)
+        .background(true)
+        .build();
+    assert_eq!(filled.population(), 25);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_duplicate() {
+    let mut universe = input_spaceship();
+    universe.set_boundary_mode(BoundaryMode::Bounded);
+    universe.tick();
+
+    let mut branch = universe.duplicate();
+    assert_eq!(branch.generation(), 1);
+    assert_eq!(branch.boundary_mode(), BoundaryMode::Bounded);
+    assert_eq!(branch.get_cells(), universe.get_cells());
+
+    branch.toggle_cell(0, 0).unwrap();
+    branch.tick();
+    assert_eq!(universe.generation(), 1);
+    assert_eq!(universe.get_cell(0, 0).unwrap(), Cell::Dead);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_set_rule() {
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    assert_eq!(universe.rule(), "B3/S23");
+    for notation in ["b63/s32", "S23/B36", "23/36"].iter() {
+        universe.set_rule(notation).unwrap();
+        assert_eq!(universe.rule(), "B36/S23");
+    }
+
+    // Seeds: every live cell dies and a pair gives birth on either side.
+    universe.set_rule("B2/S").unwrap();
+    universe.set_cells(&[(2, 2), (2, 3)]);
+    universe.tick();
+    assert_eq!(
+        universe.get_cells_in_rect(1, 2, 2, 3),
+        vec![1, 1, 0, 0, 1, 1]
+    );
+
+    let highlife = Universe::from_rle("x = 3, y = 1, rule = B36/S23\n3o!").unwrap();
+    assert_eq!(highlife.rule(), "B36/S23");
+    assert_eq!(highlife.to_pattern(), "x = 3, y = 1, rule = B36/S23\n3o!\n");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_set_rule_rejects_bad_notation() {
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    assert!(universe.set_rule("B3/S23/C1").is_err());
+    assert!(universe.set_rule("B3x/S23").is_err());
+    assert!(universe.set_rule("B33/S23").is_err());
+    assert!(universe.set_rule("B3").is_err());
+    assert!(Universe::from_rle("x = 1, y = 1, rule = foo\no!").is_err());
+    assert_eq!(universe.rule(), "B3/S23");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_rule_presets() {
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    assert_eq!(universe.rule_preset(), Some(Rule::Conway));
+
+    universe.set_rule_preset(Rule::DayAndNight);
+    assert_eq!(universe.rule(), "B3678/S34678");
+    assert_eq!(universe.rule_preset(), Some(Rule::DayAndNight));
+
+    universe.set_rule("lifewithoutdeath").unwrap();
+    assert_eq!(universe.rule(), "B3/S012345678");
+    assert_eq!(universe.rule_preset(), Some(Rule::LifeWithoutDeath));
+
+    universe.set_rule("B1/S1").unwrap();
+    assert_eq!(universe.rule_preset(), None);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_generations_rule() {
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    universe.set_rule("/2/3").unwrap();
+    assert_eq!(universe.rule(), "B2/S/C3");
+    universe.set_cells(&[(2, 2), (2, 3)]);
+
+    // Brian's Brain: the pair starts dying while new cells are born
+    // either side of it.
+    universe.tick();
+    assert_eq!(
+        universe.cell_states()[6..24],
+        [0, 0, 1, 1, 0, 0, 0, 0, 2, 2, 0, 0, 0, 0, 1, 1, 0, 0]
+    );
+    assert_eq!(universe.population(), 4);
+
+    // Dying cells don't count as neighbours and can't be born again.
+    universe.tick();
+    assert_eq!(universe.cell_states()[14..16], [0, 0]);
+    assert_eq!(universe.cell_states()[8..10], [2, 2]);
+
+    universe.set_rule("B2/S345/G4").unwrap();
+    assert_eq!(universe.rule(), "B2/S345/C4");
+    universe.set_rule_preset(Rule::Conway);
+    assert_eq!(universe.cell_states().iter().max(), Some(&1));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_larger_than_life_rule() {
+    // Radius-one Larger than Life with these ranges is Conway's Life.
+    for rule in ["R1,C0,M0,S2..3,B3..3,NM", "r1,c2,m1,s3..4,b3..3,nm"].iter() {
+        let mut ltl = input_spaceship();
+        ltl.set_rule(rule).unwrap();
+        let mut life = input_spaceship();
+        for _ in 0..8 {
+            ltl.tick();
+            life.tick();
+            assert_eq!(ltl.get_cells(), life.get_cells());
+        }
+    }
+
+    let mut universe = Universe::with_size(9, 9, Some(FillMode::Clear));
+    universe.set_rule("R2,C0,M0,S1..1,B1..1,NN").unwrap();
+    assert_eq!(universe.rule(), "R2,C0,M0,S1..1,B1..1,NN");
+    universe.set_cells(&[(4, 4)]);
+    universe.tick();
+    #[rustfmt::skip]
+    assert_eq!(universe.get_cells_in_rect(2, 2, 5, 5), vec![
+        0, 0, 1, 0, 0,
+        0, 1, 1, 1, 0,
+        1, 1, 0, 1, 1,
+        0, 1, 1, 1, 0,
+        0, 0, 1, 0, 0,
+    ]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_isotropic_rule() {
+    // Spelling out every letter gives back Conway's Life.
+    let mut isotropic = input_spaceship();
+    isotropic
+        .set_rule("B3ceaiknjqry/S2ceaikn3ceaiknjqry")
+        .unwrap();
+    assert_eq!(isotropic.rule(), "B3/S23");
+    let mut life = input_spaceship();
+    for _ in 0..8 {
+        isotropic.tick();
+        life.tick();
+        assert_eq!(isotropic.get_cells(), life.get_cells());
+    }
+
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    universe.set_rule("b3/s2-i34q").unwrap();
+    assert_eq!(universe.rule(), "B3/S2-i34q");
+
+    // Only cells with two orthogonally adjacent live edge neighbours are
+    // born.
+    universe.set_rule("B2e/S").unwrap();
+    universe.set_cells(&[(1, 2), (2, 3)]);
+    universe.tick();
+    assert_eq!(universe.population(), 2);
+    assert_eq!(universe.get_cell(2, 2).unwrap(), Cell::Alive);
+    assert_eq!(universe.get_cell(1, 3).unwrap(), Cell::Alive);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_transition_table() {
+    let conway: Vec<u8> = (0..512u32)
+        .map(|mask| {
+            let alive = mask & 1 << 4 != 0;
+            let neighbours = (mask & !(1 << 4)).count_ones();
+            (neighbours == 3 || alive && neighbours == 2) as u8
+        })
+        .collect();
+    let mut table = input_spaceship();
+    table.set_transition_table(&conway).unwrap();
+    let mut life = input_spaceship();
+    for _ in 0..8 {
+        table.tick();
+        life.tick();
+        assert_eq!(table.get_cells(), life.get_cells());
+    }
+
+    let rule = table.rule();
+    assert!(rule.starts_with("table:"));
+    let mut copy = Universe::with_size(4, 4, Some(FillMode::Clear));
+    copy.set_rule(&rule).unwrap();
+    assert_eq!(copy.rule(), rule);
+
+    // Shift everything one cell to the right: alive exactly when the west
+    // neighbour is.
+    let shift: Vec<u8> = (0..512u32).map(|mask| (mask >> 3 & 1) as u8).collect();
+    let mut universe = Universe::with_size(4, 4, Some(FillMode::Clear));
+    universe.set_transition_table(&shift).unwrap();
+    universe.set_cells(&[(1, 3)]);
+    universe.tick();
+    assert_eq!(universe.get_cells_bytes()[4..8], [1, 0, 0, 0]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_stochastic_rule() {
+    // A blinker whose births never happen: the middle survives on its
+    // two neighbours and the ends die, leaving a single cell.
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    universe.set_cells(&[(2, 1), (2, 2), (2, 3)]);
+    universe.set_noise_seed(1);
+    universe.set_birth_probability(3, 0.0).unwrap();
+    assert!(universe.is_stochastic());
+    universe.tick();
+    assert_eq!(universe.population(), 1);
+
+    // The same seed gives the same run.
+    let run = |seed: u64| {
+        let mut universe = Universe::with_size(32, 32, Some(FillMode::Pattern));
+        universe.set_noise_seed(seed);
+        universe.set_birth_probability(3, 0.9).unwrap();
+        universe.set_survival_probability(2, 0.9).unwrap();
+        for _ in 0..10 {
+            universe.tick();
+        }
+        universe.get_cells()
+    };
+    assert_eq!(run(7), run(7));
+    assert_ne!(run(7), run(8));
+
+    let mut universe = input_spaceship();
+    universe.set_noise_seed(3);
+    universe.clear_noise();
+    assert!(!universe.is_stochastic());
+    universe.tick();
+    assert_eq!(universe.get_cells(), expected_spaceship().get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_margolus_rule() {
+    // A lone billiard ball travels diagonally, one block per generation.
+    let mut universe = Universe::with_size(8, 8, Some(FillMode::Clear));
+    universe.set_rule("BBM").unwrap();
+    assert_eq!(universe.rule(), "M0,8,4,3,2,5,9,7,1,6,10,11,12,13,14,15");
+    universe.set_cells(&[(0, 0)]);
+    for step in 1..=3 {
+        universe.tick();
+        let mut expected = Universe::with_size(8, 8, Some(FillMode::Clear));
+        expected.set_cells(&[(step, step)]);
+        assert_eq!(universe.get_cells(), expected.get_cells());
+    }
+
+    // Critters inverts empty and full blocks, so a blank board flashes.
+    let mut universe = Universe::with_size(8, 8, Some(FillMode::Clear));
+    universe.set_rule("critters").unwrap();
+    universe.tick();
+    assert_eq!(universe.population(), 64);
+    universe.tick();
+    assert_eq!(universe.population(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_neighborhood() {
+    // Under B2/S with von Neumann neighbours only the cell between two
+    // live cells in a column is born: cells beside them see at most one.
+    let mut universe = Universe::with_size(7, 7, Some(FillMode::Clear));
+    universe.set_rule("B2/S").unwrap();
+    universe.set_neighborhood(NeighborhoodKind::VonNeumann);
+    assert_eq!(universe.neighborhood(), NeighborhoodKind::VonNeumann);
+    universe.set_cells(&[(3, 3)]);
+    universe.tick();
+    assert_eq!(universe.population(), 0);
+    universe.set_cells(&[(2, 3), (4, 3)]);
+    universe.tick();
+    let mut expected = Universe::with_size(7, 7, Some(FillMode::Clear));
+    expected.set_cells(&[(3, 3)]);
+    assert_eq!(universe.get_cells(), expected.get_cells());
+
+    // The radius-2 neighbourhood reaches two cells along each axis.
+    let mut universe = Universe::with_size(7, 7, Some(FillMode::Clear));
+    universe.set_rule("B1/S").unwrap();
+    universe.set_neighborhood(NeighborhoodKind::VonNeumannRadius2);
+    universe.set_cells(&[(3, 3)]);
+    universe.tick();
+    assert_eq!(universe.population(), 12);
+
+    // Twelve live radius-2 neighbours, or thirteen counting the cell
+    // itself, are more than any B/S digit, so nothing is born or survives.
+    let mut universe = Universe::with_size(7, 7, Some(FillMode::Clear));
+    universe.set_neighborhood(NeighborhoodKind::VonNeumannRadius2);
+    let ring = [
+        (1, 3),
+        (2, 2),
+        (2, 3),
+        (2, 4),
+        (3, 1),
+        (3, 2),
+        (3, 4),
+        (3, 5),
+        (4, 2),
+        (4, 3),
+        (4, 4),
+        (5, 3),
+    ];
+    universe.set_cells(&ring);
+    let centre = (3 * 7 + 3) as usize;
+    assert_eq!(universe.preview_next()[centre], 0);
+    universe.tick();
+    assert_eq!(universe.get_cells()[centre], Cell::Dead);
+    universe.set_include_center(true);
+    universe.set_cells(&ring);
+    universe.set_cells(&[(3, 3)]);
+    universe.tick();
+    assert_eq!(universe.get_cells()[centre], Cell::Dead);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_wireworld() {
+    let wireworld = |width: u32, height: u32| {
+        let mut universe = Universe::with_size(width, height, Some(FillMode::Clear));
+        universe.set_boundary_mode(BoundaryMode::Bounded);
+        universe.set_rule("Wireworld").unwrap();
+        universe
+    };
+    // Heads seen at `(row, col)` over `ticks` generations.
+    let heads_at = |universe: &mut Universe, row: u32, col: u32, ticks: u32| {
+        let idx = (row * universe.width() + col) as usize;
+        (0..ticks)
+            .filter(|_| {
+                universe.tick();
+                universe.cell_states()[idx] == 1
+            })
+            .count()
+    };
+
+    // The clock sends an electron down its wire every six generations.
+    let mut universe = wireworld(12, 3);
+    universe.insert_circuit(Circuit::Clock, 0, 0).unwrap();
+    assert_eq!(universe.cell_states()[1..3], [1, 2]);
+    assert_eq!(heads_at(&mut universe, 1, 9, 36), 5);
+
+    // A diode lets an electron through one way only.
+    let mut universe = wireworld(9, 3);
+    universe.insert_circuit(Circuit::Diode, 0, 0).unwrap();
+    universe.paint_electron(1, 0).unwrap();
+    assert_eq!(heads_at(&mut universe, 1, 8, 20), 1);
+    let mut universe = wireworld(9, 3);
+    universe.insert_circuit(Circuit::Diode, 0, 0).unwrap();
+    universe.paint_electron(1, 8).unwrap();
+    assert_eq!(heads_at(&mut universe, 1, 0, 20), 0);
+
+    // Two electrons reaching the XOR gate together cancel out.
+    for (a, b, out) in [(true, false, 1), (false, true, 1), (true, true, 0)] {
+        let mut universe = wireworld(9, 7);
+        universe.insert_circuit(Circuit::XorGate, 0, 0).unwrap();
+        if a {
+            universe.paint_electron(0, 1).unwrap();
+            universe.set_cell_state(0, 0, 2).unwrap();
+        }
+        if b {
+            universe.paint_electron(6, 1).unwrap();
+            universe.set_cell_state(6, 0, 2).unwrap();
+        }
+        assert_eq!(heads_at(&mut universe, 3, 8, 30), out);
+    }
+
+    let mut universe = wireworld(4, 1);
+    universe.paint_conductor(0, 0).unwrap();
+    universe.paint_conductor(0, 1).unwrap();
+    universe.paint_electron(0, 2).unwrap();
+    universe.tick();
+    assert_eq!(universe.cell_states(), [3, 1, 2, 0]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_load_rule_file() {
+    // Wireworld as Golly ships it, run alongside the built-in rule.
+    let wireworld_table = "@RULE WireWorld
+# Heads become tails, tails conductors, and conductors heads next to one
+# or two heads.
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+var a={0,1,2,3}
+var b={a}
+var c={a}
+var d={a}
+var e={a}
+var f={a}
+var g={a}
+var h={a}
+var i={0,2,3}
+var j={i}
+var k={i}
+var l={i}
+var m={i}
+var n={i}
+var o={i}
+1,a,b,c,d,e,f,g,h,2
+2,a,b,c,d,e,f,g,h,3
+3,1,i,j,k,l,m,n,o,1
+3,1,1,j,k,l,m,n,o,1
+@COLORS
+1 255 255 0
+";
+    let mut builtin = Universe::with_size(9, 7, Some(FillMode::Clear));
+    builtin.set_boundary_mode(BoundaryMode::Bounded);
+    builtin.set_rule("Wireworld").unwrap();
+    builtin.insert_circuit(Circuit::XorGate, 0, 0).unwrap();
+    builtin.paint_electron(0, 1).unwrap();
+    let mut loaded = Universe::with_size(9, 7, Some(FillMode::Clear));
+    loaded.set_boundary_mode(BoundaryMode::Bounded);
+    loaded.load_rule_file(wireworld_table).unwrap();
+    assert_eq!(loaded.rule(), "WireWorld");
+    for (idx, &state) in builtin.cell_states().iter().enumerate() {
+        let (row, col) = (idx as u32 / 9, idx as u32 % 9);
+        loaded.set_cell_state(row, col, state).unwrap();
+    }
+    for _ in 0..30 {
+        builtin.tick();
+        loaded.tick();
+        assert_eq!(loaded.cell_states(), builtin.cell_states());
+    }
+
+    // A tree on the von Neumann neighbourhood where a cell comes alive
+    // next to a live one and never dies.
+    let growth_tree = "@RULE Grow
+@TREE
+num_states=2
+num_neighbors=4
+num_nodes=8
+1 0 1
+1 1 1
+2 0 1
+2 1 1
+3 2 3
+3 3 3
+4 4 5
+4 5 5
+5 6 7
+";
+    let mut universe = Universe::with_size(5, 5, Some(FillMode::Clear));
+    universe.load_rule_file(growth_tree).unwrap();
+    universe.set_cells(&[(2, 2)]);
+    universe.tick();
+    let plus = [7, 11, 12, 13, 17];
+    let live: Vec<usize> = (0..25)
+        .filter(|&idx| universe.cell_states()[idx] == 1)
+        .collect();
+    assert_eq!(live, plus);
+
+    assert!(universe.load_rule_file("@TABLE\nn_states:2\n").is_err());
+    assert!(universe
+        .load_rule_file("@RULE Big\n@TABLE\nn_states:7\n")
+        .is_err());
+    assert!(universe
+        .load_rule_file("@RULE Typo\n@TABLE\nn_states:2\n0,x,0,0,0,0,0,0,0,1\n")
+        .is_err());
+    assert!(universe
+        .load_rule_file("@RULE Empty\n@TABLE\nn_states:2\nvar a={}\n0,a,0,0,0,0,0,0,0,1\n")
+        .is_err());
+    assert_eq!(universe.rule(), "Grow");
+
+    // Undoing an edit made under a rule with more states leaves the states
+    // this one lacks dead rather than out of its table.
+    universe.set_rule("B2/S/C8").unwrap();
+    universe.set_cell_state(1, 1, 7).unwrap();
+    universe.load_rule_file(wireworld_table).unwrap();
+    universe.set_cell_state(1, 1, 3).unwrap();
+    assert!(universe.undo());
+    assert!(universe.undo());
+    assert_eq!(universe.cell_states()[6], 0);
+    universe.tick();
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_brians_brain() {
+    let mut universe = Universe::with_size(6, 6, Some(FillMode::Clear));
+    universe.set_rule("briansbrain").unwrap();
+    assert_eq!(universe.rule_preset(), Some(Rule::BriansBrain));
+    universe.set_cells(&[(2, 2), (2, 3)]);
+    universe.tick();
+    assert_eq!(universe.state_counts(), [30, 4, 2]);
+
+    // Dying cells render blue between the black live ones.
+    let pixels = universe.render_rgba(1);
+    assert_eq!(pixels.len(), 6 * 6 * 4);
+    let pixel = |row: usize, col: usize| &pixels[(row * 6 + col) * 4..][..4];
+    assert_eq!(pixel(1, 2), [0, 0, 0, 255]);
+    assert_eq!(pixel(2, 2), [40, 100, 230, 255]);
+    assert_eq!(pixel(0, 0), [255, 255, 255, 255]);
+    assert!(universe.render().contains('▣'));
+
+    let mut a = Universe::brians_brain(64, 64, 5);
+    let b = Universe::brians_brain(64, 64, 5);
+    assert_eq!(a.get_cells(), b.get_cells());
+    assert_eq!(a.rule(), "B2/S/C3");
+    assert!(a.population() > 0);
+    for _ in 0..20 {
+        a.tick();
+    }
+    assert!(a.population() > 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_ants() {
+    // Langton's ant, in both notations, walks a square and then starts
+    // undoing it.
+    for program in ["RL", "{{{1, 2, 0}, {0, 8, 0}}}"] {
+        let mut universe = Universe::with_size(8, 8, Some(FillMode::Clear));
+        universe.add_ant(4, 4, Direction::North, program).unwrap();
+        universe.tick();
+        assert_eq!(universe.ants(), [4, 5, Direction::East as u32]);
+        for _ in 0..3 {
+            universe.tick();
+        }
+        assert_eq!(universe.population(), 4);
+        assert_eq!(universe.ants(), [4, 4, Direction::North as u32]);
+        universe.tick();
+        assert_eq!(universe.population(), 3);
+        assert_eq!(universe.ants(), [4, 3, Direction::West as u32]);
+    }
+
+    // Ants wrap around a toroidal board and fall off a bounded one.
+    let mut universe = Universe::with_size(4, 4, Some(FillMode::Clear));
+    universe.add_ant(0, 0, Direction::West, "NN").unwrap();
+    universe.tick();
+    assert_eq!(universe.ants(), [0, 3, Direction::West as u32]);
+    universe.set_boundary_mode(BoundaryMode::Bounded);
+    universe.add_ant(0, 0, Direction::North, "NN").unwrap();
+    universe.tick();
+    assert_eq!(universe.ants(), [0, 2, Direction::West as u32]);
+
+    // Without ants the rule runs again.
+    universe.clear_ants();
+    universe.reset_clear();
+    universe.set_cells(&[(1, 0), (1, 1), (1, 2)]);
+    universe.tick();
+    assert_eq!(universe.population(), 3);
+    assert!(universe.ants().is_empty());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_elementary_rule() {
+    // Rule 90 grows a Sierpinski triangle up the spacetime diagram.
+    let mut universe = Universe::with_size(9, 4, Some(FillMode::Clear));
+    universe.set_rule("w90").unwrap();
+    assert_eq!(universe.rule(), "W90");
+    universe.set_cells(&[(3, 4)]);
+    universe.tick();
+    universe.tick();
+    assert_eq!(
+        universe.get_cells_bytes()[9..],
+        [
+            0, 0, 0, 0, 1, 0, 0, 0, 0, //
+            0, 0, 0, 1, 0, 1, 0, 0, 0, //
+            0, 0, 1, 0, 0, 0, 1, 0, 0,
+        ]
+    );
+
+    // Rule 30 on a single row, wrapping at the edges.
+    let mut universe = Universe::with_size(5, 1, Some(FillMode::Clear));
+    universe.set_rule("W30").unwrap();
+    universe.set_cells(&[(0, 2)]);
+    universe.tick();
+    assert_eq!(universe.get_cells_bytes(), [0, 1, 1, 1, 0]);
+    universe.tick();
+    assert_eq!(universe.get_cells_bytes(), [1, 1, 0, 0, 1]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_sandpile() {
+    let mut universe = Universe::with_size(5, 5, Some(FillMode::Clear));
+    universe.set_boundary_mode(BoundaryMode::Bounded);
+    universe.set_rule("Sandpile").unwrap();
+    universe.drop_sand(2, 2, 4).unwrap();
+    assert!(!universe.is_stable());
+    assert_eq!(universe.cell_states()[12], 4);
+    universe.tick();
+    assert!(universe.is_stable());
+    let grains = universe.grains();
+    assert_eq!(grains[12], 0);
+    assert_eq!(
+        [grains[7], grains[11], grains[13], grains[17]],
+        [1, 1, 1, 1]
+    );
+    assert_eq!(universe.population(), 4);
+
+    // Sixteen grains spread out without reaching the edges, so none are
+    // lost.
+    universe.drop_sand(2, 2, 12).unwrap();
+    while !universe.is_stable() {
+        universe.tick();
+    }
+    assert_eq!(universe.grains().iter().sum::<u32>(), 16);
+    assert!(universe.grains().iter().all(|&count| count < 4));
+
+    // Grains toppled off a bounded board are lost.
+    universe.drop_sand(0, 0, 4).unwrap();
+    universe.tick();
+    assert_eq!(universe.grains().iter().sum::<u32>(), 18);
+
+    // Piles as high as they go topple into each other without overflowing.
+    universe.drop_sand(4, 3, u32::MAX).unwrap();
+    universe.drop_sand(4, 4, u32::MAX).unwrap();
+    universe.tick();
+    assert!(universe.grains()[24] >= u32::MAX - 4);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_forest_fire() {
+    // Fire spreads along a row of trees, burning each out behind it.
+    let mut universe = Universe::with_size(5, 3, Some(FillMode::Clear));
+    universe.set_forest_fire(0.0, 0.0, 1).unwrap();
+    universe.set_cells(&[(1, 0), (1, 1), (1, 2), (1, 3)]);
+    universe.set_cell_state(1, 0, 2).unwrap();
+    universe.tick();
+    assert_eq!(universe.cell_states()[5..10], [0, 2, 1, 1, 0]);
+    universe.tick();
+    assert_eq!(universe.cell_states()[5..10], [0, 0, 2, 1, 0]);
+
+    // With certain growth every empty cell grows a tree.
+    universe.set_forest_fire(1.0, 0.0, 1).unwrap();
+    universe.tick();
+    assert_eq!(universe.cell_states()[5..10], [1, 1, 0, 2, 1]);
+
+    let rule = "ForestFire,P0.05,F0.001,S42";
+    let run = |rule: &str| {
+        let mut universe = Universe::with_size(32, 32, Some(FillMode::Clear));
+        universe.set_rule(rule).unwrap();
+        for _ in 0..30 {
+            universe.tick();
+        }
+        universe.cell_states()
+    };
+    assert_eq!(run(rule), run(rule));
+    assert_ne!(run(rule), run("ForestFire,P0.05,F0.001,S43"));
+    let mut universe = Universe::with_size(4, 4, Some(FillMode::Clear));
+    universe.set_rule(&rule.to_lowercase()).unwrap();
+    assert_eq!(universe.rule(), rule);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_wator() {
+    let sea = || {
+        let mut universe = Universe::with_size(5, 5, Some(FillMode::Clear));
+        universe.set_boundary_mode(BoundaryMode::Bounded);
+        universe.set_wator(2, 8, 2, 7).unwrap();
+        universe
+    };
+
+    // A fish swims every tick and breeds every other one.
+    let mut universe = sea();
+    universe.set_cell_state(2, 2, 1).unwrap();
+    universe.tick();
+    assert_eq!(universe.state_counts(), [24, 1, 0]);
+    assert_eq!(universe.cell_states()[12], 0);
+    universe.tick();
+    assert_eq!(universe.state_counts(), [23, 2, 0]);
+
+    // A shark eats the fish beside it and, with nothing left to eat,
+    // starves.
+    let mut universe = sea();
+    universe.set_cell_state(2, 2, 2).unwrap();
+    universe.set_cell_state(2, 3, 1).unwrap();
+    assert_eq!(universe.shark_energy()[12], 2);
+    universe.tick();
+    assert_eq!(universe.cell_states()[12..14], [0, 2]);
+    assert_eq!(universe.shark_energy()[13], 3);
+    for _ in 0..3 {
+        universe.tick();
+    }
+    assert_eq!(universe.state_counts(), [25, 0, 0]);
+
+    // A well-fed shark's energy tops out rather than wrapping round.
+    let mut universe = sea();
+    universe.set_wator(2, 8, u32::MAX, 7).unwrap();
+    universe.set_cell_state(2, 2, 2).unwrap();
+    universe.set_cell_state(2, 3, 1).unwrap();
+    universe.tick();
+    assert_eq!(universe.shark_energy()[13], u32::MAX);
+
+    let rule = "WaTor,FB3,SB8,E4,S42";
+    let run = || {
+        let mut universe = Universe::with_size(32, 32, Some(FillMode::Clear));
+        universe.set_rule(rule).unwrap();
+        assert_eq!(universe.rule(), rule);
+        universe.fill_sea(0.3, 0.05, 1).unwrap();
+        for _ in 0..20 {
+            universe.tick();
+        }
+        (universe.cell_states(), universe.shark_energy())
+    };
+    assert_eq!(run(), run());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_gray_scott() {
+    let mut sim = GrayScott::new(64, 64);
+    sim.set_preset(GrayScottPreset::Coral);
+    assert_eq!((sim.feed(), sim.kill()), (0.0545, 0.062));
+    let field = |sim: &GrayScott| {
+        unsafe { std::slice::from_raw_parts(sim.concentration_ptr(), 64 * 64) }.to_vec()
+    };
+    assert!(field(&sim).iter().all(|&v| v == 0.0));
+
+    // V spreads out from the seeded square and stays in range.
+    sim.seed_square(28, 28, 8);
+    sim.tick(200);
+    let v = field(&sim);
+    assert!(v.iter().all(|&v| (0.0..=1.0).contains(&v)));
+    assert!(v[20 * 64 + 32] > 0.0);
+    assert!(v[0] < 1e-6);
+
+    sim.reset();
+    assert!(field(&sim).iter().all(|&v| v == 0.0));
+
+    let mut empty = GrayScott::new(0, 0);
+    empty.seed_square(0, 0, 4);
+    empty.tick(5);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_lenia() {
+    let cells =
+        |lenia: &Lenia| unsafe { std::slice::from_raw_parts(lenia.cells_ptr(), 64 * 64) }.to_vec();
+    // Where the mass sits, as the average column weighted by state.
+    let centre_col = |lenia: &Lenia| {
+        let cells = cells(lenia);
+        let weighted: f32 = cells
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (i % 64) as f32 * c)
+            .sum();
+        weighted / lenia.mass()
+    };
+
+    // Orbium keeps its shape, roughly its mass, and moves.
+    let mut lenia = Lenia::new(64, 64);
+    lenia.insert_orbium(22, 22);
+    let mass = lenia.mass();
+    let start = centre_col(&lenia);
+    lenia.tick(50);
+    assert!((lenia.mass() - mass).abs() < 0.2 * mass);
+    assert!((centre_col(&lenia) - start).abs() > 1.0);
+    assert!(cells(&lenia).iter().all(|&c| (0.0..=1.0).contains(&c)));
+
+    // Without anything nearby, empty space stays empty.
+    lenia.clear();
+    lenia.tick(1);
+    assert_eq!(lenia.mass(), 0.0);
+
+    let mut empty = Lenia::new(0, 0);
+    empty.insert_orbium(0, 0);
+    empty.tick(1);
+    assert_eq!(empty.mass(), 0.0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_colored_life() {
+    let mut universe = Universe::with_size(8, 8, Some(FillMode::Clear));
+    universe.set_rule("Immigration").unwrap();
+    assert_eq!(universe.rule(), "Immigration");
+
+    // A blinker keeps its colours as it turns, and the new cells at the
+    // ends take the majority colour of the three that made them.
+    universe.set_cell_color(2, 3, 1).unwrap();
+    universe.set_cell_color(3, 3, 2).unwrap();
+    universe.set_cell_color(4, 3, 2).unwrap();
+    universe.tick();
+    let colors = universe.cell_colors();
+    assert_eq!(universe.population(), 3);
+    assert_eq!(colors[3 * 8 + 2..3 * 8 + 5], [2, 2, 2]);
+    assert_eq!(colors.iter().filter(|&&color| color > 0).count(), 3);
+
+    // In QuadLife three parents of different colours give the fourth.
+    universe.set_rule("B3/S23/QuadLife").unwrap();
+    assert_eq!(universe.rule(), "QuadLife");
+    universe.reset_clear();
+    universe.set_cell_color(2, 2, 1).unwrap();
+    universe.set_cell_color(2, 3, 2).unwrap();
+    universe.set_cell_color(3, 2, 3).unwrap();
+    universe.tick();
+    assert_eq!(universe.cell_colors()[3 * 8 + 3], 4);
+
+    // Colours go away with the rule.
+    universe.set_rule("B3/S23").unwrap();
+    assert!(universe.cell_colors().iter().all(|&color| color == 0));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_word_parallel_tick_matches_table() {
+    // Conway's rule as a transition table, which takes the cell-by-cell
+    // path, for checking the 64-cells-at-a-time one against. B3/S34
+    // counting the cell itself is the same rule.
+    let conway: Vec<u8> = (0u32..512)
+        .map(|block| {
+            let neighbours = (block & !16).count_ones();
+            (neighbours == 3 || (block & 16 != 0 && neighbours == 2)) as u8
+        })
+        .collect();
+    for &width in &[1, 3, 31, 64, 65, 130] {
+        for &(boundary, background) in &[
+            (BoundaryMode::Toroidal, false),
+            (BoundaryMode::Bounded, false),
+            (BoundaryMode::Bounded, true),
+        ] {
+            for &include_center in &[false, true] {
+                let mut fast = Universe::with_size(width, 7, Some(FillMode::Clear));
+                fast.reset_random_quadrants(&[0.3, 0.5, 0.7, 0.4], width as u64)
+                    .unwrap();
+                fast.set_boundary_mode(boundary);
+                fast.set_background(background);
+                fast.set_include_center(include_center);
+                if include_center {
+                    fast.set_rule("B3/S34").unwrap();
+                }
+                let mut slow = fast.clone();
+                slow.set_transition_table(&conway).unwrap();
+                for _ in 0..4 {
+                    fast.tick();
+                    slow.tick();
+                    assert_eq!(fast.get_cells(), slow.get_cells());
+                }
+            }
+        }
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_benchmark_ticks() {
+    let mut universe = Universe::with_size(64, 64, None);
+    let rate = universe.benchmark_ticks(10);
+    assert!(rate > 0.0);
+    assert_eq!(universe.generation(), 10);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_sparse_ticks_see_edits() {
+    // Conway's rule as a transition table goes cell by cell, and on a
+    // sparse board only near the last changes; Conway's rule itself goes
+    // over every cell. Edits between ticks have to wake cells up.
+    let conway: Vec<u8> = (0u32..512)
+        .map(|block| {
+            let neighbours = (block & !16).count_ones();
+            (neighbours == 3 || (block & 16 != 0 && neighbours == 2)) as u8
+        })
+        .collect();
+    let mut sparse = Universe::with_size(40, 40, Some(FillMode::Clear));
+    sparse.set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+    sparse.set_transition_table(&conway).unwrap();
+    let mut full = sparse.clone();
+    full.set_rule("B3/S23").unwrap();
+    for generation in 0..60 {
+        if generation == 20 {
+            // A blinker far from the glider, and a block next to it.
+            for universe in [&mut sparse, &mut full] {
+                universe.set_cells(&[(30, 10), (30, 11), (30, 12)]);
+                universe.set_cells(&[(20, 30), (20, 31), (21, 30), (21, 31)]);
+            }
+        }
+        sparse.tick();
+        full.tick();
+        assert_eq!(sparse.get_cells(), full.get_cells());
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_switching_rules_with_dying_states() {
+    let mut universe = Universe::with_size(16, 16, Some(FillMode::Clear));
+    universe
+        .reset_random_quadrants(&[0.4, 0.4, 0.4, 0.4], 3)
+        .unwrap();
+    universe.set_rule("B2/S/C8").unwrap();
+    for _ in 0..3 {
+        universe.tick();
+    }
+    assert!(universe.state_counts()[4] > 0);
+    universe.set_rule("B2/S/C3").unwrap();
+    universe.tick();
+    assert_eq!(universe.state_counts().len(), 3);
+
+    // Dying states mean something else under another kind of rule, so
+    // switching drops them and keeps only the live cells.
+    for _ in 0..3 {
+        universe.tick();
+    }
+    universe.set_rule("B2/S/C8").unwrap();
+    universe.tick();
+    universe.tick();
+    let live = universe.population();
+    universe.set_rule("Wireworld").unwrap();
+    assert_eq!(universe.state_counts()[1..], [live, 0, 0]);
+    universe.render_rgba(1);
+    universe.set_rule("B2/S/C4").unwrap();
+    assert_eq!(universe.state_counts()[1..], [live, 0, 0]);
+    universe.render_rgba(1);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_reshaped_board_ticks_every_cell() {
+    // Cells skipped for being far from the last tick's changes are only
+    // far on the board that tick ran on. The domino is still under
+    // von Neumann B2/S12, but its cells land apart on the new board.
+    let mut universe = Universe::with_size(20, 20, Some(FillMode::Clear));
+    universe.set_neighborhood(NeighborhoodKind::VonNeumann);
+    universe.set_rule("B2/S12").unwrap();
+    universe.set_cells(&[(0, 5), (1, 5)]);
+    universe.tick();
+    assert_eq!(universe.population(), 2);
+    universe.resize(40, 10, ResizeMode::Clear);
+    universe.set_cells(&[(0, 5), (0, 25)]);
+    universe.tick();
+    assert_eq!(universe.population(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_neighbor_counts_follow_edits() {
+    // B2/S12 on the von Neumann neighbourhood keeps neighbour counts
+    // between ticks; the same rule as a transition table on the four
+    // orthogonal cells of the 3x3 block counts afresh.
+    let table: Vec<u8> = (0u32..512)
+        .map(|block| {
+            let neighbours = (block & 0b010_101_010).count_ones();
+            let alive = block & 16 != 0;
+            (if alive {
+                neighbours == 1 || neighbours == 2
+            } else {
+                neighbours == 2
+            }) as u8
+        })
+        .collect();
+    let mut counted = Universe::with_size(20, 20, Some(FillMode::Clear));
+    counted
+        .reset_random_quadrants(&[0.1, 0.2, 0.3, 0.2], 7)
+        .unwrap();
+    counted.set_neighborhood(NeighborhoodKind::VonNeumann);
+    counted.set_rule("B2/S12").unwrap();
+    let mut fresh = counted.clone();
+    fresh.set_transition_table(&table).unwrap();
+    for generation in 0..30 {
+        match generation {
+            10 => {
+                for universe in [&mut counted, &mut fresh] {
+                    universe.set_cells(&[(5, 5), (5, 6), (12, 0), (19, 19)]);
+                    universe.toggle_cell(0, 0).unwrap();
+                }
+            }
+            20 => {
+                for universe in [&mut counted, &mut fresh] {
+                    universe.set_boundary_mode(BoundaryMode::Bounded);
+                }
+            }
+            _ => {}
+        }
+        counted.tick();
+        fresh.tick();
+        assert_eq!(counted.get_cells(), fresh.get_cells());
+    }
+    assert!(counted.population() > 0);
+
+    // Reshaping to the same number of cells lays them out afresh.
+    for universe in [&mut counted, &mut fresh] {
+        universe.resize(40, 10, ResizeMode::Clear);
+        universe.set_cells(&[(2, 2), (2, 3), (3, 30), (4, 30), (9, 39)]);
+    }
+    for _ in 0..5 {
+        counted.tick();
+        fresh.tick();
+        assert_eq!(counted.get_cells(), fresh.get_cells());
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_hashlife_jumps_match_ticks() {
+    let mut flat = Universe::with_size(32, 32, Some(FillMode::Clear));
+    flat.reset_random_quadrants(&[0.3, 0.4, 0.2, 0.35], 11)
+        .unwrap();
+    flat.set_rule("B36/S23").unwrap();
+    let mut hashlife = flat.clone();
+    hashlife.set_engine(Engine::Hashlife);
+    assert_eq!(hashlife.engine(), Engine::Hashlife);
+
+    for _ in 0..5 {
+        flat.tick();
+        hashlife.tick();
+        assert_eq!(hashlife.get_cells(), flat.get_cells());
+    }
+    for k in [0, 3, 6] {
+        for _ in 0..1 << k {
+            flat.tick();
+        }
+        hashlife.jump(k).unwrap();
+        assert_eq!(hashlife.get_cells(), flat.get_cells());
+        assert_eq!(hashlife.generation(), flat.generation());
+    }
+
+    // Edits made through the flat API carry over to the next jump.
+    for universe in [&mut flat, &mut hashlife] {
+        universe.set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+    }
+    for _ in 0..16 {
+        flat.tick();
+    }
+    hashlife.jump(4).unwrap();
+    assert_eq!(hashlife.get_cells(), flat.get_cells());
+
+    // A glider crosses a 64-wide torus diagonally every 256 generations,
+    // so 2^40 generations on it is back where it started.
+    let mut glider = Universe::with_size(64, 64, Some(FillMode::Clear));
+    glider.set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+    let start = glider.get_cells();
+    glider.set_engine(Engine::Hashlife);
+    glider.jump(40).unwrap();
+    assert_eq!(glider.get_cells(), start);
+    assert_eq!(glider.generation(), 1 << 40);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_jump_rejects_huge_steps() {
+    let mut universe = Universe::with_size(16, 16, Some(FillMode::Clear));
+    universe.set_engine(Engine::Hashlife);
+    assert!(universe.jump(63).is_err());
+    assert_eq!(universe.generation(), 0);
+
+    // Without Hashlife a jump ticks every generation, so it is kept short.
+    universe.set_engine(Engine::Flat);
+    assert!(universe.jump(40).is_err());
+    assert_eq!(universe.generation(), 0);
+    universe.jump(4).unwrap();
+    assert_eq!(universe.generation(), 16);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_gpu_texels_round_trip_through_padded_rows() {
+    let mut universe = Universe::with_size(70, 3, Some(FillMode::Clear));
+    universe.set_cells(&[(0, 0), (1, 69), (2, 35)]);
+    let texels = universe.gpu_texels();
+    assert_eq!(texels.len(), 70 * 3 * 4);
+    assert_eq!(texels[..8], [1, 0, 0, 0, 0, 0, 0, 0]);
+    let target = Universe::from_texels(70, 3, &texels, 70 * 4).unwrap();
+    assert_eq!(target.get_cells(), universe.get_cells());
+
+    // Read back from a texture, rows of 280 bytes come 512 apart.
+    let mut mapped = vec![0xff; 512 * 3];
+    for (row, texels) in texels.chunks(70 * 4).enumerate() {
+        mapped[row * 512..][..texels.len()].copy_from_slice(texels);
+    }
+    let target = Universe::from_texels(70, 3, &mapped, 512).unwrap();
+    assert_eq!(target.get_cells(), universe.get_cells());
+    assert!(Universe::from_texels(70, 3, &mapped, 256).is_err());
+    assert!(Universe::from_texels(70, 3, &mapped[..2 * 512], 512).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_gpu_engine_takes_two_state_life() {
+    let mut universe = Universe::with_size(8, 8, Some(FillMode::Clear));
+    universe.set_engine(Engine::Gpu);
+    assert_eq!(universe.engine(), Engine::Gpu);
+    assert!(universe.runs_on_gpu());
+    universe.set_boundary_mode(BoundaryMode::Bounded);
+    universe.set_include_center(true);
+    assert!(universe.runs_on_gpu());
+
+    // Ticks stay on the CPU.
+    universe.set_include_center(false);
+    universe.set_cells(&[(3, 2), (3, 3), (3, 4)]);
+    universe.tick();
+    assert_eq!(universe.population(), 3);
+
+    universe.set_rule("B3/S23/C3").unwrap();
+    assert!(!universe.runs_on_gpu());
+    universe.set_rule("Wireworld").unwrap();
+    assert!(!universe.runs_on_gpu());
+    universe.set_rule("B3/S23").unwrap();
+    universe.set_frozen_border(1);
+    assert!(!universe.runs_on_gpu());
+    universe.set_frozen_border(0);
+    universe.set_neighborhood(NeighborhoodKind::VonNeumann);
+    assert!(!universe.runs_on_gpu());
+    universe.set_neighborhood(NeighborhoodKind::Moore);
+    universe.set_birth_probability(3, 0.5).unwrap();
+    assert!(!universe.runs_on_gpu());
+    assert!(!Universe::with_size(0, 4, Some(FillMode::Clear)).runs_on_gpu());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_sparse_universe_matches_bounded() {
+    // A soup in the middle of a board with dead edges it doesn't reach
+    // runs the same unbounded, across chunk boundaries.
+    let mut bounded = Universe::with_size(200, 200, Some(FillMode::Clear));
+    bounded.set_boundary_mode(BoundaryMode::Bounded);
+    bounded.set_rule("B36/S23").unwrap();
+    let mut soup = vec![];
+    for row in 50..90 {
+        for col in 40..80 {
+            if (row * 7 + col * 13) % 5 < 2 {
+                soup.push((row, col));
+            }
+        }
+    }
+    bounded.set_cells(&soup);
+    let mut sparse = SparseUniverse::from_universe(&bounded).unwrap();
+    assert_eq!(sparse.rule(), "B36/S23");
+    for _ in 0..40 {
+        bounded.tick();
+        sparse.tick();
+    }
+    let cells = bounded.get_cells();
+    for row in 0..200 {
+        for col in 0..200 {
+            let idx = (row * 200 + col) as usize;
+            assert_eq!(sparse.get(row, col), cells[idx] == Cell::Alive);
+        }
+    }
+    assert_eq!(sparse.population(), bounded.population() as u64);
+
+    // Boards it couldn't run the same aren't copied under another rule.
+    bounded.set_include_center(true);
+    assert!(SparseUniverse::from_universe(&bounded).is_err());
+    bounded.set_include_center(false);
+    bounded.set_neighborhood(NeighborhoodKind::VonNeumann);
+    assert!(SparseUniverse::from_universe(&bounded).is_err());
+    bounded.set_neighborhood(NeighborhoodKind::Moore);
+    bounded.set_rule("B2/S/C3").unwrap();
+    assert!(SparseUniverse::from_universe(&bounded).is_err());
+
+    // A glider heading up and left leaves chunk (0, 0) for negative rows
+    // and columns, and empty chunks are dropped.
+    let mut glider = SparseUniverse::new();
+    for (row, col) in [(1, 1), (1, 2), (1, 3), (2, 1), (3, 2)] {
+        glider.set(row, col, true);
+    }
+    assert_eq!(glider.chunks(), vec![0, 0]);
+    for _ in 0..40 {
+        glider.tick();
+    }
+    assert_eq!(glider.population(), 5);
+    assert_eq!(glider.chunks(), vec![-1, -1]);
+    let chunk = glider.chunk(-1, -1);
+    assert_eq!(chunk.len(), 128);
+    let live: u32 = chunk.iter().map(|word| word.count_ones()).sum();
+    assert_eq!(live, 5);
+    // Ten cells on, in the same phase.
+    for (row, col) in [(1, 1), (1, 2), (1, 3), (2, 1), (3, 2)] {
+        assert!(glider.get(row - 10, col - 10));
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_rule_change_rebuilds_lookup_table() {
+    // B1/S012 on the von Neumann neighbourhood as a transition table on
+    // the four orthogonal cells of the 3x3 block.
+    let table: Vec<u8> = (0u32..512)
+        .map(|block| {
+            let neighbours = (block & 0b010_101_010).count_ones();
+            let alive = block & 16 != 0;
+            (if alive {
+                neighbours <= 2
+            } else {
+                neighbours == 1
+            }) as u8
+        })
+        .collect();
+    let mut universe = Universe::with_size(24, 24, Some(FillMode::Clear));
+    universe.set_neighborhood(NeighborhoodKind::VonNeumann);
+    universe.set_cells(&[(10, 10), (10, 11), (11, 10), (12, 12)]);
+    universe.set_rule("B2/S/C3").unwrap();
+    universe.tick();
+    universe.set_rule("B1/S012").unwrap();
+    let mut expected = universe.clone();
+    expected.set_transition_table(&table).unwrap();
+    for _ in 0..10 {
+        universe.tick();
+        expected.tick();
+        assert_eq!(universe.get_cells(), expected.get_cells());
+    }
+    assert!(universe.population() > 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_shards_tick_like_the_whole_board() {
+    for boundary in [BoundaryMode::Toroidal, BoundaryMode::Bounded] {
+        let mut whole = Universe::with_size(40, 31, Some(FillMode::Clear));
+        whole
+            .reset_random_quadrants(&[0.3, 0.4, 0.35, 0.3], 5)
+            .unwrap();
+        whole.set_boundary_mode(boundary);
+        whole.set_rule("B36/S23").unwrap();
+        let mut shards: Vec<UniverseShard> = (0..3)
+            .map(|index| UniverseShard::from_universe(&whole, 3, index).unwrap())
+            .collect();
+        assert_eq!(shards.iter().map(|shard| shard.rows()).sum::<u32>(), 31);
+        let words = shards[0].words_per_row() as usize;
+        let dead = vec![0; words];
+        for _ in 0..20 {
+            // The halo exchange, as workers would do it through a shared
+            // buffer.
+            let edges: Vec<Vec<u32>> = shards.iter().map(|shard| shard.edges()).collect();
+            for (index, shard) in shards.iter_mut().enumerate() {
+                let above = match (index, boundary) {
+                    (0, BoundaryMode::Bounded) => &dead[..],
+                    (0, _) => &edges[2][words..],
+                    (i, _) => &edges[i - 1][words..],
+                };
+                let below = match (index, boundary) {
+                    (2, BoundaryMode::Bounded) => &dead[..],
+                    (2, _) => &edges[0][..words],
+                    (i, _) => &edges[i + 1][..words],
+                };
+                shard.set_halos(above, below).unwrap();
+                shard.tick();
+            }
+            whole.tick();
+        }
+        let board: Vec<u32> = shards.iter().flat_map(|shard| shard.band()).collect();
+        let stitched = UniverseShard::stitch(40, 31, &board).unwrap();
+        assert_eq!(stitched.get_cells(), whole.get_cells());
+        assert_eq!(shards[1].generation(), 20);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen_test]
+pub fn test_shards_exchange_through_shared_buffers() {
+    let mut whole = Universe::with_size(40, 31, Some(FillMode::Clear));
+    whole
+        .reset_random_quadrants(&[0.3, 0.4, 0.35, 0.3], 5)
+        .unwrap();
+    let mut shards: Vec<UniverseShard> = (0..3)
+        .map(|index| UniverseShard::from_universe(&whole, 3, index).unwrap())
+        .collect();
+    let words = shards[0].words_per_row();
+    let halos = js_sys::Uint32Array::new_with_length(2 * 3 * words);
+    let board = js_sys::Uint32Array::new_with_length(31 * words);
+    for _ in 0..10 {
+        for shard in &shards {
+            shard.publish_edges(&halos).unwrap();
+        }
+        for shard in &mut shards {
+            shard.sync_halos(&halos).unwrap();
+            shard.tick();
+        }
+        whole.tick();
+    }
+    for shard in &shards {
+        shard.write_band(&board).unwrap();
+    }
+    let stitched = UniverseShard::stitch(40, 31, &board.to_vec()).unwrap();
+    assert_eq!(stitched.get_cells(), whole.get_cells());
+
+    // Buffers of the wrong size are turned down rather than half written.
+    let short = js_sys::Uint32Array::new_with_length(2 * 3 * words - 1);
+    assert!(shards[2].publish_edges(&short).is_err());
+    assert!(shards[0].sync_halos(&short).is_err());
+    let short = js_sys::Uint32Array::new_with_length(30 * words);
+    assert!(shards[0].write_band(&short).is_ok());
+    assert!(shards[2].write_band(&short).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_shards_exchange_through_slices() {
+    let mut whole = Universe::with_size(40, 31, Some(FillMode::Clear));
+    whole
+        .reset_random_quadrants(&[0.3, 0.4, 0.35, 0.3], 5)
+        .unwrap();
+    whole.set_boundary_mode(BoundaryMode::Toroidal);
+    let mut shards: Vec<UniverseShard> = (0..3)
+        .map(|index| UniverseShard::from_universe(&whole, 3, index).unwrap())
+        .collect();
+    let words = shards[0].words_per_row() as usize;
+    let mut halos = vec![0; 2 * 3 * words];
+    let mut board = vec![0; 31 * words];
+    for _ in 0..10 {
+        for shard in &shards {
+            shard.publish_edges_into(&mut halos).unwrap();
+        }
+        for shard in &mut shards {
+            shard.sync_halos_from(&halos).unwrap();
+            shard.tick();
+        }
+        whole.tick();
+    }
+    for shard in &shards {
+        shard.write_band_into(&mut board).unwrap();
+    }
+    let stitched = UniverseShard::stitch(40, 31, &board).unwrap();
+    assert_eq!(stitched.get_cells(), whole.get_cells());
+
+    let mut short = vec![0; 2 * 3 * words - 1];
+    assert!(shards[2].publish_edges_into(&mut short).is_err());
+    assert!(shards[0].sync_halos_from(&short).is_err());
+    let mut short = vec![0; 30 * words];
+    assert!(shards[0].write_band_into(&mut short).is_ok());
+    assert!(shards[2].write_band_into(&mut short).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_shared_cells_stay_put_across_ticks() {
+    let mut universe = input_spaceship();
+    universe.share_cells();
+    let layout = universe.shared_cells().unwrap();
+    for _ in 0..3 {
+        universe.tick();
+        assert_eq!(universe.shared_cells().unwrap(), layout);
+    }
+    let (state, words) = (layout[3] as *const u32, layout[2] as usize);
+    // Only wasm32 addresses fit in a u32.
+    if cfg!(target_arch = "wasm32") {
+        let (front, sequence) = unsafe { (*state, *state.add(1)) };
+        assert_eq!(sequence, 6);
+        let cells =
+            unsafe { std::slice::from_raw_parts(layout[front as usize] as *const u32, words) };
+        let expected = unsafe { std::slice::from_raw_parts(universe.cells(), words) };
+        assert_eq!(cells, expected);
+    }
+
+    // Resizing, keeping the cells or not, sets aside buffers of the new
+    // length.
+    universe.resize(64, 64, ResizeMode::PreserveTopLeft);
+    universe.tick();
+    assert_eq!(universe.shared_cells().unwrap()[2], 64 * 64 / 32);
+    universe.resize(40, 40, ResizeMode::Clear);
+    universe.tick();
+    assert_eq!(universe.shared_cells().unwrap()[2], 40 * 40 / 32);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_n_stops_when_settled() {
+    let mut glider = input_spaceship();
+    glider.set_stop_when_stable(true);
+    assert_eq!(glider.tick_n(1), 1);
+    assert_eq!(glider.get_cells(), expected_spaceship().get_cells());
+    assert_eq!(glider.tick_n(8), 8);
+
+    let mut block = Universe::with_size(8, 8, Some(FillMode::Clear));
+    block.set_cells(&[(1, 1), (1, 2), (2, 1), (2, 2)]);
+    assert_eq!(block.tick_n(5), 5);
+    block.set_stop_when_stable(true);
+    assert_eq!(block.tick_n(5), 1);
+
+    let mut lone = Universe::with_size(8, 8, Some(FillMode::Clear));
+    lone.set_cells(&[(3, 3)]);
+    lone.set_stop_when_extinct(true);
+    assert_eq!(lone.tick_n(5), 1);
+    assert_eq!(lone.generation(), 1);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_tick_for_millis_counts_ticks() {
+    let mut universe = Universe::new();
+    let ran = universe.tick_for_millis(20.0);
+    assert!(ran > 0);
+    assert_eq!(universe.generation(), ran as u64);
+    assert_eq!(universe.tick_for_millis(0.0), 0);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen_test]
+pub async fn test_run_async_reports_progress() {
+    let run = input_spaceship().run_async(100, None);
+    assert_eq!(run.remaining(), 100);
+    let universe = wasm_bindgen_futures::JsFuture::from(run.done())
+        .await
+        .unwrap();
+    assert!(!universe.is_undefined());
+    assert_eq!(run.remaining(), 0);
+    assert_eq!(run.generation(), 100);
+    assert_eq!(run.population(), 5);
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen_test]
+pub async fn test_run_async_stops_when_cancelled_or_failing() {
+    let run = input_spaceship().run_async(100, None);
+    run.cancel_token().cancel();
+    wasm_bindgen_futures::JsFuture::from(run.done())
+        .await
+        .unwrap();
+    assert_eq!(run.remaining(), 0);
+    assert_eq!(run.generation(), 0);
+    assert!(run.error().is_none());
+
+    // A failing callback stops the run but still hands the universe back.
+    let failing = js_sys::Function::new_no_args("throw new Error('stop')");
+    let run = input_spaceship().run_async(100, Some(failing));
+    let universe = wasm_bindgen_futures::JsFuture::from(run.done())
+        .await
+        .unwrap();
+    assert!(!universe.is_undefined());
+    assert!(run.error().is_some());
+    assert_eq!(run.remaining(), 0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_seeded_soups_repeat_across_resizes() {
+    let soup = |seed| {
+        let mut universe = Universe::with_size(16, 16, Some(FillMode::Clear));
+        universe.set_seed(seed);
+        universe.reset_random();
+        universe
+    };
+    let (mut a, mut b) = (soup(7), soup(7));
+    assert_eq!(a.get_cells(), b.get_cells());
+    assert_ne!(a.get_cells(), soup(8).get_cells());
+
+    // The stream carries on across a resize, even to another shape, rather
+    // than starting over.
+    a.resize(32, 8, ResizeMode::PreserveTopLeft);
+    a.reset_random();
+    b.reset_random();
+    let mut wide = Universe::with_size(32, 8, Some(FillMode::Clear));
+    wide.set_seed(7);
+    wide.reset_random();
+    wide.reset_random();
+    assert_eq!(a.get_cells(), wide.get_cells());
+    assert_eq!(a.get_cells(), b.get_cells());
+    assert_ne!(a.get_cells(), soup(7).get_cells());
+
+    a.set_birth_probability(3, 0.5).unwrap();
+    wide.set_birth_probability(3, 0.5).unwrap();
+    for _ in 0..4 {
+        a.tick();
+        wide.tick();
+    }
+    assert_eq!(a.get_cells(), wide.get_cells());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+pub fn test_noise_stream_survives_resizes() {
+    let noisy = || {
+        let mut universe = Universe::with_size(24, 24, Some(FillMode::Clear));
+        universe.set_seed(11);
+        universe.reset_random();
+        universe.set_birth_probability(3, 0.9).unwrap();
+        universe.set_survival_probability(2, 0.9).unwrap();
+        universe.tick_n(5);
+        universe
+    };
+    // Growing and shrinking back moves every buffer to a new board and
+    // then back, keeping the cells, so only a lost stream tells the two
+    // apart.
+    let mut still = noisy();
+    let mut resized = noisy();
+    for mode in [ResizeMode::PreserveTopLeft, ResizeMode::PreserveCentered] {
+        resized.resize(40, 30, mode);
+        resized.resize(24, 24, mode);
+        assert_eq!(resized.get_cells(), still.get_cells());
+        assert_eq!(resized.generation(), still.generation());
+        still.tick_n(5);
+        resized.tick_n(5);
+        assert_eq!(resized.get_cells(), still.get_cells());
+    }
+
+    // Restarting the stream instead would have given another run.
+    let mut restarted = noisy();
+    restarted.set_noise_seed(11);
+    restarted.tick_n(10);
+    assert_ne!(restarted.get_cells(), still.get_cells());
+}